@@ -15,11 +15,22 @@ self.addEventListener('install', function (e) {
   );
 });
 
-/* Serve cached content when offline */
+/* Serve cached content when offline, fonts fetched by the app are cached when first loaded */
 self.addEventListener('fetch', function (e) {
   e.respondWith(
     caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
+      if (response) {
+        return response;
+      }
+      return fetch(e.request).then(function (response) {
+        if (response.ok && new URL(e.request.url).pathname.includes('/fonts/')) {
+          var copy = response.clone();
+          caches.open(cacheName).then(function (cache) {
+            cache.put(e.request, copy);
+          });
+        }
+        return response;
+      });
     })
   );
 });
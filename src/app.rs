@@ -1,16 +1,59 @@
+mod animation_editor;
+mod assets_panel;
+mod code_editor;
+mod dock;
+mod font_editor;
+mod hierarchy;
+mod input_editor;
+mod inspector;
+mod keymap;
 pub mod logwidget;
-mod style;
+mod mixer;
+mod palette;
+mod preferences;
+mod profiler;
+mod projects;
+mod stats_overlay;
+pub(crate) mod style;
+mod theme;
+mod tile_editor;
+mod viewport;
 
-use egui::{Layout, Pos2, Color32, Stroke};
+use egui_dock::{DockArea, DockState, NodeIndex, SurfaceIndex};
 use log::{debug, error, info, warn};
 
+use std::collections::BTreeMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
 use std::sync::mpsc;
+use dock::{Layout, Panel};
+use keymap::{Command, Keymap, KEYMAP_KEY};
+use palette::{AssetKind, Choice, Item, Palette, PALETTE_KEY};
 use style::*;
+use preferences::{Preferences, PREFERENCES_KEY};
+use projects::ZIP_EXTENSION;
+use theme::{Theme, THEME_EXTENSION};
 
-pub const LOREM_IPSUM: &str = "Lorem 😏😏😏😏ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::audio::Audio;
+use crate::engine::import::{self, ImportError};
+use crate::engine::input::{Gamepads, InputFrame};
+use crate::engine::pack::{self, PACK_EXTENSION};
+use crate::engine::prefab;
+use crate::engine::profiler::Stats;
+use crate::engine::project::{Project, PROJECT_FILE};
+use crate::engine::runtime::Runtime;
+use crate::engine::scene::{EntityId, Scene};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::engine::vfs::NativeDir;
+use crate::engine::vfs::{self, FileSystem, Vfs, VfsError};
+use crate::engine::watch;
 
 type LoadedFile = (String, Vec<u8>);
 
+/// Where the project folder or storage is mounted.
+pub const PROJECT_MOUNT: &str = "project";
+
 fn open_file(sender: mpsc::Sender<LoadedFile>) {
     let future = async move {
         let file = rfd::AsyncFileDialog::new().pick_file().await;
@@ -33,15 +76,97 @@ fn open_file(sender: mpsc::Sender<LoadedFile>) {
     }
 }
 
+/// Reads files of the VFS and sends them by their path inside the mount.
+async fn read_files(vfs: Vfs, paths: Vec<String>, sender: mpsc::Sender<(String, Vec<u8>)>) {
+    for path in paths {
+        match vfs.read(&path).await {
+            Ok(data) => {
+                let name = path.split_once('/').map_or(path.as_str(), |(_, name)| name);
+                sender
+                    .send((name.to_owned(), data))
+                    .expect("File loading channel unexpectedly closed.");
+            }
+            // Removed since it was listed or changed.
+            Err(VfsError::NotFound(_)) => {}
+            Err(e) => error!("Failed to read {path}: {e}"),
+        }
+    }
+}
+
+/// Saves a file where the user picks, or downloads it on the web.
+async fn save_picked(file_name: String, data: Vec<u8>) {
+    let file = rfd::AsyncFileDialog::new()
+        .set_file_name(&file_name)
+        .save_file()
+        .await;
+    if let Some(file) = file {
+        match file.write(&data).await {
+            Ok(()) => info!("Saved {file_name}."),
+            Err(e) => error!("Failed to save {file_name}: {e}"),
+        }
+    }
+}
+
+fn save_file(file_name: String, data: Vec<u8>) {
+    let future = save_picked(file_name, data);
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen_futures::spawn_local;
+        spawn_local(future);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use futures::executor::block_on;
+        block_on(future);
+    }
+}
+
 pub struct App {
-    // Example stuff:
-    label: String,
-    value: f32,
-    show_inspector: bool,
-    show_console: bool,
-    avg_frametime: f32,
+    project: Project,
+    scene: Scene,
+    /// The file of the scene in the project, once it was opened or saved.
+    scene_file: Option<String>,
+    /// The game running in play mode, on a copy of `scene`.
+    runtime: Option<Runtime>,
+    audio: Audio,
+    gamepads: Gamepads,
+    assets: Assets,
+    selected: Option<EntityId>,
+    viewport: viewport::Viewport,
+    animation_editor: animation_editor::AnimationEditor,
+    tile_editor: tile_editor::TileEditor,
+    code_editor: code_editor::CodeEditor,
+    input_editor: input_editor::InputEditor,
+    layout: Layout,
+    themes: theme::Themes,
+    theme_editor: theme::ThemeEditor,
+    font_editor: font_editor::FontEditor,
+    editor_fonts: EditorFonts,
+    preferences: Preferences,
+    preferences_open: bool,
+    keymap: Keymap,
+    keymap_editor: keymap::KeymapEditor,
+    palette: Palette,
+    profiler: profiler::ProfilerPanel,
     file_load_rx: mpsc::Receiver<LoadedFile>,
     file_load_tx: mpsc::Sender<LoadedFile>,
+    /// The project, once one is mounted, and other storages.
+    vfs: Vfs,
+    /// Files read from the project.
+    project_load_rx: mpsc::Receiver<(String, Vec<u8>)>,
+    project_load_tx: mpsc::Sender<(String, Vec<u8>)>,
+    /// The scenes of the game being exported, once they are read from the project.
+    game_scenes_rx: mpsc::Receiver<Vec<(String, Scene)>>,
+    game_scenes_tx: mpsc::Sender<Vec<(String, Scene)>>,
+    /// Changed files of the project, to import them again.
+    project_watch: Option<vfs::Watch>,
+    /// The projects kept in the browser.
+    #[cfg(target_arch = "wasm32")]
+    projects: projects::ProjectList,
+    #[cfg(not(target_arch = "wasm32"))]
+    hot_reload: bool,
+    /// Play the scene once the files opened on startup are loaded.
+    play_on_start: bool,
     log_widget: logwidget::MyLogger,
 }
 
@@ -50,19 +175,60 @@ impl App {
     pub fn new(cc: &eframe::CreationContext<'_>, log_widget: logwidget::MyLogger) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
-        load_fonts(cc);
-        cc.egui_ctx.set_style(style::style_dark());
+        let editor_fonts = EditorFonts::default();
+        cc.egui_ctx.set_fonts(editor_fonts.definitions());
+        cc.egui_ctx.set_style(style::base_style());
 
         let (tx, rx) = mpsc::channel();
+        let (project_tx, project_rx) = mpsc::channel();
+        let (game_scenes_tx, game_scenes_rx) = mpsc::channel();
         let s = Self {
-            avg_frametime: 0.016666,
-            // Example stuff:
-            label: "Hello World!".to_owned(),
-            value: 2.7,
-            show_inspector: false,
-            show_console: false,
+            project: Project::default(),
+            scene: Scene::default(),
+            scene_file: None,
+            runtime: None,
+            audio: Audio::null(),
+            gamepads: Gamepads::new(),
+            assets: Assets::default(),
+            selected: None,
+            viewport: Default::default(),
+            animation_editor: Default::default(),
+            tile_editor: Default::default(),
+            code_editor: Default::default(),
+            input_editor: Default::default(),
+            layout: Layout::default(),
+            themes: Default::default(),
+            theme_editor: Default::default(),
+            font_editor: Default::default(),
+            editor_fonts,
+            preferences: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, PREFERENCES_KEY))
+                .unwrap_or_default(),
+            preferences_open: false,
+            keymap: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, KEYMAP_KEY))
+                .unwrap_or_default(),
+            keymap_editor: Default::default(),
+            palette: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, PALETTE_KEY))
+                .unwrap_or_default(),
+            profiler: Default::default(),
             file_load_rx: rx,
             file_load_tx: tx,
+            vfs: Vfs::default(),
+            project_load_rx: project_rx,
+            project_load_tx: project_tx,
+            game_scenes_rx,
+            game_scenes_tx,
+            project_watch: None,
+            #[cfg(target_arch = "wasm32")]
+            projects: projects::ProjectList::new(&cc.egui_ctx),
+            #[cfg(not(target_arch = "wasm32"))]
+            hot_reload: true,
+            play_on_start: false,
             log_widget,
         };
         debug!("This is a debug message.");
@@ -71,23 +237,736 @@ impl App {
         error!("This is an error message!");
         s
     }
+
+    /// Opens a file given on the command line, as if the user picked it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(&self, name: String, path: PathBuf) -> std::io::Result<()> {
+        let data = std::fs::read(&path)?;
+        self.file_load_tx
+            .send((name, data))
+            .expect("File loading channel unexpectedly closed.");
+        Ok(())
+    }
+
+    /// Opens the project in `fs`: loads the project file, the first scene and the assets, and
+    /// saves into it from then on.
+    pub fn mount_project(&mut self, ctx: &egui::Context, fs: impl FileSystem + 'static) {
+        self.project = Project::default();
+        self.scene = Scene::default();
+        self.scene_file = None;
+        self.selected = None;
+        self.runtime = None;
+        self.assets = Assets::default();
+        // Saving must not write the scripts of the last project into this one.
+        self.code_editor.close_all();
+        self.viewport.reset_stats();
+        self.install_fonts(ctx);
+        self.vfs.mount(PROJECT_MOUNT, fs);
+        let wake = ctx.clone();
+        self.project_watch = self
+            .vfs
+            .watch(PROJECT_MOUNT, move || wake.request_repaint())
+            .map_err(|e| warn!("Changed files won't be reloaded: {e}"))
+            .ok();
+        let (vfs, sender, ctx) = (self.vfs.clone(), self.project_load_tx.clone(), ctx.clone());
+        vfs::spawn(async move {
+            let project_file = format!("{PROJECT_MOUNT}/{PROJECT_FILE}");
+            match vfs.list(PROJECT_MOUNT).await {
+                Ok(files) => {
+                    let scene = files.iter().find(|p| p.ends_with(".scene.json")).cloned();
+                    let mut files: Vec<String> = files
+                        .into_iter()
+                        .filter(|p| *p == project_file || import::is_importable(p))
+                        .collect();
+                    files.extend(scene);
+                    read_files(vfs, files, sender).await;
+                }
+                Err(e) => error!("Failed to open the project: {e}"),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Saves a file into the project, or where the user picks if there is no project.
+    fn save_to_project(&self, name: String, data: Vec<u8>) {
+        if !self.vfs.is_mounted(PROJECT_MOUNT) {
+            save_file(name, data);
+            return;
+        }
+        let vfs = self.vfs.clone();
+        vfs::spawn(async move {
+            match vfs.write(&format!("{PROJECT_MOUNT}/{name}"), data).await {
+                Ok(()) => info!("Saved {name}."),
+                Err(e) => error!("Failed to save {name}: {e}"),
+            }
+        });
+    }
+
+    /// The file of a prefab, clip or tileset as edited, `None` for other assets.
+    fn asset_file(&self, asset: &AssetRef) -> Option<Vec<u8>> {
+        let assets = &self.assets;
+        let text = if let Some(prefab) = assets.prefabs.get(asset) {
+            prefab.save()
+        } else if let Some(clip) = assets.clips.get(asset) {
+            clip.save()
+        } else {
+            assets.tilesets.get(asset)?.save()
+        };
+        Some(text.into_bytes())
+    }
+
+    /// Saves a prefab, clip or tileset made or edited in the editor into the project.
+    fn save_asset(&self, asset: &AssetRef) {
+        if let Some(data) = self.asset_file(asset) {
+            self.save_to_project(asset.to_string(), data);
+        }
+    }
+
+    /// The prefabs, clips and tilesets, which may have been made or edited in the editor.
+    fn edited_assets(&self) -> Vec<AssetRef> {
+        let assets = &self.assets;
+        let prefabs = assets.prefabs.keys();
+        prefabs
+            .chain(assets.clips.keys())
+            .chain(assets.tilesets.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// Lets the user pick a project: on the web one kept in the browser, natively a folder.
+    fn open_project(&mut self, _ctx: &egui::Context) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.projects.open = true;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let folder = futures::executor::block_on(rfd::AsyncFileDialog::new().pick_folder());
+            if let Some(folder) = folder {
+                self.mount_project(_ctx, NativeDir::new(folder.path()));
+            }
+        }
+    }
+
+    /// Opens a project zip file: on the web as a new project in the browser, natively unpacked
+    /// into a folder the user picks.
+    fn open_zip(&mut self, _ctx: &egui::Context, name: &str, data: &[u8]) {
+        let files = match projects::unzip_files(data) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("Failed to open {name}: {e}");
+                return;
+            }
+        };
+        let _name = name.trim_end_matches(ZIP_EXTENSION);
+        #[cfg(target_arch = "wasm32")]
+        self.projects.create(_ctx, _name, files);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let dialog = rfd::AsyncFileDialog::new().set_title(format!("Unpack {_name} into"));
+            let Some(folder) = futures::executor::block_on(dialog.pick_folder()) else {
+                return;
+            };
+            // Unpacking would overwrite the files of whatever is in there.
+            let empty = std::fs::read_dir(folder.path()).map(|mut e| e.next().is_none());
+            if !matches!(empty, Ok(true)) {
+                error!(
+                    "Can't unpack {name} into {}, pick an empty folder.",
+                    folder.path().display()
+                );
+                return;
+            }
+            let dir = NativeDir::new(folder.path());
+            let unpacked = futures::executor::block_on(async {
+                for (path, data) in files {
+                    dir.write(&path, data).await?;
+                }
+                Ok::<(), VfsError>(())
+            });
+            match unpacked {
+                Ok(()) => self.mount_project(_ctx, dir),
+                Err(e) => error!("Failed to unpack {name}: {e}"),
+            }
+        }
+    }
+
+    /// The file of the open scene in the project, named after the scene if it has none yet.
+    fn scene_path(&mut self) -> String {
+        let name = &self.scene.name;
+        let path = self
+            .scene_file
+            .get_or_insert_with(|| format!("{name}.scene.json"));
+        path.clone()
+    }
+
+    /// The files of the project as they are in the editor: the project file, the open scene and
+    /// the imported assets, with the scripts, prefabs, clips and tilesets as edited.
+    fn project_files(&mut self) -> BTreeMap<String, Vec<u8>> {
+        self.code_editor.save_all(&mut self.assets);
+        self.project.layout = Some(self.layout.save());
+        let assets = &self.assets;
+        let mut files: BTreeMap<String, Vec<u8>> = assets
+            .sources
+            .iter()
+            .map(|(asset, data)| (asset.to_string(), data.to_vec()))
+            .collect();
+        let scripts = assets.scripts.iter();
+        files.extend(scripts.map(|(asset, text)| (asset.to_string(), text.as_bytes().to_vec())));
+        for asset in self.edited_assets() {
+            if let Some(data) = self.asset_file(&asset) {
+                files.insert(asset.to_string(), data);
+            }
+        }
+        files.insert(PROJECT_FILE.to_owned(), self.project.save().into_bytes());
+        files.insert(self.scene_path(), self.scene.save().into_bytes());
+        files
+    }
+
+    /// Saves the whole project as a zip file, with the files only the project storage has,
+    /// like the other scenes.
+    fn export_project(&mut self) {
+        let files = self.project_files();
+        let file_name = format!("{}{ZIP_EXTENSION}", self.project.name);
+        let vfs = self.vfs.clone();
+        vfs::spawn(async move {
+            let mut all = BTreeMap::new();
+            if vfs.is_mounted(PROJECT_MOUNT) {
+                let (sender, stored) = mpsc::channel();
+                match vfs.list(PROJECT_MOUNT).await {
+                    Ok(paths) => read_files(vfs, paths, sender).await,
+                    Err(e) => error!("Failed to list the project files: {e}"),
+                }
+                all.extend(stored.try_iter());
+            }
+            all.extend(files);
+            match projects::zip_files(&all) {
+                Ok(data) => save_picked(file_name, data).await,
+                Err(e) => error!("Failed to zip the project: {e}"),
+            }
+        });
+    }
+
+    /// Copies a file imported from outside into the project, so it stays with the project.
+    fn copy_to_project(&self, name: &str, data: &[u8]) {
+        if !self.vfs.is_mounted(PROJECT_MOUNT) || !import::is_importable(name) {
+            return;
+        }
+        let (vfs, data) = (self.vfs.clone(), data.to_vec());
+        let path = format!("{PROJECT_MOUNT}/{name}");
+        vfs::spawn(async move {
+            if let Err(e) = vfs.write(&path, data).await {
+                error!("Failed to copy {path} into the project: {e}");
+            }
+        });
+    }
+
+    /// Starts playing the scene on the first frame, after the opened files are loaded.
+    pub fn play_on_start(&mut self) {
+        self.play_on_start = true;
+    }
+
+    /// Handles a file picked or dropped by the user, based on its extension.
+    fn load_file(&mut self, ctx: &egui::Context, name: &str, data: &[u8]) {
+        if name.rsplit('/').next() == Some(PROJECT_FILE) {
+            match Project::load(&String::from_utf8_lossy(data)) {
+                Ok(project) => {
+                    info!("Loaded project '{}'.", project.name);
+                    if let Some(layout) = &project.layout {
+                        match Layout::load(layout) {
+                            Ok(layout) => self.layout = layout,
+                            Err(e) => warn!("Failed to load the panel layout: {e}"),
+                        }
+                    }
+                    self.project = project;
+                    self.install_fonts(ctx);
+                }
+                Err(e) => error!("Failed to load project {name}: {e}"),
+            }
+        } else if name.ends_with(THEME_EXTENSION) {
+            match Theme::load(&String::from_utf8_lossy(data)) {
+                Ok(theme) => {
+                    info!("Loaded theme '{}'.", theme.name);
+                    self.themes.insert(theme);
+                }
+                Err(e) => error!("Failed to load theme {name}: {e}"),
+            }
+        } else if name.ends_with(".scene.json") {
+            match Scene::load(&String::from_utf8_lossy(data)) {
+                Ok(scene) => {
+                    info!("Loaded scene '{}' from {name}.", scene.name);
+                    self.scene = scene;
+                    self.scene_file = Some(name.to_owned());
+                    self.selected = None;
+                }
+                Err(e) => error!("Failed to load scene {name}: {e}"),
+            }
+        } else if name.ends_with(ZIP_EXTENSION) {
+            self.open_zip(ctx, name, data);
+        } else if import::is_importable(name) {
+            match self.import(ctx, name, data) {
+                Ok(()) => info!("Imported {name}."),
+                Err(e) => error!("Failed to import {name}: {e}"),
+            }
+        } else {
+            warn!("Don't know how to open {name}.");
+        }
+    }
+
+    /// Imports an asset, replacing the old version in the scene and the editors.
+    fn import(&mut self, ctx: &egui::Context, name: &str, data: &[u8]) -> Result<(), ImportError> {
+        import::import(ctx, &mut self.assets, name, data)?;
+        let asset = AssetRef::new(name);
+        if let Some(p) = self.assets.prefabs.get(&asset) {
+            prefab::sync_instances(&mut self.scene, &asset, p);
+        }
+        self.code_editor.reloaded(&self.assets, &asset);
+        if self.assets.fonts.contains_key(&asset) {
+            self.install_fonts(ctx);
+        }
+        Ok(())
+    }
+
+    /// Installs the editor fonts together with the project fonts.
+    fn install_fonts(&self, ctx: &egui::Context) {
+        let mut fonts = self.editor_fonts.definitions();
+        self.project.fonts.install(&self.assets, &mut fonts);
+        ctx.set_fonts(fonts);
+    }
+
+    /// Imports the files of the project which changed again.
+    fn reload_project(&mut self, ctx: &egui::Context) {
+        let Some(watch) = &mut self.project_watch else {
+            return;
+        };
+        let changed = watch.poll();
+        if watch.pending() {
+            ctx.request_repaint_after(watch::SETTLE_TIME);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.hot_reload {
+            return;
+        }
+        let changed: Vec<String> = changed
+            .into_iter()
+            .filter(|p| import::is_importable(p))
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        let (vfs, sender, ctx) = (self.vfs.clone(), self.project_load_tx.clone(), ctx.clone());
+        vfs::spawn(async move {
+            read_files(vfs, changed, sender).await;
+            ctx.request_repaint();
+        });
+    }
+
+    /// Contents of a docked or floating panel.
+    fn panel_ui(&mut self, ui: &mut egui::Ui, panel: Panel) {
+        let scene = match &mut self.runtime {
+            Some(runtime) => &mut runtime.scene,
+            None => &mut self.scene,
+        };
+        crate::profile_scope!(panel.title());
+        match panel {
+            Panel::Viewport => {
+                let overlay = self.preferences.stats_overlay;
+                let overlay = overlay.then_some(self.preferences.stats_corner);
+                let changed = self.viewport.ui(
+                    ui,
+                    scene,
+                    &self.assets,
+                    &mut self.selected,
+                    &mut self.tile_editor,
+                    overlay,
+                );
+                if let Some(id) = self.selected.filter(|_| changed) {
+                    self.entity_changed(id);
+                }
+            }
+            Panel::Hierarchy => {
+                hierarchy::hierarchy_ui(ui, scene, &self.assets, &mut self.selected);
+            }
+            Panel::Inspector => {
+                let saved = egui::ScrollArea::vertical().show(ui, |ui| {
+                    inspector::inspector_ui(ui, scene, &mut self.assets, self.selected)
+                });
+                if let Some(asset) = saved.inner {
+                    self.save_asset(&asset);
+                }
+            }
+            Panel::Assets => {
+                if ui.button("Import…").clicked() {
+                    open_file(self.file_load_tx.clone());
+                }
+                let selected = self.selected.and_then(|id| scene.find_mut(id));
+                let mut edit = None;
+                let audio = &mut self.audio;
+                if assets_panel::assets_ui(ui, &self.assets, selected, audio, &mut edit) {
+                    if let Some(id) = self.selected {
+                        self.entity_changed(id);
+                    }
+                }
+                if let Some(script) = edit {
+                    self.code_editor.open_script(&self.assets, &script);
+                }
+            }
+            Panel::Console => {
+                ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
+                    self.log_widget.show_log(ui)
+                });
+            }
+            Panel::Mixer => mixer::mixer_ui(ui, &mut self.audio),
+            Panel::Profiler => self.profiler.ui(ui),
+        }
+    }
+
+    fn toggle_play(&mut self) {
+        if self.runtime.take().is_some() {
+            info!("Stopped playing.");
+        } else {
+            info!("Playing scene '{}'.", self.scene.name);
+            // Playing is started by the user, which browsers require for sound.
+            self.audio.start_device();
+            self.runtime = Some(Runtime::new(
+                self.scene.clone(),
+                &self.project,
+                self.audio.mixer.clone(),
+            ));
+        }
+    }
+
+    /// Cooks the project, the open scene and the assets it uses into a pack, and saves it.
+    fn export_game(&mut self, ctx: &egui::Context) {
+        self.code_editor.save_all(&mut self.assets);
+        // The game starts with the open scene, as when playing it in the editor.
+        let open = (self.scene_path(), self.scene.clone());
+        if !self.vfs.is_mounted(PROJECT_MOUNT) {
+            self.cook_game(vec![open]);
+            return;
+        }
+        let (vfs, sender, ctx) = (self.vfs.clone(), self.game_scenes_tx.clone(), ctx.clone());
+        vfs::spawn(async move {
+            let mut scenes = vec![];
+            let paths = match vfs.list(PROJECT_MOUNT).await {
+                Ok(paths) => paths,
+                Err(e) => {
+                    error!("Failed to list the scenes of the project: {e}");
+                    return;
+                }
+            };
+            for path in paths.iter().filter(|p| p.ends_with(".scene.json")) {
+                let name = path.split_once('/').map_or(path.as_str(), |(_, name)| name);
+                if name == open.0 {
+                    continue;
+                }
+                let scene = match vfs.read(path).await {
+                    Ok(data) => {
+                        Scene::load(&String::from_utf8_lossy(&data)).map_err(|e| e.to_string())
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+                match scene {
+                    Ok(scene) => scenes.push((name.to_owned(), scene)),
+                    Err(e) => {
+                        error!("Failed to export the game, {path} doesn't load: {e}");
+                        return;
+                    }
+                }
+            }
+            scenes.insert(0, open);
+            let _ = sender.send(scenes);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Cooks the scenes of the game and the assets they use into a pack, which the user saves.
+    fn cook_game(&self, scenes: Vec<(String, Scene)>) {
+        match pack::cook(&self.project, &scenes, &self.assets) {
+            Ok((data, report)) => {
+                report.log();
+                save_file(format!("{}{PACK_EXTENSION}", self.project.name), data);
+            }
+            Err(e) => error!("Failed to export the game: {e}"),
+        }
+    }
+
+    /// Keeps the prefab overrides up to date after an entity was edited outside the inspector.
+    fn entity_changed(&mut self, id: EntityId) {
+        let asset = self
+            .scene
+            .find(id)
+            .and_then(|e| e.prefab.as_ref())
+            .map(|l| l.asset.clone());
+        if let Some(p) = asset.and_then(|a| self.assets.prefabs.get(&a)) {
+            prefab::update_overrides(&mut self.scene, id, p);
+        }
+    }
+}
+
+/// Shows the panels of the app as tabs of the dock.
+struct PanelTabs<'a> {
+    app: &'a mut App,
+    /// Panel to move into its own window once the dock is drawn.
+    float: Option<Panel>,
+}
+
+impl egui_dock::TabViewer for PanelTabs<'_> {
+    type Tab = Panel;
+
+    fn title(&mut self, panel: &mut Panel) -> egui::WidgetText {
+        panel.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, panel: &mut Panel) {
+        self.app.panel_ui(ui, *panel);
+    }
+
+    fn context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        panel: &mut Panel,
+        _surface: SurfaceIndex,
+        _node: NodeIndex,
+    ) {
+        if ui.button("🗗 Open in window").clicked() {
+            self.float = Some(*panel);
+            ui.close_menu();
+        }
+    }
+
+    fn clear_background(&self, panel: &Panel) -> bool {
+        *panel != Panel::Viewport
+    }
 }
 
 impl eframe::App for App {
+    /// Called by the framework to save state before shutdown, and every now and then.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, PREFERENCES_KEY, &self.preferences);
+        eframe::set_value(storage, KEYMAP_KEY, &self.keymap);
+        eframe::set_value(storage, PALETTE_KEY, &self.palette);
+    }
+
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        if let Ok((name, _data)) = self.file_load_rx.try_recv() {
-            log::info!("{name}");
+        crate::engine::profiler::begin_frame();
+        self.update_frame(ctx, frame);
+        crate::engine::profiler::end_frame();
+    }
+}
+
+impl App {
+    /// One frame of the editor, recorded by the profiler.
+    fn update_frame(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let system_dark = frame.info().system_theme.map(|t| t == eframe::Theme::Dark);
+        let mut restyle = self.themes.update(ctx, system_dark);
+        restyle |= preferences::preferences_window(
+            ctx,
+            &mut self.preferences_open,
+            &mut self.preferences,
+            &self.keymap,
+        );
+        if restyle {
+            if let Some(theme) = self.themes.active(system_dark) {
+                self.preferences.apply(ctx, theme);
+            }
+        }
+        self.preferences.sync_zoom(ctx);
+        if self.editor_fonts.poll(ctx) {
+            self.install_fonts(ctx);
+        }
+        self.load_files(ctx);
+        if std::mem::take(&mut self.play_on_start) && self.runtime.is_none() {
+            self.toggle_play();
+        }
+        self.update_game(ctx);
+
+        {
+            crate::profile_scope!("Top bar");
+            self.top_bar(ctx);
+        }
+        {
+            crate::profile_scope!("Panels");
+            self.panels(ctx);
+        }
+        {
+            crate::profile_scope!("Windows");
+            self.windows(ctx, system_dark);
+        }
+        for command in self.keymap.pressed(ctx) {
+            self.run_command(ctx, command);
+        }
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: Command) {
+        if let Some(panel) = command.panel() {
+            self.layout.toggle(panel);
+            return;
+        }
+        match command {
+            Command::NewScene => {
+                self.scene = Scene::default();
+                self.scene_file = None;
+                self.selected = None;
+            }
+            Command::Open => open_file(self.file_load_tx.clone()),
+            Command::OpenProject => self.open_project(ctx),
+            Command::SaveScene => {
+                let name = self.scene_path();
+                self.save_to_project(name, self.scene.save().into_bytes());
+            }
+            Command::SaveProject => {
+                let saved = self.code_editor.save_all(&mut self.assets);
+                self.project.layout = Some(self.layout.save());
+                self.save_to_project(PROJECT_FILE.to_owned(), self.project.save().into_bytes());
+                // The edited assets only live in the editor without a project to save them into.
+                if self.vfs.is_mounted(PROJECT_MOUNT) {
+                    for asset in saved {
+                        if let Some(source) = self.assets.scripts.get(&asset) {
+                            self.save_to_project(asset.to_string(), source.as_bytes().to_vec());
+                        }
+                    }
+                    for asset in self.edited_assets() {
+                        self.save_asset(&asset);
+                    }
+                }
+            }
+            Command::ExportProject => self.export_project(),
+            Command::ExportGame => self.export_game(ctx),
+            Command::Preferences => self.preferences_open = true,
+            Command::Keymap => self.keymap_editor.open = true,
+            Command::Palette => self.palette.toggle(),
+            Command::RenameScene | Command::AddEntity => self.palette.prompt(command),
+            Command::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            Command::Play => self.toggle_play(),
+            Command::AnimationEditor => toggle(&mut self.animation_editor.open),
+            Command::TileEditor => toggle(&mut self.tile_editor.open),
+            Command::CodeEditor => toggle(&mut self.code_editor.open),
+            Command::InputEditor => toggle(&mut self.input_editor.open),
+            Command::ThemeEditor => toggle(&mut self.theme_editor.open),
+            Command::FontEditor => toggle(&mut self.font_editor.open),
+            Command::Colliders => toggle(&mut self.viewport.show_colliders),
+            Command::Stats => toggle(&mut self.preferences.stats_overlay),
+            Command::Viewport
+            | Command::Hierarchy
+            | Command::Inspector
+            | Command::Assets
+            | Command::Console
+            | Command::Mixer
+            | Command::Profiler => unreachable!("panels are handled above"),
         }
+    }
+
+    /// Runs what the user picked in the command palette.
+    fn run_choice(&mut self, ctx: &egui::Context, choice: Choice) {
+        let scene = match &mut self.runtime {
+            Some(runtime) => &mut runtime.scene,
+            None => &mut self.scene,
+        };
+        match choice {
+            Choice::Item(Item::Command(command)) => self.run_command(ctx, command),
+            Choice::Item(Item::Asset(AssetKind::Script, asset)) => {
+                self.code_editor.open_script(&self.assets, &asset);
+            }
+            Choice::Item(Item::Asset(AssetKind::Clip, asset)) => {
+                self.animation_editor.open_clip(&asset);
+            }
+            Choice::Item(Item::Asset(AssetKind::Prefab, asset)) => {
+                if let Some(p) = self.assets.prefabs.get(&asset) {
+                    self.selected = Some(prefab::instantiate(scene, &asset, p, None));
+                }
+            }
+            Choice::Item(Item::Asset(..)) => self.layout.open(Panel::Assets),
+            Choice::Item(Item::Entity(id, _)) => {
+                self.selected = Some(id);
+                self.layout.open(Panel::Inspector);
+            }
+            Choice::Argument(Command::RenameScene, name) => scene.name = name,
+            Choice::Argument(Command::AddEntity, name) => {
+                self.selected = Some(scene.spawn(name, None));
+            }
+            Choice::Argument(command, _) => unreachable!("{command:?} takes no argument"),
+        }
+    }
+
+    /// Loads picked, dropped and changed files, and the files of the project.
+    fn load_files(&mut self, ctx: &egui::Context) {
+        crate::profile_scope!("Load files");
+        while let Ok((name, data)) = self.project_load_rx.try_recv() {
+            self.load_file(ctx, &name, &data);
+        }
+        while let Ok(scenes) = self.game_scenes_rx.try_recv() {
+            self.cook_game(scenes);
+        }
+        while let Ok((name, data)) = self.file_load_rx.try_recv() {
+            self.load_file(ctx, &name, &data);
+            self.copy_to_project(&name, &data);
+        }
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            if let Some(bytes) = file.bytes {
+                self.load_file(ctx, &file.name, &bytes);
+                self.copy_to_project(&file.name, &bytes);
+                continue;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = file.path {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                match std::fs::read(&path) {
+                    Ok(data) => {
+                        self.load_file(ctx, &name, &data);
+                        self.copy_to_project(&name, &data);
+                    }
+                    Err(e) => error!("Failed to read {}: {e}", path.display()),
+                }
+            }
+        }
+        self.reload_project(ctx);
+    }
+
+    /// Advances the game in play mode.
+    fn update_game(&mut self, ctx: &egui::Context) {
+        crate::profile_scope!("Game");
+        self.audio.update(ctx.input(|i| i.stable_dt));
+        if let Some(runtime) = &mut self.runtime {
+            // Long frames (e.g. a dragged window) must not make the game jump.
+            let dt = ctx.input(|i| i.stable_dt).min(0.1);
+            // The game gets no keys while a text field has the focus, and no mouse outside of it.
+            let mut input = InputFrame::capture(ctx, !ctx.wants_keyboard_input(), self.viewport.hovered);
+            self.gamepads.poll(&mut input);
+            runtime.input.update(input);
+            runtime.update(&mut self.assets, dt);
+            ctx.request_repaint();
+        }
+    }
+
+    fn top_bar(&mut self, ctx: &egui::Context) {
+        let mut run = None;
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.with_layout(Layout::left_to_right(egui::Align::Min), |ui| {
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
                 // The top panel is often a good place for a menu bar:
                 egui::menu::bar(ui, |ui| {
                     ui.menu_button("File", |ui| {
-                        if ui.button("Open file…").clicked() {
-                            open_file(self.file_load_tx.clone());
-                        };
+                        for command in [
+                            Command::NewScene,
+                            Command::Open,
+                            Command::OpenProject,
+                            Command::SaveScene,
+                            Command::SaveProject,
+                            Command::ExportProject,
+                            Command::ExportGame,
+                            Command::Preferences,
+                            Command::Keymap,
+                            Command::Palette,
+                        ] {
+                            if self.keymap.button(ui, command).clicked() {
+                                run = Some(command);
+                                ui.close_menu();
+                            }
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        ui.checkbox(&mut self.hot_reload, "♻ Reload changed files")
+                            .on_hover_text("Import files again when they change on disk.");
 
                         ui.hyperlink_to(
                             "Open Source Code",
@@ -95,94 +974,125 @@ impl eframe::App for App {
                         );
 
                         #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
-                        if ui.button("Quit").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        if self.keymap.button(ui, Command::Quit).clicked() {
+                            run = Some(Command::Quit);
                         }
                     });
+                    ui.menu_button("Window", |ui| self.layout.menu_ui(ui, &self.keymap));
                     ui.separator();
-                    ui.toggle_value(&mut self.show_inspector, "🪛 Inspector");
-                    ui.toggle_value(&mut self.show_console, "🖹 Console");
+                    for (open, command) in [
+                        (&mut self.animation_editor.open, Command::AnimationEditor),
+                        (&mut self.tile_editor.open, Command::TileEditor),
+                        (&mut self.code_editor.open, Command::CodeEditor),
+                        (&mut self.viewport.show_colliders, Command::Colliders),
+                        (&mut self.input_editor.open, Command::InputEditor),
+                        (&mut self.theme_editor.open, Command::ThemeEditor),
+                        (&mut self.font_editor.open, Command::FontEditor),
+                    ] {
+                        let response = ui.toggle_value(open, command.title());
+                        self.keymap.on_hover(response, command);
+                    }
+                    ui.separator();
+                    let playing = self.runtime.is_some();
+                    let response =
+                        ui.selectable_label(playing, if playing { "⏹ Stop" } else { "▶ Play" });
+                    if self.keymap.on_hover(response, Command::Play).clicked() {
+                        run = Some(Command::Play);
+                    }
                 });
-                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     egui::warn_if_debug_build(ui);
                     ui.label(build_time::build_time_local!("Built on %d.%m.%Y, %H:%M."));
                     ui.separator();
-                    if let Some(usage) = frame.info().cpu_usage{
-                        self.avg_frametime = self.avg_frametime*0.9+usage*0.1;
-                        ui.label(format!("Frame time: {:.2}ms", self.avg_frametime*1000.0));
+                    let recent = crate::engine::profiler::with(|p| {
+                        Stats::new(p.frames().iter().rev().take(60))
+                    });
+                    if let Some(stats) = recent {
+                        let open = self.layout.is_open(Panel::Profiler);
+                        if ui
+                            .selectable_label(open, format!("⏱ {:.2} ms", stats.avg * 1000.0))
+                            .on_hover_text("Average frame time of the last 60 frames.")
+                            .clicked()
+                        {
+                            run = Some(Command::Profiler);
+                        }
                     }
                 });
             });
         });
+        if let Some(command) = run {
+            self.run_command(ctx, command);
+        }
+    }
 
-        if self.show_inspector {
-            egui::SidePanel::left("side_panel").show(ctx, |ui| {
-                ui.heading("Side Panel");
-
-                ui.horizontal(|ui| {
-                    ui.label("Write something: ");
-                    ui.text_edit_singleline(&mut self.label);
-                });
-
-                ui.add(egui::Slider::new(&mut self.value, 0.0..=10.0).text("value"));
-                if ui.button("Increment").clicked() {
-                    self.value += 1.0;
-                }
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.label(LOREM_IPSUM.repeat(1));
-                });
-
-                ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                    ui.horizontal(|ui| {
-                        ui.spacing_mut().item_spacing.x = 0.0;
-                        ui.label("powered by ");
-                        ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-                        ui.label(" and ");
-                        ui.hyperlink_to(
-                            "eframe",
-                            "https://github.com/emilk/egui/tree/master/crates/eframe",
-                        );
-                        ui.label(".");
-                    });
-                });
-            });
+    /// The dock and the panels floated into their own windows.
+    fn panels(&mut self, ctx: &egui::Context) {
+        let mut dock = std::mem::replace(&mut self.layout.dock, DockState::new(vec![]));
+        let mut tabs = PanelTabs {
+            app: self,
+            float: None,
+        };
+        DockArea::new(&mut dock)
+            .style(egui_dock::Style::from_egui(ctx.style().as_ref()))
+            .show(ctx, &mut tabs);
+        let float = tabs.float;
+        self.layout.dock = dock;
+        if let Some(panel) = float {
+            self.layout.float(panel);
         }
-        if self.show_console {
-            egui::TopBottomPanel::top("log console")
-                .resizable(true)
-                .show(ctx, |ui| {
-                    ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
-                        self.log_widget.show_log(ui)
-                    });
-                });
+        for panel in self.layout.windows.clone() {
+            if !dock::show_window(ctx, panel, |ui| self.panel_ui(ui, panel)) {
+                self.layout.unfloat(panel);
+            }
         }
+    }
 
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-        egui::Frame::canvas(&ctx.style()).show(ui, |ui|{
-            ui.painter().circle(Pos2::ZERO, 256.0, Color32::YELLOW, Stroke{ width: 2.0, color: Color32::BLUE });
-        });
-
-        ui.heading("Welcome to my life!");
-            ui.hyperlink("https://github.com/emilk/eframe_template");
-            ui.add(egui::github_link_file!(
-                "https://github.com/emilk/eframe_template/blob/master/",
-                "Source code."
-            ));
-
-            ui.heading("Top Heading");
-            ui.label(LOREM_IPSUM);
-            ui.monospace(LOREM_IPSUM);
-            ui.small(LOREM_IPSUM);
-        });
-
-        if false {
-            egui::Window::new("Window").show(ctx, |ui| {
-                ui.label("Windows can be moved by dragging them.");
-                ui.label("They are automatically sized based on contents.");
-                ui.label("You can turn on resizing and scrolling if you like.");
-                ui.label("You would normally choose either panels OR windows.");
-            });
+    fn windows(&mut self, ctx: &egui::Context, system_dark: Option<bool>) {
+        if let Some(clip) = self.animation_editor.show(ctx, &mut self.assets) {
+            self.save_asset(&clip);
+        }
+        self.code_editor.show(ctx, &mut self.assets);
+        self.theme_editor.show(ctx, &mut self.themes, system_dark);
+        self.keymap_editor.show(ctx, &mut self.keymap);
+        let scene = self.runtime.as_ref().map_or(&self.scene, |r| &r.scene);
+        let choice = self.palette.show(ctx, &self.keymap, &self.assets, scene);
+        if let Some(choice) = choice {
+            self.run_choice(ctx, choice);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some((name, files)) = self.projects.show(ctx) {
+            info!("Opened project {name}.");
+            self.mount_project(ctx, files);
+        }
+        let fonts = &mut self.project.fonts;
+        if self
+            .font_editor
+            .show(ctx, &self.assets, fonts, &self.file_load_tx)
+        {
+            self.install_fonts(ctx);
+        }
+        if self.input_editor.show(ctx, &mut self.project.input) {
+            if let Some(runtime) = &mut self.runtime {
+                runtime.input.map = self.project.input.clone();
+            }
+        }
+        let scene = match &mut self.runtime {
+            Some(runtime) => &mut runtime.scene,
+            None => &mut self.scene,
+        };
+        let mut save = None;
+        let assets = &mut self.assets;
+        if self.tile_editor.show(ctx, scene, assets, self.selected, &mut save) {
+            if let Some(id) = self.selected {
+                self.entity_changed(id);
+            }
+        }
+        if let Some(tileset) = save {
+            self.save_asset(&tileset);
         }
     }
 }
+
+fn toggle(value: &mut bool) {
+    *value = !*value;
+}
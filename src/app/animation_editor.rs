@@ -0,0 +1,346 @@
+use egui::{Color32, Context, Image, Sense, Stroke, Ui, Vec2};
+
+use crate::engine::animation::{AnimationClip, AnimationEvent, AnimationFrame, LoopMode};
+use crate::engine::assets::{AssetRef, Assets};
+
+/// Width of one second of animation in the timeline.
+const POINTS_PER_SECOND: f32 = 240.0;
+const THUMBNAIL: f32 = 40.0;
+
+/// Window for editing animation clips, with a timeline and a preview.
+pub struct AnimationEditor {
+    pub open: bool,
+    clip: Option<AssetRef>,
+    new_name: String,
+    selected_frame: usize,
+    preview_time: f32,
+    preview_playing: bool,
+    preview_speed: f32,
+}
+
+impl Default for AnimationEditor {
+    fn default() -> Self {
+        Self {
+            open: false,
+            clip: None,
+            new_name: "new".to_owned(),
+            selected_frame: 0,
+            preview_time: 0.0,
+            preview_playing: false,
+            preview_speed: 1.0,
+        }
+    }
+}
+
+/// Shows the image of an animation frame, or a placeholder if it is not loaded.
+fn frame_image(ui: &mut Ui, assets: &Assets, frame: &AnimationFrame, size: f32) {
+    match assets.image_region(&frame.image, &frame.region) {
+        Some((texture, region)) => {
+            ui.add(
+                Image::new(&texture.handle)
+                    .uv(texture.uv(region))
+                    .fit_to_exact_size(Vec2::splat(size))
+                    .maintain_aspect_ratio(true),
+            );
+        }
+        None => {
+            ui.add_sized(Vec2::splat(size), egui::Label::new("?"));
+        }
+    }
+}
+
+/// Swaps two frames, with the events on them.
+fn swap_frames(clip: &mut AnimationClip, a: usize, b: usize) {
+    clip.frames.swap(a, b);
+    for e in &mut clip.events {
+        if e.frame == a {
+            e.frame = b;
+        } else if e.frame == b {
+            e.frame = a;
+        }
+    }
+}
+
+impl AnimationEditor {
+    /// Opens the editor on a clip.
+    pub fn open_clip(&mut self, asset: &AssetRef) {
+        self.open = true;
+        self.clip = Some(asset.clone());
+        self.selected_frame = 0;
+    }
+
+    /// Returns the clip the user saved.
+    pub fn show(&mut self, ctx: &Context, assets: &mut Assets) -> Option<AssetRef> {
+        let mut open = self.open;
+        let mut save = None;
+        egui::Window::new("🎞 Animation")
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| self.ui(ui, assets, &mut save));
+        self.open = open;
+        save
+    }
+
+    fn ui(&mut self, ui: &mut Ui, assets: &mut Assets, save: &mut Option<AssetRef>) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("clip")
+                .selected_text(self.clip.as_ref().map_or("No clip", |c| c.path()))
+                .show_ui(ui, |ui| {
+                    for asset in assets.clips.keys() {
+                        ui.selectable_value(&mut self.clip, Some(asset.clone()), asset.path());
+                    }
+                });
+            ui.separator();
+            ui.text_edit_singleline(&mut self.new_name);
+            if ui.button("➕ New clip").clicked() {
+                let asset = AssetRef::new(format!("{}.anim.json", self.new_name));
+                assets.clips.entry(asset.clone()).or_default();
+                self.clip = Some(asset);
+                self.selected_frame = 0;
+            }
+        });
+        let Some(asset) = self.clip.clone() else {
+            ui.weak("Import or create an animation clip.");
+            return;
+        };
+        let Some(mut clip) = assets.clips.get(&asset).cloned() else {
+            self.clip = None;
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Mode")
+                .selected_text(format!("{:?}", clip.mode))
+                .show_ui(ui, |ui| {
+                    for mode in LoopMode::ALL {
+                        ui.selectable_value(&mut clip.mode, mode, format!("{mode:?}"));
+                    }
+                });
+            ui.label(format!("{:.2}s", clip.duration()));
+            ui.menu_button("➕ Frames from atlas", |ui| {
+                for (atlas_ref, atlas) in &assets.atlases {
+                    if ui.button(atlas_ref.path()).clicked() {
+                        clip.frames
+                            .extend(atlas.frames.keys().map(|name| AnimationFrame {
+                                image: atlas_ref.clone(),
+                                region: name.clone(),
+                                duration: 0.1,
+                            }));
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui.button("💾 Save…").clicked() {
+                *save = Some(asset.clone());
+            }
+        });
+        ui.separator();
+
+        self.preview_ui(ui, assets, &clip);
+        ui.separator();
+        self.timeline_ui(ui, assets, &clip);
+        ui.separator();
+        self.frame_ui(ui, assets, &mut clip);
+
+        if assets.clips.get(&asset) != Some(&clip) {
+            assets.clips.insert(asset, clip);
+        }
+    }
+
+    fn preview_ui(&mut self, ui: &mut Ui, assets: &Assets, clip: &AnimationClip) {
+        if self.preview_playing {
+            self.preview_time += ui.input(|i| i.stable_dt) * self.preview_speed;
+            if clip.finished(self.preview_time) {
+                self.preview_playing = false;
+            }
+            ui.ctx().request_repaint();
+        }
+        ui.horizontal(|ui| {
+            let current = clip.frame_at(self.preview_time);
+            match current.and_then(|i| clip.frames.get(i)) {
+                Some(frame) => frame_image(ui, assets, frame, 128.0),
+                None => {
+                    ui.add_sized(Vec2::splat(128.0), egui::Label::new("Empty clip"));
+                }
+            }
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    let label = if self.preview_playing { "⏸" } else { "▶" };
+                    if ui.button(label).clicked() {
+                        if !self.preview_playing && clip.finished(self.preview_time) {
+                            self.preview_time = 0.0;
+                        }
+                        self.preview_playing = !self.preview_playing;
+                    }
+                    if ui.button("⏮").clicked() {
+                        self.preview_time = 0.0;
+                    }
+                    ui.label(format!("{:.2}s", self.preview_time));
+                });
+                ui.add(
+                    egui::Slider::new(&mut self.preview_speed, 0.1..=4.0)
+                        .text("speed")
+                        .logarithmic(true),
+                );
+                if let Some(i) = current {
+                    ui.label(format!("Frame {} / {}", i + 1, clip.frames.len()));
+                }
+            });
+        });
+    }
+
+    fn timeline_ui(&mut self, ui: &mut Ui, assets: &Assets, clip: &AnimationClip) {
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                let mut start = 0.0;
+                for (i, frame) in clip.frames.iter().enumerate() {
+                    let width = (frame.duration * POINTS_PER_SECOND).max(THUMBNAIL);
+                    let (rect, response) =
+                        ui.allocate_exact_size(Vec2::new(width, THUMBNAIL + 16.0), Sense::click());
+                    let visuals = ui.visuals();
+                    let fill = if i == self.selected_frame {
+                        visuals.selection.bg_fill
+                    } else {
+                        visuals.widgets.inactive.bg_fill
+                    };
+                    ui.painter().rect(
+                        rect.shrink(1.0),
+                        0.0,
+                        fill,
+                        visuals.widgets.noninteractive.bg_stroke,
+                    );
+                    let mut child = ui.child_ui(rect.shrink(2.0), *ui.layout());
+                    frame_image(&mut child, assets, frame, THUMBNAIL);
+                    let has_event = clip.events.iter().any(|e| e.frame == i);
+                    child.small(if has_event {
+                        format!("{}⚑", i + 1)
+                    } else {
+                        format!("{}", i + 1)
+                    });
+                    if clip.frame_at(self.preview_time) == Some(i) {
+                        ui.painter().rect_stroke(
+                            rect,
+                            0.0,
+                            Stroke {
+                                width: 2.0,
+                                color: Color32::YELLOW,
+                            },
+                        );
+                    }
+                    if response.clicked() {
+                        self.selected_frame = i;
+                        self.preview_time = start;
+                        self.preview_playing = false;
+                    }
+                    start += frame.duration;
+                }
+            });
+        });
+    }
+
+    fn frame_ui(&mut self, ui: &mut Ui, assets: &Assets, clip: &mut AnimationClip) {
+        if clip.frames.is_empty() {
+            if ui.button("➕ Add frame").clicked() {
+                clip.frames.push(AnimationFrame {
+                    image: AssetRef::default(),
+                    region: String::new(),
+                    duration: 0.1,
+                });
+            }
+            return;
+        }
+        let i = self.selected_frame.min(clip.frames.len() - 1);
+        self.selected_frame = i;
+
+        ui.horizontal(|ui| {
+            ui.strong(format!("Frame {}", i + 1));
+            if ui.add_enabled(i > 0, egui::Button::new("◀")).clicked() {
+                swap_frames(clip, i, i - 1);
+                self.selected_frame -= 1;
+            }
+            if ui
+                .add_enabled(i + 1 < clip.frames.len(), egui::Button::new("▶"))
+                .clicked()
+            {
+                swap_frames(clip, i, i + 1);
+                self.selected_frame += 1;
+            }
+            if ui.button("Duplicate").clicked() {
+                clip.frames.insert(i + 1, clip.frames[i].clone());
+                for e in &mut clip.events {
+                    if e.frame > i {
+                        e.frame += 1;
+                    }
+                }
+            }
+            if ui.button("🗑").clicked() {
+                clip.frames.remove(i);
+                clip.events.retain(|e| e.frame != i);
+                for e in &mut clip.events {
+                    if e.frame > i {
+                        e.frame -= 1;
+                    }
+                }
+            }
+        });
+        let Some(frame) = clip.frames.get_mut(i) else {
+            return;
+        };
+        egui::Grid::new("frame").num_columns(2).show(ui, |ui| {
+            ui.label("Duration");
+            ui.add(
+                egui::DragValue::new(&mut frame.duration)
+                    .speed(0.01)
+                    .clamp_range(0.0..=10.0)
+                    .suffix("s"),
+            );
+            ui.end_row();
+
+            ui.label("Image");
+            egui::ComboBox::from_id_source("image")
+                .selected_text(frame.image.path())
+                .show_ui(ui, |ui| {
+                    for asset in assets.textures.keys().chain(assets.atlases.keys()) {
+                        ui.selectable_value(&mut frame.image, asset.clone(), asset.path());
+                    }
+                });
+            ui.end_row();
+
+            if let Some(atlas) = assets.atlases.get(&frame.image) {
+                ui.label("Region");
+                egui::ComboBox::from_id_source("region")
+                    .selected_text(&frame.region)
+                    .show_ui(ui, |ui| {
+                        for name in atlas.frames.keys() {
+                            ui.selectable_value(&mut frame.region, name.clone(), name);
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+
+        ui.label("Events");
+        let mut remove = None;
+        for (j, event) in clip.events.iter_mut().enumerate() {
+            if event.frame != i {
+                continue;
+            }
+            ui.horizontal(|ui| {
+                ui.push_id(j, |ui| ui.text_edit_singleline(&mut event.name));
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(j);
+                }
+            });
+        }
+        if let Some(j) = remove {
+            clip.events.remove(j);
+        }
+        if ui.small_button("➕ Event").clicked() {
+            clip.events.push(AnimationEvent {
+                frame: i,
+                name: "event".to_owned(),
+            });
+        }
+    }
+}
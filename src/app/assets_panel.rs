@@ -0,0 +1,214 @@
+use egui::{Image, RichText, Ui, Vec2};
+
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::audio::{Audio, AudioSource};
+use crate::engine::scene::{Component, Entity, Sprite};
+use crate::engine::script::Script;
+use crate::engine::text::{self, Text};
+
+const THUMBNAIL: f32 = 32.0;
+
+/// Points the sprite of `entity` at the image, adding a sprite if it has none.
+fn use_image(entity: &mut Entity, image: &AssetRef, region: &str) {
+    let sprite = entity.components.iter_mut().find_map(|c| match c {
+        Component::Sprite(s) => Some(s),
+        _ => None,
+    });
+    match sprite {
+        Some(s) => {
+            s.image = image.clone();
+            s.region = region.to_owned();
+        }
+        None => entity.components.push(Component::Sprite(Sprite {
+            image: image.clone(),
+            region: region.to_owned(),
+            ..Default::default()
+        })),
+    }
+}
+
+/// Points the audio source of `entity` at the sound, adding a source if it has none.
+fn use_sound(entity: &mut Entity, sound: &AssetRef) {
+    let source = entity.components.iter_mut().find_map(|c| match c {
+        Component::AudioSource(s) => Some(s),
+        _ => None,
+    });
+    match source {
+        Some(s) => s.sound = sound.clone(),
+        None => entity.components.push(Component::AudioSource(AudioSource {
+            sound: sound.clone(),
+            ..Default::default()
+        })),
+    }
+}
+
+/// Sets the font of the text of `entity`, adding a text if it has none.
+fn use_font(entity: &mut Entity, font: &AssetRef) {
+    let text = entity.components.iter_mut().find_map(|c| match c {
+        Component::Text(t) => Some(t),
+        _ => None,
+    });
+    match text {
+        Some(t) => t.font = font.path().to_owned(),
+        None => entity.components.push(Component::Text(Text {
+            font: font.path().to_owned(),
+            ..Default::default()
+        })),
+    }
+}
+
+/// Adds the script to `entity`, unless it already runs it.
+fn use_script(entity: &mut Entity, script: &AssetRef) {
+    let running = entity
+        .components
+        .iter()
+        .any(|c| matches!(c, Component::Script(s) if &s.script == script));
+    if !running {
+        entity.components.push(Component::Script(Script {
+            script: script.clone(),
+        }));
+    }
+}
+
+/// Lists the loaded assets. Images, atlas frames, sounds, scripts and fonts can be assigned to the
+/// selected entity, returns true if it was changed. A script picked for editing is put in
+/// `edit`.
+pub fn assets_ui(
+    ui: &mut Ui,
+    assets: &Assets,
+    mut selected: Option<&mut Entity>,
+    audio: &mut Audio,
+    edit: &mut Option<AssetRef>,
+) -> bool {
+    let mut changed = false;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::CollapsingHeader::new(format!("Images ({})", assets.textures.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for (asset, texture) in &assets.textures {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            Image::new(&texture.handle)
+                                .fit_to_exact_size(Vec2::splat(THUMBNAIL))
+                                .maintain_aspect_ratio(true),
+                        );
+                        ui.label(asset.path());
+                        ui.weak(format!("{}×{}", texture.size[0], texture.size[1]));
+                        if let Some(entity) = selected.as_deref_mut() {
+                            if ui.small_button("Use").clicked() {
+                                use_image(entity, asset, "");
+                                changed = true;
+                            }
+                        }
+                    });
+                }
+            });
+        egui::CollapsingHeader::new(format!("Atlases ({})", assets.atlases.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for (asset, atlas) in &assets.atlases {
+                    ui.collapsing(asset.path(), |ui| {
+                        if !assets.textures.contains_key(&atlas.image) {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                format!("Image {} not loaded.", atlas.image),
+                            );
+                        }
+                        for name in atlas.frames.keys() {
+                            ui.horizontal(|ui| {
+                                if let Some((texture, region)) = assets.image_region(asset, name) {
+                                    ui.add(
+                                        Image::new(&texture.handle)
+                                            .uv(texture.uv(region))
+                                            .fit_to_exact_size(Vec2::splat(THUMBNAIL))
+                                            .maintain_aspect_ratio(true),
+                                    );
+                                }
+                                ui.label(name);
+                                if let Some(entity) = selected.as_deref_mut() {
+                                    if ui.small_button("Use").clicked() {
+                                        use_image(entity, asset, name);
+                                        changed = true;
+                                    }
+                                }
+                            });
+                        }
+                    });
+                }
+            });
+        egui::CollapsingHeader::new(format!("Sounds ({})", assets.sounds.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for (asset, sound) in &assets.sounds {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("▶").on_hover_text("Preview").clicked() {
+                            audio.preview(sound.clone());
+                        }
+                        ui.label(asset.path());
+                        ui.weak(format!(
+                            "{:.1}s, {} Hz, {} ch",
+                            sound.duration(),
+                            sound.sample_rate,
+                            sound.channels
+                        ));
+                        if let Some(entity) = selected.as_deref_mut() {
+                            if ui.small_button("Use").clicked() {
+                                use_sound(entity, asset);
+                                changed = true;
+                            }
+                        }
+                    });
+                }
+                if audio.is_previewing() && ui.small_button("⏹ Stop preview").clicked() {
+                    audio.stop_preview();
+                }
+            });
+        egui::CollapsingHeader::new(format!("Scripts ({})", assets.scripts.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for (asset, source) in &assets.scripts {
+                    ui.horizontal(|ui| {
+                        ui.label(asset.path());
+                        ui.weak(format!("{} lines", source.lines().count()));
+                        if ui.small_button("📝 Edit").clicked() {
+                            *edit = Some(asset.clone());
+                        }
+                        if let Some(entity) = selected.as_deref_mut() {
+                            if ui.small_button("Use").clicked() {
+                                use_script(entity, asset);
+                                changed = true;
+                            }
+                        }
+                    });
+                }
+            });
+        egui::CollapsingHeader::new(format!("Fonts ({})", assets.fonts.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                let families = ui.ctx().fonts(|f| f.families());
+                for asset in assets.fonts.keys() {
+                    ui.horizontal(|ui| {
+                        ui.label(asset.path());
+                        let family = text::family(asset.path());
+                        if families.contains(&family) {
+                            ui.label(RichText::new("AaBbCc 123").family(family));
+                        }
+                        if let Some(entity) = selected.as_deref_mut() {
+                            if ui.small_button("Use").clicked() {
+                                use_font(entity, asset);
+                                changed = true;
+                            }
+                        }
+                    });
+                }
+            });
+        egui::CollapsingHeader::new(format!("Prefabs ({})", assets.prefabs.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for asset in assets.prefabs.keys() {
+                    ui.label(asset.path());
+                }
+            });
+    });
+    changed
+}
@@ -0,0 +1,741 @@
+//! Window for editing scripts inside the editor, with tabs, highlighting, find/replace and the
+//! errors of the script compiler underlined in the text.
+
+use egui::text::{CCursor, CCursorRange, LayoutJob};
+use egui::{
+    Align, Color32, Context, Id, Key, KeyboardShortcut, Modifiers, Pos2, Shape, Stroke, TextFormat,
+    TextStyle, Ui, Vec2,
+};
+use std::sync::Arc;
+
+use super::save_file;
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::script::{self, ScriptError};
+
+const NEW_SCRIPT: &str = "fn on_start() {\n}\n\nfn on_update(dt) {\n}\n";
+/// Edits closer in time than this are undone together.
+const UNDO_GROUP_SECONDS: f64 = 1.0;
+const MAX_UNDO: usize = 100;
+
+const SAVE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+const FIND: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+const REPLACE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::H);
+const GO_TO_LINE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::G);
+const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Y);
+const REDO_SHIFT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// A script opened in the editor.
+struct Tab {
+    asset: AssetRef,
+    text: String,
+    /// The text when it was last saved into the assets.
+    saved: String,
+    error: Option<ScriptError>,
+    undo: Vec<String>,
+    redo: Vec<String>,
+    last_edit: f64,
+}
+
+impl Tab {
+    fn new(asset: AssetRef, text: String) -> Self {
+        let mut tab = Self {
+            asset,
+            saved: text.clone(),
+            text,
+            error: None,
+            undo: vec![],
+            redo: vec![],
+            last_edit: 0.0,
+        };
+        tab.check();
+        tab
+    }
+
+    fn dirty(&self) -> bool {
+        self.text != self.saved
+    }
+
+    fn check(&mut self) {
+        self.error = script::check(&self.text).err();
+    }
+
+    /// Remembers the text before an edit, grouping quick successive edits.
+    fn edited(&mut self, before: String, time: f64) {
+        if time - self.last_edit > UNDO_GROUP_SECONDS || self.undo.is_empty() {
+            self.undo.push(before);
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+        }
+        self.last_edit = time;
+        self.redo.clear();
+        self.check();
+    }
+
+    fn undo(&mut self) {
+        if let Some(text) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.text, text));
+            self.last_edit = 0.0;
+            self.check();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(text) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.text, text));
+            self.last_edit = 0.0;
+            self.check();
+        }
+    }
+
+    /// Stores the text in the assets, which reloads it in a running game.
+    fn save(&mut self, assets: &mut Assets) {
+        assets
+            .scripts
+            .insert(self.asset.clone(), Arc::from(self.text.as_str()));
+        self.saved = self.text.clone();
+    }
+}
+
+#[derive(Default)]
+struct FindBar {
+    open: bool,
+    replace: bool,
+    query: String,
+    replacement: String,
+    match_case: bool,
+}
+
+/// Moves the cursor of the active tab in the next frame.
+#[derive(Default)]
+struct CursorRequest {
+    /// Char range to select.
+    select: Option<(usize, usize)>,
+    focus: bool,
+}
+
+#[derive(Default)]
+pub struct CodeEditor {
+    pub open: bool,
+    tabs: Vec<Tab>,
+    active: usize,
+    /// Tab asking whether to discard its changes.
+    closing: Option<usize>,
+    find: FindBar,
+    go_to_line: Option<String>,
+    request: CursorRequest,
+    /// Char range of the last match of the find bar, which "Replace" replaces.
+    found: Option<(usize, usize)>,
+}
+
+/// Index of the first char of the 1-based line and column.
+fn char_index(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split('\n')
+        .take(line.saturating_sub(1))
+        .map(|l| l.chars().count() + 1)
+        .sum();
+    line_start + column.saturating_sub(1)
+}
+
+fn byte_to_char(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+fn char_to_byte(text: &str, char: usize) -> usize {
+    text.char_indices().nth(char).map_or(text.len(), |(b, _)| b)
+}
+
+/// Byte ranges of all matches of `query`. Ignoring the case only folds ASCII letters, which
+/// keeps byte positions the same as in `text`.
+fn find_all(text: &str, query: &str, match_case: bool) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return vec![];
+    }
+    let (text, query) = if match_case {
+        (text.to_owned(), query.to_owned())
+    } else {
+        (text.to_ascii_lowercase(), query.to_ascii_lowercase())
+    };
+    text.match_indices(&query)
+        .map(|(i, m)| (i, i + m.len()))
+        .collect()
+}
+
+/// Byte range of the first match starting at `from` or later, wrapping around.
+fn find_next(text: &str, query: &str, from: usize, match_case: bool) -> Option<(usize, usize)> {
+    let matches = find_all(text, query, match_case);
+    let next = matches.iter().find(|(start, _)| *start >= from);
+    next.or(matches.first()).copied()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Plain,
+    Keyword,
+    Literal,
+    String,
+    Number,
+    Comment,
+    Function,
+}
+
+const KEYWORDS: &[&str] = &[
+    "let", "const", "fn", "if", "else", "switch", "do", "while", "until", "loop", "for", "in",
+    "break", "continue", "return", "throw", "try", "catch", "import", "export", "as", "global",
+    "private", "this",
+];
+const LITERALS: &[&str] = &["true", "false"];
+
+fn token_color(token: Token, dark: bool) -> Color32 {
+    match (token, dark) {
+        (Token::Plain, true) => Color32::from_rgb(0xd4, 0xd4, 0xd4),
+        (Token::Plain, false) => Color32::from_rgb(0x20, 0x20, 0x20),
+        (Token::Keyword, true) => Color32::from_rgb(0xc5, 0x86, 0xc0),
+        (Token::Keyword, false) => Color32::from_rgb(0xaf, 0x00, 0xdb),
+        (Token::Literal, true) => Color32::from_rgb(0x56, 0x9c, 0xd6),
+        (Token::Literal, false) => Color32::from_rgb(0x00, 0x00, 0xff),
+        (Token::String, true) => Color32::from_rgb(0xce, 0x91, 0x78),
+        (Token::String, false) => Color32::from_rgb(0xa3, 0x15, 0x15),
+        (Token::Number, true) => Color32::from_rgb(0xb5, 0xce, 0xa8),
+        (Token::Number, false) => Color32::from_rgb(0x09, 0x86, 0x58),
+        (Token::Comment, true) => Color32::from_rgb(0x6a, 0x99, 0x55),
+        (Token::Comment, false) => Color32::from_rgb(0x00, 0x80, 0x00),
+        (Token::Function, true) => Color32::from_rgb(0xdc, 0xdc, 0xaa),
+        (Token::Function, false) => Color32::from_rgb(0x79, 0x5e, 0x26),
+    }
+}
+
+/// Splits Rhai source into colored tokens, as byte ranges.
+fn tokenize(text: &str) -> Vec<(Token, usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        let token = if text[i..].starts_with("//") {
+            i = text[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            Token::Comment
+        } else if text[i..].starts_with("/*") {
+            i = text[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4);
+            Token::Comment
+        } else if c == b'"' || c == b'`' || c == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            Token::String
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                i += 1;
+            }
+            Token::Number
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let word = &text[start..i];
+            if KEYWORDS.contains(&word) {
+                Token::Keyword
+            } else if LITERALS.contains(&word) {
+                Token::Literal
+            } else if text[i..].trim_start().starts_with('(') {
+                Token::Function
+            } else {
+                Token::Plain
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+            Token::Plain
+        };
+        tokens.push((token, start, i));
+    }
+    tokens
+}
+
+fn highlight(ui: &Ui, text: &str) -> LayoutJob {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let dark = ui.visuals().dark_mode;
+    let mut job = LayoutJob::default();
+    for (token, start, end) in tokenize(text) {
+        job.append(
+            &text[start..end],
+            0.0,
+            TextFormat::simple(font_id.clone(), token_color(token, dark)),
+        );
+    }
+    job
+}
+
+/// A wavy line under the error, like in other code editors.
+fn squiggle(from: Pos2, width: f32, color: Color32) -> Shape {
+    let step = 2.0;
+    let points = (0..=(width / step).ceil().max(2.0) as usize)
+        .map(|i| from + Vec2::new(i as f32 * step, if i % 2 == 0 { 0.0 } else { step }))
+        .collect();
+    Shape::line(points, Stroke { width: 1.0, color })
+}
+
+impl CodeEditor {
+    /// Opens the script in a tab, or switches to its tab.
+    pub fn open_script(&mut self, assets: &Assets, asset: &AssetRef) {
+        self.open = true;
+        if let Some(i) = self.tabs.iter().position(|t| &t.asset == asset) {
+            self.active = i;
+            return;
+        }
+        let text = assets
+            .scripts
+            .get(asset)
+            .map_or_else(String::new, |s| s.to_string());
+        self.tabs.push(Tab::new(asset.clone(), text));
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// Shows the new version of a reloaded script, unless its tab has unsaved changes.
+    pub fn reloaded(&mut self, assets: &Assets, asset: &AssetRef) {
+        let Some(source) = assets.scripts.get(asset) else {
+            return;
+        };
+        if let Some(tab) = self
+            .tabs
+            .iter_mut()
+            .find(|t| &t.asset == asset && !t.dirty())
+        {
+            tab.text = source.to_string();
+            tab.saved = tab.text.clone();
+            tab.check();
+        }
+    }
+
+    /// Closes all tabs, when another project is opened. Their unsaved changes are lost.
+    pub fn close_all(&mut self) {
+        for tab in self.tabs.iter().filter(|t| t.dirty()) {
+            log::warn!("Discarded the unsaved changes of {}.", tab.asset);
+        }
+        *self = Self {
+            open: self.open,
+            ..Default::default()
+        };
+    }
+
+    /// Stores all changed tabs in the assets, before the project is saved. Returns the scripts
+    /// which changed.
+    pub fn save_all(&mut self, assets: &mut Assets) -> Vec<AssetRef> {
+        let mut saved = vec![];
+        for tab in self.tabs.iter_mut().filter(|t| t.dirty()) {
+            tab.save(assets);
+            log::info!("Saved {}.", tab.asset);
+            saved.push(tab.asset.clone());
+        }
+        saved
+    }
+
+    pub fn show(&mut self, ctx: &Context, assets: &mut Assets) {
+        let mut open = self.open;
+        egui::Window::new("📝 Code")
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| self.ui(ui, assets));
+        self.open = open;
+    }
+
+    fn new_script(&mut self, assets: &mut Assets) {
+        let mut n = 1;
+        let mut asset = AssetRef::new("scripts/new.rhai");
+        while assets.scripts.contains_key(&asset) || self.tabs.iter().any(|t| t.asset == asset) {
+            n += 1;
+            asset = AssetRef::new(format!("scripts/new{n}.rhai"));
+        }
+        let mut tab = Tab::new(asset, NEW_SCRIPT.to_owned());
+        tab.save(assets);
+        self.tabs.push(tab);
+        self.active = self.tabs.len() - 1;
+    }
+
+    fn tab_bar(&mut self, ui: &mut Ui, assets: &mut Assets) {
+        ui.horizontal_wrapped(|ui| {
+            let mut close = None;
+            for (i, tab) in self.tabs.iter().enumerate() {
+                let name = tab.asset.path().rsplit('/').next().unwrap_or_default();
+                let label = if tab.dirty() {
+                    format!("{name} ●")
+                } else {
+                    name.to_owned()
+                };
+                if ui
+                    .selectable_label(i == self.active, label)
+                    .on_hover_text(tab.asset.path())
+                    .clicked()
+                {
+                    self.active = i;
+                }
+                if ui.small_button("✖").clicked() {
+                    close = Some(i);
+                }
+                ui.separator();
+            }
+            if let Some(i) = close {
+                if self.tabs[i].dirty() {
+                    self.closing = Some(i);
+                } else {
+                    self.close_tab(i);
+                }
+            }
+            ui.menu_button("📂 Open", |ui| {
+                let scripts: Vec<_> = assets.scripts.keys().cloned().collect();
+                if scripts.is_empty() {
+                    ui.weak("No scripts loaded.");
+                }
+                for asset in scripts {
+                    if ui.button(asset.path()).clicked() {
+                        self.open_script(assets, &asset);
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui.button("➕ New").clicked() {
+                self.new_script(assets);
+            }
+        });
+    }
+
+    fn close_tab(&mut self, i: usize) {
+        self.tabs.remove(i);
+        self.closing = None;
+        if self.active >= i && self.active > 0 {
+            self.active -= 1;
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, assets: &mut Assets) {
+        self.tab_bar(ui, assets);
+        if let Some(i) = self.closing {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{} has unsaved changes.", self.tabs[i].asset),
+                );
+                if ui.button("Discard").clicked() {
+                    self.close_tab(i);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.closing = None;
+                }
+            });
+        }
+        if self.tabs.is_empty() {
+            ui.weak("Open a script, or use \"Edit\" in the assets panel.");
+            return;
+        }
+        self.active = self.active.min(self.tabs.len() - 1);
+        let editor_id = Id::new(("code editor", &self.tabs[self.active].asset));
+        let focused = ui.memory(|m| m.has_focus(editor_id));
+        self.shortcuts(ui, assets, focused);
+        self.toolbar(ui, assets);
+        if self.find.open {
+            self.find_bar(ui);
+        }
+        if let Some(line) = &mut self.go_to_line {
+            let mut go = None;
+            ui.horizontal(|ui| {
+                ui.label("Go to line");
+                let response = ui.text_edit_singleline(line);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    go = line.trim().parse::<usize>().ok();
+                }
+                if !response.has_focus() && !response.lost_focus() {
+                    response.request_focus();
+                }
+            });
+            if let Some(line) = go {
+                let tab = &self.tabs[self.active];
+                let line = line.clamp(1, tab.text.split('\n').count());
+                let i = char_index(&tab.text, line, 1);
+                self.request = CursorRequest {
+                    select: Some((i, i)),
+                    focus: true,
+                };
+                self.go_to_line = None;
+            }
+        }
+
+        let tab = &self.tabs[self.active];
+        match &tab.error {
+            Some(e) => ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {e}")),
+            None => ui.weak("No errors."),
+        };
+        ui.separator();
+        self.text_ui(ui, editor_id);
+    }
+
+    fn shortcuts(&mut self, ui: &mut Ui, assets: &mut Assets, focused: bool) {
+        if !focused {
+            return;
+        }
+        let tab = &mut self.tabs[self.active];
+        // Undo is handled here rather than by the text edit, so it works per tab and matches
+        // the toolbar buttons.
+        ui.input_mut(|i| {
+            if i.consume_shortcut(&REDO_SHIFT) || i.consume_shortcut(&REDO) {
+                tab.redo();
+            }
+            if i.consume_shortcut(&UNDO) {
+                tab.undo();
+            }
+            if i.consume_shortcut(&SAVE) {
+                tab.save(assets);
+            }
+            if i.consume_shortcut(&FIND) {
+                self.find.open = true;
+                self.find.replace = false;
+            }
+            if i.consume_shortcut(&REPLACE) {
+                self.find.open = true;
+                self.find.replace = true;
+            }
+            if i.consume_shortcut(&GO_TO_LINE) {
+                self.go_to_line = Some(String::new());
+            }
+        });
+    }
+
+    fn toolbar(&mut self, ui: &mut Ui, assets: &mut Assets) {
+        let tab = &mut self.tabs[self.active];
+        ui.horizontal(|ui| {
+            let save = ui.add_enabled(tab.dirty(), egui::Button::new("💾 Save"));
+            if save
+                .on_hover_text(format!(
+                    "Use the changes in the game ({})",
+                    ui.ctx().format_shortcut(&SAVE)
+                ))
+                .clicked()
+            {
+                tab.save(assets);
+            }
+            if ui
+                .button("⬇ Export…")
+                .on_hover_text("Save the script to a file")
+                .clicked()
+            {
+                let name = tab.asset.path().rsplit('/').next().unwrap_or_default();
+                save_file(name.to_owned(), tab.text.clone().into_bytes());
+            }
+            ui.separator();
+            if ui
+                .add_enabled(!tab.undo.is_empty(), egui::Button::new("⮪"))
+                .on_hover_text(format!("Undo ({})", ui.ctx().format_shortcut(&UNDO)))
+                .clicked()
+            {
+                tab.undo();
+            }
+            if ui
+                .add_enabled(!tab.redo.is_empty(), egui::Button::new("⮫"))
+                .on_hover_text(format!("Redo ({})", ui.ctx().format_shortcut(&REDO)))
+                .clicked()
+            {
+                tab.redo();
+            }
+            ui.separator();
+            if ui
+                .selectable_label(self.find.open, "🔍 Find")
+                .on_hover_text(ui.ctx().format_shortcut(&FIND))
+                .clicked()
+            {
+                self.find.open = !self.find.open;
+            }
+            if ui
+                .button("Go to line")
+                .on_hover_text(ui.ctx().format_shortcut(&GO_TO_LINE))
+                .clicked()
+            {
+                self.go_to_line = Some(String::new());
+            }
+        });
+    }
+
+    fn find_bar(&mut self, ui: &mut Ui) {
+        let tab = &mut self.tabs[self.active];
+        let find = &mut self.find;
+        let mut next = false;
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut find.query)
+                    .hint_text("Find")
+                    .desired_width(160.0),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                next = true;
+            }
+            next |= ui.button("Next").clicked();
+            ui.checkbox(&mut find.match_case, "Aa")
+                .on_hover_text("Match case");
+            ui.weak(format!(
+                "{} matches",
+                find_all(&tab.text, &find.query, find.match_case).len()
+            ));
+            ui.toggle_value(&mut find.replace, "Replace");
+            if ui.small_button("✖").clicked() {
+                find.open = false;
+            }
+        });
+        if find.replace {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut find.replacement)
+                        .hint_text("Replace with")
+                        .desired_width(160.0),
+                );
+                if ui.button("Replace").clicked() {
+                    // Replaces the current match, then moves to the next one.
+                    let found = self.found.take().map(|(start, end)| {
+                        (char_to_byte(&tab.text, start), char_to_byte(&tab.text, end))
+                    });
+                    let matches = find_all(&tab.text, &find.query, find.match_case);
+                    if let Some((start, end)) = found.filter(|f| matches.contains(f)) {
+                        let before = tab.text.clone();
+                        tab.text.replace_range(start..end, &find.replacement);
+                        tab.edited(before, ui.input(|i| i.time));
+                        let end = byte_to_char(&tab.text, start + find.replacement.len());
+                        self.found = Some((end, end));
+                    }
+                    next = true;
+                }
+                if ui.button("Replace all").clicked() {
+                    let matches = find_all(&tab.text, &find.query, find.match_case);
+                    if !matches.is_empty() {
+                        let before = tab.text.clone();
+                        for (start, end) in matches.into_iter().rev() {
+                            tab.text.replace_range(start..end, &find.replacement);
+                        }
+                        tab.edited(before, ui.input(|i| i.time));
+                        self.found = None;
+                    }
+                }
+            });
+        }
+        if next {
+            let from = self.found.map_or(0, |(_, end)| end);
+            let from = char_to_byte(&tab.text, from);
+            if let Some((start, end)) = find_next(&tab.text, &find.query, from, find.match_case) {
+                let found = (byte_to_char(&tab.text, start), byte_to_char(&tab.text, end));
+                self.found = Some(found);
+                self.request = CursorRequest {
+                    select: Some(found),
+                    focus: true,
+                };
+            }
+        }
+    }
+
+    fn text_ui(&mut self, ui: &mut Ui, editor_id: Id) {
+        let tab = &mut self.tabs[self.active];
+        let request = std::mem::take(&mut self.request);
+        if let Some((start, end)) = request.select {
+            let mut state = egui::TextEdit::load_state(ui.ctx(), editor_id).unwrap_or_default();
+            state.set_ccursor_range(Some(CCursorRange::two(
+                CCursor::new(start),
+                CCursor::new(end),
+            )));
+            state.store(ui.ctx(), editor_id);
+        }
+        let mut layouter = |ui: &Ui, text: &str, _wrap_width: f32| {
+            let job = highlight(ui, text);
+            ui.fonts(|f| f.layout_job(job))
+        };
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    let lines = tab.text.split('\n').count();
+                    let digits = lines.to_string().len().max(3);
+                    let font_id = TextStyle::Monospace.resolve(ui.style());
+                    let char_width = ui.fonts(|f| f.glyph_width(&font_id, '0'));
+                    let (gutter, _) = ui.allocate_exact_size(
+                        Vec2::new(char_width * digits as f32 + 8.0, 0.0),
+                        egui::Sense::hover(),
+                    );
+
+                    let before = tab.text.clone();
+                    let output = egui::TextEdit::multiline(&mut tab.text)
+                        .id(editor_id)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(20)
+                        .lock_focus(true)
+                        .layouter(&mut layouter)
+                        .show(ui);
+                    if output.response.changed() {
+                        tab.edited(before, ui.input(|i| i.time));
+                        self.found = None;
+                    }
+                    if request.focus {
+                        output.response.request_focus();
+                    }
+
+                    // Line numbers next to the first row of each line.
+                    let painter = ui.painter();
+                    let error_line = tab.error.as_ref().map(|e| e.line);
+                    let mut line = 1;
+                    let mut starts_line = true;
+                    for row in &output.galley.rows {
+                        if starts_line {
+                            let color = if Some(line) == error_line {
+                                ui.visuals().error_fg_color
+                            } else {
+                                ui.visuals().weak_text_color()
+                            };
+                            painter.text(
+                                Pos2::new(
+                                    gutter.right() - 4.0,
+                                    output.text_draw_pos.y + row.rect.top(),
+                                ),
+                                egui::Align2::RIGHT_TOP,
+                                line,
+                                font_id.clone(),
+                                color,
+                            );
+                            line += 1;
+                        }
+                        starts_line = row.ends_with_newline;
+                    }
+
+                    if let Some(e) = &tab.error {
+                        let start = char_index(&tab.text, e.line, e.column.max(1));
+                        let word = tab
+                            .text
+                            .chars()
+                            .skip(start)
+                            .take_while(|c| c.is_alphanumeric() || *c == '_')
+                            .count()
+                            .max(1);
+                        let cursor = output.galley.from_ccursor(CCursor::new(start));
+                        let rect = output.galley.pos_from_cursor(&cursor);
+                        let from = output.text_draw_pos + rect.left_bottom().to_vec2();
+                        let width = char_width * word as f32;
+                        painter.add(squiggle(from, width, ui.visuals().error_fg_color));
+                        let hover = egui::Rect::from_min_size(
+                            from - Vec2::new(0.0, rect.height()),
+                            Vec2::new(width, rect.height()),
+                        );
+                        if ui.rect_contains_pointer(hover) {
+                            egui::show_tooltip_text(ui.ctx(), Id::new("script error"), &e.message);
+                        }
+                    }
+
+                    if let Some((start, _)) = request.select {
+                        let cursor = output.galley.from_ccursor(CCursor::new(start));
+                        let rect = output.galley.pos_from_cursor(&cursor);
+                        ui.scroll_to_rect(
+                            rect.translate(output.text_draw_pos.to_vec2()),
+                            Some(Align::Center),
+                        );
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,177 @@
+use egui::{Context, Ui, ViewportBuilder, ViewportClass, ViewportId};
+use egui_dock::{DockState, NodeIndex};
+use serde::{Deserialize, Serialize};
+
+use super::keymap::{Command, Keymap};
+
+/// A panel of the editor, shown as a tab of the dock or in its own window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Panel {
+    Viewport,
+    Hierarchy,
+    Inspector,
+    Assets,
+    Console,
+    Mixer,
+    Profiler,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 7] = [
+        Panel::Viewport,
+        Panel::Hierarchy,
+        Panel::Inspector,
+        Panel::Assets,
+        Panel::Console,
+        Panel::Mixer,
+        Panel::Profiler,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Panel::Viewport => "🖵 Viewport",
+            Panel::Hierarchy => "🖧 Hierarchy",
+            Panel::Inspector => "🪛 Inspector",
+            Panel::Assets => "🗀 Assets",
+            Panel::Console => "🖹 Console",
+            Panel::Mixer => "🔊 Mixer",
+            Panel::Profiler => "⏱ Profiler",
+        }
+    }
+}
+
+/// Arrangement of the panels, saved with the project.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layout {
+    pub dock: DockState<Panel>,
+    /// Panels floated into their own native windows.
+    #[serde(default)]
+    pub windows: Vec<Panel>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        let mut dock = DockState::new(vec![Panel::Viewport]);
+        let tree = dock.main_surface_mut();
+        let [center, _] = tree.split_left(NodeIndex::root(), 0.82, vec![Panel::Hierarchy]);
+        let [center, _] = tree.split_right(center, 0.78, vec![Panel::Inspector]);
+        tree.split_below(center, 0.72, vec![Panel::Assets, Panel::Console]);
+        Self {
+            dock,
+            windows: vec![],
+        }
+    }
+}
+
+impl Layout {
+    pub fn load(value: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        let mut value = value.clone();
+        fill_unset_coordinates(&mut value);
+        Self::deserialize(value)
+    }
+
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("layouts are plain data")
+    }
+
+    pub fn is_open(&self, panel: Panel) -> bool {
+        self.windows.contains(&panel) || self.dock.find_tab(&panel).is_some()
+    }
+
+    /// Closes an open panel, or opens a closed one as a tab.
+    pub fn toggle(&mut self, panel: Panel) {
+        if let Some(i) = self.windows.iter().position(|p| *p == panel) {
+            self.windows.remove(i);
+        } else if let Some(tab) = self.dock.find_tab(&panel) {
+            self.dock.remove_tab(tab);
+        } else {
+            self.dock.push_to_focused_leaf(panel);
+        }
+    }
+
+    /// Opens a panel as a tab if it is closed.
+    pub fn open(&mut self, panel: Panel) {
+        if !self.is_open(panel) {
+            self.dock.push_to_focused_leaf(panel);
+        }
+    }
+
+    /// Moves a docked panel into its own window.
+    pub fn float(&mut self, panel: Panel) {
+        if let Some(tab) = self.dock.find_tab(&panel) {
+            self.dock.remove_tab(tab);
+        }
+        if !self.windows.contains(&panel) {
+            self.windows.push(panel);
+        }
+    }
+
+    /// Moves a panel from its own window back into the dock.
+    pub fn unfloat(&mut self, panel: Panel) {
+        self.windows.retain(|p| *p != panel);
+        if self.dock.find_tab(&panel).is_none() {
+            self.dock.push_to_focused_leaf(panel);
+        }
+    }
+
+    /// Contents of the Window menu.
+    pub fn menu_ui(&mut self, ui: &mut Ui, keymap: &Keymap) {
+        for panel in Panel::ALL {
+            let mut open = self.is_open(panel);
+            let response = ui.checkbox(&mut open, panel.title());
+            if keymap
+                .on_hover(response, Command::for_panel(panel))
+                .changed()
+            {
+                self.toggle(panel);
+            }
+        }
+        ui.separator();
+        if ui.button("Reset layout").clicked() {
+            *self = Self::default();
+            ui.close_menu();
+        }
+    }
+}
+
+/// The dock keeps the rects of its nodes, which are infinite before they are laid out and saved as
+/// null by JSON. They are laid out again anyway, so any number will do.
+fn fill_unset_coordinates(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                if value.is_null() && (key == "x" || key == "y") {
+                    *value = 0.0.into();
+                } else {
+                    fill_unset_coordinates(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(fill_unset_coordinates),
+        _ => {}
+    }
+}
+
+/// Shows a floated panel in its own native window, or in an egui window where there is only one
+/// native window (e.g. on the web). Returns false once the user closed the window, which
+/// should dock the panel again.
+pub fn show_window(ctx: &Context, panel: Panel, add_contents: impl FnOnce(&mut Ui)) -> bool {
+    let builder = ViewportBuilder::default()
+        .with_title(panel.title())
+        .with_inner_size([360.0, 420.0]);
+    ctx.show_viewport_immediate(
+        ViewportId::from_hash_of(("panel", panel)),
+        builder,
+        |ctx, class| {
+            if class == ViewportClass::Embedded {
+                let mut open = true;
+                egui::Window::new(panel.title())
+                    .open(&mut open)
+                    .show(ctx, add_contents);
+                return open;
+            }
+            egui::CentralPanel::default().show(ctx, add_contents);
+            !ctx.input(|i| i.viewport().close_requested())
+        },
+    )
+}
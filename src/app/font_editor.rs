@@ -0,0 +1,260 @@
+use egui::{Context, FontFamily, FontId, FontTweak, RichText, Slider, Ui};
+use std::sync::mpsc;
+
+use super::{open_file, LoadedFile};
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::text::{self, FontSettings};
+
+const SAMPLE: &str = "The quick brown fox jumps over the lazy dog.\n0123456789 ÄÖÜ äöü ß €";
+
+/// A change to the font chain of a family.
+enum ChainEdit {
+    Up(usize),
+    Down(usize),
+    Remove(usize),
+    Add(AssetRef),
+}
+
+/// Window for the project fonts: how they are scaled, the families made of them and a preview.
+pub struct FontEditor {
+    pub open: bool,
+    /// Family shown in the preview.
+    family: String,
+    size: f32,
+    sample: String,
+    new_family: String,
+}
+
+impl Default for FontEditor {
+    fn default() -> Self {
+        Self {
+            open: false,
+            family: String::new(),
+            size: 24.0,
+            sample: SAMPLE.to_owned(),
+            new_family: String::new(),
+        }
+    }
+}
+
+impl FontEditor {
+    /// Shows the window, returns true if the fonts have to be installed again.
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        assets: &Assets,
+        settings: &mut FontSettings,
+        load: &mpsc::Sender<LoadedFile>,
+    ) -> bool {
+        let mut changed = false;
+        let mut open = self.open;
+        egui::Window::new("🗛 Fonts")
+            .open(&mut open)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    changed = self.ui(ui, assets, settings, load);
+                });
+            });
+        self.open = open;
+        changed
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        assets: &Assets,
+        settings: &mut FontSettings,
+        load: &mpsc::Sender<LoadedFile>,
+    ) -> bool {
+        let mut changed = false;
+        let families = ui.ctx().fonts(|f| f.families());
+        ui.horizontal(|ui| {
+            if ui.button("📂 Load font…").clicked() {
+                open_file(load.clone());
+            }
+            ui.weak("TTF and OTF files become project fonts.");
+        });
+
+        egui::CollapsingHeader::new(format!("Fonts ({})", assets.fonts.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                for asset in assets.fonts.keys() {
+                    ui.collapsing(asset.path(), |ui| {
+                        let family = text::family(asset.path());
+                        if families.contains(&family) {
+                            ui.label(RichText::new(SAMPLE).family(family));
+                        }
+                        let mut tweak = settings.tweaks.get(asset).copied().unwrap_or_default();
+                        if tweak_ui(ui, &mut tweak) {
+                            if tweak == FontTweak::default() {
+                                settings.tweaks.remove(asset);
+                            } else {
+                                settings.tweaks.insert(asset.clone(), tweak);
+                            }
+                            changed = true;
+                        }
+                    });
+                }
+            });
+
+        egui::CollapsingHeader::new("Families")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.weak(
+                    "A character is drawn with the first font which has it. Fonts added to \
+                     Proportional and Monospace are fallbacks of the editor fonts.",
+                );
+                let mut remove = None;
+                for (name, chain) in &mut settings.families {
+                    ui.horizontal(|ui| {
+                        ui.strong(name);
+                        if ui.small_button("🗑").clicked() {
+                            remove = Some(name.clone());
+                        }
+                    });
+                    if let Some(edit) = chain_ui(ui, name, chain, assets) {
+                        match edit {
+                            ChainEdit::Up(i) => chain.swap(i - 1, i),
+                            ChainEdit::Down(i) => chain.swap(i, i + 1),
+                            ChainEdit::Remove(i) => {
+                                chain.remove(i);
+                            }
+                            ChainEdit::Add(font) => chain.push(font),
+                        }
+                        changed = true;
+                    }
+                }
+                if let Some(name) = remove {
+                    settings.families.remove(&name);
+                    changed = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_family);
+                    let name = self.new_family.trim().to_owned();
+                    let free = !name.is_empty() && !settings.families.contains_key(&name);
+                    if ui
+                        .add_enabled(free, egui::Button::new("➕ Family"))
+                        .clicked()
+                    {
+                        settings.families.insert(name, vec![]);
+                        self.new_family.clear();
+                        changed = true;
+                    }
+                });
+            });
+
+        egui::CollapsingHeader::new("Preview")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let label = if self.family.is_empty() {
+                        "Proportional"
+                    } else {
+                        &self.family
+                    };
+                    egui::ComboBox::from_id_source("preview family")
+                        .selected_text(label)
+                        .show_ui(ui, |ui| {
+                            for family in &families {
+                                let name = family_name(family);
+                                let label = if name.is_empty() {
+                                    "Proportional"
+                                } else {
+                                    &name
+                                };
+                                if ui.selectable_label(name == self.family, label).clicked() {
+                                    self.family = name;
+                                }
+                            }
+                        });
+                    ui.add(Slider::new(&mut self.size, 6.0..=96.0).suffix(" pt"));
+                });
+                ui.text_edit_multiline(&mut self.sample);
+                let family = Some(text::family(&self.family))
+                    .filter(|f| families.contains(f))
+                    .unwrap_or_default();
+                ui.label(RichText::new(&self.sample).font(FontId::new(self.size, family)));
+            });
+        changed
+    }
+}
+
+/// The name of a family as used in [`text::family`].
+fn family_name(family: &FontFamily) -> String {
+    match family {
+        FontFamily::Proportional => String::new(),
+        FontFamily::Monospace => "Monospace".to_owned(),
+        FontFamily::Name(name) => name.to_string(),
+    }
+}
+
+fn tweak_ui(ui: &mut Ui, tweak: &mut FontTweak) -> bool {
+    let mut changed = false;
+    egui::Grid::new("tweak").num_columns(2).show(ui, |ui| {
+        ui.label("Scale");
+        changed |= ui.add(Slider::new(&mut tweak.scale, 0.25..=4.0)).changed();
+        ui.end_row();
+        ui.label("Offset");
+        changed |= ui
+            .add(Slider::new(&mut tweak.y_offset_factor, -1.0..=1.0))
+            .on_hover_text("Moves the glyphs down, as a fraction of the font size.")
+            .changed();
+        ui.end_row();
+        ui.label("Baseline");
+        changed |= ui
+            .add(Slider::new(&mut tweak.baseline_offset_factor, -1.0..=1.0))
+            .on_hover_text("Moves the baseline, to line up with other fonts.")
+            .changed();
+        ui.end_row();
+        if ui.button("Reset").clicked() {
+            *tweak = FontTweak::default();
+            changed = true;
+        }
+        ui.end_row();
+    });
+    changed
+}
+
+fn chain_ui(ui: &mut Ui, family: &str, chain: &[AssetRef], assets: &Assets) -> Option<ChainEdit> {
+    let mut edit = None;
+    for (i, font) in chain.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add_space(12.0);
+            if assets.fonts.contains_key(font) {
+                ui.label(font.path());
+            } else {
+                ui.colored_label(ui.visuals().warn_fg_color, font.path())
+                    .on_hover_text("Not loaded.");
+            }
+            if ui
+                .add_enabled(i > 0, egui::Button::new("⏶").small())
+                .clicked()
+            {
+                edit = Some(ChainEdit::Up(i));
+            }
+            if ui
+                .add_enabled(i + 1 < chain.len(), egui::Button::new("⏷").small())
+                .clicked()
+            {
+                edit = Some(ChainEdit::Down(i));
+            }
+            if ui.small_button("✖").clicked() {
+                edit = Some(ChainEdit::Remove(i));
+            }
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.add_space(12.0);
+        egui::ComboBox::from_id_source(("add font", family))
+            .selected_text("➕ Font")
+            .show_ui(ui, |ui| {
+                for font in assets.fonts.keys().filter(|f| !chain.contains(f)) {
+                    if ui.selectable_label(false, font.path()).clicked() {
+                        edit = Some(ChainEdit::Add(font.clone()));
+                    }
+                }
+            });
+    });
+    edit
+}
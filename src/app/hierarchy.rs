@@ -0,0 +1,82 @@
+use egui::Ui;
+
+use crate::engine::assets::Assets;
+use crate::engine::prefab;
+use crate::engine::scene::{Entity, EntityId, Scene};
+
+/// Tree view of the scene entities. Returns true if the scene was changed.
+pub fn hierarchy_ui(
+    ui: &mut Ui,
+    scene: &mut Scene,
+    assets: &Assets,
+    selected: &mut Option<EntityId>,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Scene");
+        changed |= ui.text_edit_singleline(&mut scene.name).changed();
+    });
+    ui.horizontal(|ui| {
+        if ui.button("➕ Entity").clicked() {
+            *selected = Some(scene.spawn("Entity", None));
+            changed = true;
+        }
+        ui.add_enabled_ui(!assets.prefabs.is_empty(), |ui| {
+            ui.menu_button("➕ Prefab", |ui| {
+                for (asset, p) in &assets.prefabs {
+                    if ui.button(asset.path()).clicked() {
+                        *selected = Some(prefab::instantiate(scene, asset, p, None));
+                        changed = true;
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+        if let Some(id) = *selected {
+            if ui.button("➕ Child").clicked() {
+                *selected = Some(scene.spawn("Entity", Some(id)));
+                changed = true;
+            }
+            if ui.button("🗑 Delete").clicked() {
+                scene.remove(id);
+                *selected = None;
+                changed = true;
+            }
+        }
+    });
+    ui.separator();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for e in &scene.entities {
+            entity_row(ui, e, selected);
+        }
+    });
+    changed
+}
+
+fn entity_row(ui: &mut Ui, entity: &Entity, selected: &mut Option<EntityId>) {
+    let is_selected = *selected == Some(entity.id);
+    // Prefab instances are shown in the hyperlink color, like overridden fields in the inspector.
+    let name = if entity.prefab.is_some() {
+        egui::RichText::new(&entity.name).color(ui.visuals().hyperlink_color)
+    } else {
+        egui::RichText::new(&entity.name)
+    };
+    if entity.children.is_empty() {
+        if ui.selectable_label(is_selected, name).clicked() {
+            *selected = Some(entity.id);
+        }
+        return;
+    }
+    let id = ui.make_persistent_id(entity.id);
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+        .show_header(ui, |ui| {
+            if ui.selectable_label(is_selected, name).clicked() {
+                *selected = Some(entity.id);
+            }
+        })
+        .body(|ui| {
+            for c in &entity.children {
+                entity_row(ui, c, selected);
+            }
+        });
+}
@@ -0,0 +1,285 @@
+use egui::{Context, Event, Key, Slider, Ui};
+
+use crate::engine::input::{
+    Action, Axis, AxisBinding, Chord, GamepadAxis, InputMap, InputSource, Modifier,
+};
+
+/// What the next key press is bound to.
+#[derive(Clone, Debug, PartialEq)]
+enum Listen {
+    /// A new chord of the action.
+    Action(String),
+    /// One side of the button pair of an axis binding.
+    Axis {
+        axis: String,
+        binding: usize,
+        positive: bool,
+    },
+}
+
+/// Window for editing the actions and axes of the project.
+#[derive(Default)]
+pub struct InputEditor {
+    pub open: bool,
+    listening: Option<Listen>,
+    new_name: String,
+}
+
+/// The first key pressed this frame, with the modifiers held.
+fn pressed_chord(ctx: &Context) -> Option<Chord> {
+    ctx.input(|i| {
+        i.events.iter().find_map(|e| match e {
+            Event::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            } => {
+                let mut sources = vec![];
+                for (held, m) in [
+                    (modifiers.ctrl, Modifier::Ctrl),
+                    (modifiers.shift, Modifier::Shift),
+                    (modifiers.alt, Modifier::Alt),
+                ] {
+                    if held {
+                        sources.push(InputSource::Modifier(m));
+                    }
+                }
+                sources.push(InputSource::Key(*key));
+                Some(Chord(sources))
+            }
+            _ => None,
+        })
+    })
+}
+
+/// Menu with the mouse and gamepad inputs, which can't be bound by pressing them.
+fn buttons_menu(ui: &mut Ui, label: &str) -> Option<InputSource> {
+    let mut picked = None;
+    ui.menu_button(label, |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for source in InputSource::buttons() {
+                if ui.button(source.to_string()).clicked() {
+                    picked = Some(source);
+                    ui.close_menu();
+                }
+            }
+        });
+    });
+    picked
+}
+
+impl InputEditor {
+    /// Shows the window, returns true if the map was changed.
+    pub fn show(&mut self, ctx: &Context, map: &mut InputMap) -> bool {
+        let mut changed = false;
+        if let Some(listen) = self.listening.clone() {
+            if let Some(chord) = pressed_chord(ctx) {
+                self.listening = None;
+                if chord.0 != [InputSource::Key(Key::Escape)] {
+                    changed |= bind(map, listen, chord);
+                }
+            }
+        }
+        let mut open = self.open;
+        egui::Window::new("🎮 Input")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| changed |= self.ui(ui, map));
+            });
+        self.open = open;
+        changed
+    }
+
+    fn listen_button(&mut self, ui: &mut Ui, label: String, listen: Listen) {
+        let active = self.listening.as_ref() == Some(&listen);
+        let text = if active {
+            "Press a key…".to_owned()
+        } else {
+            label
+        };
+        let response = ui
+            .selectable_label(active, text)
+            .on_hover_text("Click, then press a key. Escape cancels.");
+        if response.clicked() {
+            self.listening = if active { None } else { Some(listen) };
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, map: &mut InputMap) -> bool {
+        let mut changed = false;
+        ui.heading("Actions");
+        let mut remove_action = None;
+        for (name, action) in &mut map.actions {
+            ui.horizontal_wrapped(|ui| {
+                ui.strong(name);
+                let mut remove = None;
+                for (i, chord) in action.bindings.iter().enumerate() {
+                    if ui
+                        .button(chord.to_string())
+                        .on_hover_text("Click to remove")
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                }
+                if let Some(i) = remove {
+                    action.bindings.remove(i);
+                    changed = true;
+                }
+                self.listen_button(ui, "➕ Key".to_owned(), Listen::Action(name.clone()));
+                if let Some(source) = buttons_menu(ui, "➕ Button") {
+                    action.bindings.push(source.into());
+                    changed = true;
+                }
+                if ui.small_button("🗑").clicked() {
+                    remove_action = Some(name.clone());
+                }
+            });
+        }
+        if let Some(name) = remove_action {
+            map.actions.remove(&name);
+            changed = true;
+        }
+
+        ui.separator();
+        ui.heading("Axes");
+        let mut remove_axis = None;
+        for (name, axis) in &mut map.axes {
+            ui.horizontal(|ui| {
+                ui.strong(name);
+                ui.label("Dead zone");
+                changed |= ui
+                    .add(Slider::new(&mut axis.dead_zone, 0.0..=0.9))
+                    .changed();
+                if ui.small_button("🗑").clicked() {
+                    remove_axis = Some(name.clone());
+                }
+            });
+            let mut remove = None;
+            for (i, binding) in axis.bindings.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add_space(12.0);
+                    match binding {
+                        AxisBinding::Buttons { negative, positive } => {
+                            for (source, is_positive) in [(negative, false), (positive, true)] {
+                                ui.label(if is_positive { "+" } else { "−" });
+                                let listen = Listen::Axis {
+                                    axis: name.clone(),
+                                    binding: i,
+                                    positive: is_positive,
+                                };
+                                self.listen_button(ui, source.to_string(), listen);
+                                if let Some(picked) = buttons_menu(ui, "⏷") {
+                                    *source = picked;
+                                    changed = true;
+                                }
+                            }
+                        }
+                        AxisBinding::Analog { axis, invert } => {
+                            egui::ComboBox::from_id_source(("analog", name.as_str(), i))
+                                .selected_text(format!("Pad{axis:?}"))
+                                .show_ui(ui, |ui| {
+                                    for a in GamepadAxis::ALL {
+                                        changed |= ui
+                                            .selectable_value(axis, a, format!("Pad{a:?}"))
+                                            .changed();
+                                    }
+                                });
+                            changed |= ui.checkbox(invert, "Invert").changed();
+                        }
+                    }
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                axis.bindings.remove(i);
+                changed = true;
+            }
+            ui.horizontal(|ui| {
+                ui.add_space(12.0);
+                if ui.small_button("➕ Buttons").clicked() {
+                    axis.bindings.push(AxisBinding::Buttons {
+                        negative: InputSource::Key(Key::ArrowLeft),
+                        positive: InputSource::Key(Key::ArrowRight),
+                    });
+                    changed = true;
+                }
+                if ui.small_button("➕ Stick").clicked() {
+                    axis.bindings.push(AxisBinding::Analog {
+                        axis: GamepadAxis::LeftX,
+                        invert: false,
+                    });
+                    changed = true;
+                }
+            });
+        }
+        if let Some(name) = remove_axis {
+            map.axes.remove(&name);
+            changed = true;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_name);
+            let name = self.new_name.trim().to_owned();
+            let free = !name.is_empty()
+                && !map.actions.contains_key(&name)
+                && !map.axes.contains_key(&name);
+            if ui
+                .add_enabled(free, egui::Button::new("➕ Action"))
+                .clicked()
+            {
+                map.actions.insert(name.clone(), Action::default());
+                self.new_name.clear();
+                changed = true;
+            }
+            if ui.add_enabled(free, egui::Button::new("➕ Axis")).clicked() {
+                map.axes.insert(name, Axis::default());
+                self.new_name.clear();
+                changed = true;
+            }
+        });
+        changed
+    }
+}
+
+/// Applies a pressed chord to what was listening for it.
+fn bind(map: &mut InputMap, listen: Listen, chord: Chord) -> bool {
+    match listen {
+        Listen::Action(name) => match map.actions.get_mut(&name) {
+            Some(action) if !action.bindings.contains(&chord) => {
+                action.bindings.push(chord);
+                true
+            }
+            _ => false,
+        },
+        Listen::Axis {
+            axis,
+            binding,
+            positive,
+        } => {
+            // An axis side is a single input, so modifiers are dropped.
+            let Some(&key) = chord.0.last() else {
+                return false;
+            };
+            let binding = map
+                .axes
+                .get_mut(&axis)
+                .and_then(|a| a.bindings.get_mut(binding));
+            match binding {
+                Some(AxisBinding::Buttons {
+                    negative,
+                    positive: p,
+                }) => {
+                    *(if positive { p } else { negative }) = key;
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+}
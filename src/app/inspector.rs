@@ -0,0 +1,290 @@
+//! Inspector for the selected entity.
+//!
+//! Components are edited generically: each one is converted to a JSON value, shown with
+//! [`value_ui`] and converted back when something changed. This way a new component type
+//! only needs to derive `Serialize` and `Deserialize` to show up here.
+
+use egui::{DragValue, RichText, Ui};
+use serde_json::Value;
+
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::prefab::{self, Prefab};
+use crate::engine::scene::{Component, Entity, EntityId, Scene};
+
+/// Edits any JSON value in place, returns true if it was changed.
+pub fn value_ui(ui: &mut Ui, value: &mut Value) -> bool {
+    match value {
+        Value::Null => {
+            ui.weak("none");
+            false
+        }
+        Value::Bool(b) => ui.checkbox(b, "").changed(),
+        Value::Number(n) => {
+            if let Some(mut i) = n.as_i64() {
+                let changed = ui.add(DragValue::new(&mut i)).changed();
+                *n = i.into();
+                changed
+            } else {
+                let mut f = n.as_f64().unwrap_or_default();
+                let changed = ui.add(DragValue::new(&mut f).speed(0.1)).changed();
+                if let Some(new) = serde_json::Number::from_f64(f) {
+                    *n = new;
+                }
+                changed
+            }
+        }
+        Value::String(s) => ui.text_edit_singleline(s).changed(),
+        Value::Array(items) => {
+            let mut changed = false;
+            ui.vertical(|ui| {
+                for (i, item) in items.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| changed |= value_ui(ui, item));
+                }
+            });
+            changed
+        }
+        Value::Object(map) => {
+            // Small vectors like `{x, y}` are shown on a single line.
+            if map.len() <= 4 && map.values().all(Value::is_number) {
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    for (k, v) in map.iter_mut() {
+                        ui.label(k.as_str());
+                        changed |= value_ui(ui, v);
+                    }
+                });
+                return changed;
+            }
+            let mut changed = false;
+            egui::Grid::new(ui.next_auto_id())
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (k, v) in map.iter_mut() {
+                        ui.label(k.as_str());
+                        ui.push_id(k.as_str(), |ui| changed |= value_ui(ui, v));
+                        ui.end_row();
+                    }
+                });
+            changed
+        }
+    }
+}
+
+/// Label of a field, highlighted if the field overrides the prefab value.
+fn field_label(ui: &mut Ui, text: &str, overridden: bool) {
+    if overridden {
+        ui.label(
+            RichText::new(text)
+                .strong()
+                .color(ui.visuals().hyperlink_color),
+        )
+        .on_hover_text("Overrides the prefab value.");
+    } else {
+        ui.label(text);
+    }
+}
+
+/// Edits a component through its serialized form, returns true if it was changed.
+///
+/// Fields listed in `overrides` (as `<Component>.<field>`) are highlighted.
+pub fn component_ui(ui: &mut Ui, component: &mut Component, overrides: &[String]) -> bool {
+    // Tiles are painted in the viewport. Converting every cell to JSON each frame would make
+    // big maps slow, so the layers are set aside while the other fields are edited.
+    if let Component::Tilemap(tilemap) = component {
+        let layers = std::mem::take(&mut tilemap.layers);
+        let changed = fields_ui(ui, component, overrides);
+        if let Component::Tilemap(tilemap) = component {
+            tilemap.layers = layers;
+            ui.weak(format!(
+                "{} layers, painted with the 🧱 Tiles window.",
+                tilemap.layers.len()
+            ));
+        }
+        return changed;
+    }
+    fields_ui(ui, component, overrides)
+}
+
+fn fields_ui(ui: &mut Ui, component: &mut Component, overrides: &[String]) -> bool {
+    let type_name = component.type_name();
+    let Ok(Value::Object(mut map)) = serde_json::to_value(&*component) else {
+        return false;
+    };
+    // The `type` tag is not editable.
+    let tag = map.remove("type");
+    let mut changed = false;
+    egui::Grid::new(type_name).num_columns(2).show(ui, |ui| {
+        for (k, v) in map.iter_mut() {
+            let path = format!("{type_name}.{k}");
+            field_label(ui, k, overrides.contains(&path));
+            ui.push_id(k.as_str(), |ui| changed |= value_ui(ui, v));
+            ui.end_row();
+        }
+    });
+    if !changed {
+        return false;
+    }
+    if let Some(tag) = tag {
+        map.insert("type".to_owned(), tag);
+    }
+    match serde_json::from_value(Value::Object(map)) {
+        Ok(c) => {
+            *component = c;
+            true
+        }
+        Err(e) => {
+            log::warn!("Invalid {type_name} value: {e}");
+            false
+        }
+    }
+}
+
+/// Shows the name and components of an entity, returns true if anything was changed.
+pub fn entity_ui(ui: &mut Ui, entity: &mut Entity) -> bool {
+    let overrides = entity
+        .prefab
+        .as_ref()
+        .map(|l| l.overrides.clone())
+        .unwrap_or_default();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        field_label(ui, "Name", overrides.iter().any(|o| o == "name"));
+        changed |= ui.text_edit_singleline(&mut entity.name).changed();
+    });
+    ui.weak(format!("id {}", entity.id.0));
+    ui.separator();
+
+    let mut remove = None;
+    for (i, component) in entity.components.iter_mut().enumerate() {
+        let type_name = component.type_name();
+        let header = if overrides.iter().any(|o| o == type_name) {
+            RichText::new(type_name).color(ui.visuals().hyperlink_color)
+        } else {
+            RichText::new(type_name)
+        };
+        ui.push_id(i, |ui| {
+            egui::CollapsingHeader::new(header)
+                .default_open(true)
+                .show(ui, |ui| {
+                    changed |= component_ui(ui, component, &overrides);
+                    if ui.small_button("🗑 Remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+        });
+    }
+    if let Some(i) = remove {
+        entity.components.remove(i);
+        changed = true;
+    }
+
+    ui.menu_button("➕ Add component", |ui| {
+        for component in Component::all_defaults() {
+            if ui.button(component.type_name()).clicked() {
+                entity.components.push(component);
+                changed = true;
+                ui.close_menu();
+            }
+        }
+    });
+    changed
+}
+
+/// A path under `prefabs/` for a new prefab named after the entity, not used by another prefab.
+fn new_prefab_ref(assets: &Assets, name: &str) -> AssetRef {
+    let mut stem: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        stem = "prefab".to_owned();
+    }
+    let mut asset = AssetRef::new(format!("prefabs/{stem}.prefab.json"));
+    let mut n = 2;
+    while assets.prefabs.contains_key(&asset) || assets.sources.contains_key(&asset) {
+        asset = AssetRef::new(format!("prefabs/{stem} {n}.prefab.json"));
+        n += 1;
+    }
+    asset
+}
+
+/// Prefab actions for the selected entity: create a prefab from it, or apply/revert overrides.
+///
+/// Returns the prefab which was created or changed and needs to be saved.
+fn prefab_ui(
+    ui: &mut Ui,
+    scene: &mut Scene,
+    assets: &mut Assets,
+    id: EntityId,
+) -> Option<AssetRef> {
+    let entity = scene.find_mut(id)?;
+    let Some(link) = entity.prefab.clone() else {
+        if ui.button("Create prefab…").clicked() {
+            let asset = new_prefab_ref(assets, &entity.name);
+            let prefab = Prefab::create(asset.clone(), entity);
+            assets.prefabs.insert(asset.clone(), prefab);
+            return Some(asset);
+        }
+        return None;
+    };
+    ui.horizontal(|ui| {
+        ui.label("Prefab");
+        ui.monospace(link.asset.path());
+    });
+    let Some(prefab) = assets.prefabs.get_mut(&link.asset) else {
+        ui.colored_label(ui.visuals().warn_fg_color, "Prefab not loaded.");
+        return None;
+    };
+    let mut applied = false;
+    ui.horizontal(|ui| {
+        if ui
+            .button("Apply overrides")
+            .on_hover_text("Write the overrides of this instance into the prefab.")
+            .clicked()
+        {
+            prefab::apply_overrides(scene, id, prefab);
+            prefab::sync_instances(scene, &link.asset, prefab);
+            applied = true;
+        }
+        if ui
+            .button("Revert")
+            .on_hover_text("Drop the overrides of this instance.")
+            .clicked()
+        {
+            prefab::revert_overrides(scene, id, prefab);
+        }
+    });
+    applied.then_some(link.asset)
+}
+
+/// The inspector panel: prefab actions and components of the selected entity.
+///
+/// Returns the prefab to save when one was created or had overrides applied.
+pub fn inspector_ui(
+    ui: &mut Ui,
+    scene: &mut Scene,
+    assets: &mut Assets,
+    selected: Option<EntityId>,
+) -> Option<AssetRef> {
+    let Some(id) = selected.filter(|id| scene.find(*id).is_some()) else {
+        ui.weak("No entity selected.");
+        return None;
+    };
+    let saved = prefab_ui(ui, scene, assets, id);
+    ui.separator();
+    let entity = scene.find_mut(id)?;
+    let asset = entity.prefab.as_ref().map(|l| l.asset.clone());
+    if entity_ui(ui, entity) {
+        if let Some(prefab) = asset.and_then(|a| assets.prefabs.get(&a)) {
+            prefab::update_overrides(scene, id, prefab);
+        }
+    }
+    saved
+}
@@ -0,0 +1,400 @@
+//! Keyboard shortcuts of the editor commands, which the user can rebind.
+
+use egui::{Context, Event, Key, KeyboardShortcut, Modifiers, Response, Ui};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::dock::Panel;
+
+/// Key of the keymap in the eframe storage.
+pub const KEYMAP_KEY: &str = "keymap";
+
+/// Something the editor does which can be bound to a shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Command {
+    NewScene,
+    Open,
+    OpenProject,
+    SaveScene,
+    SaveProject,
+    ExportProject,
+    ExportGame,
+    Preferences,
+    Keymap,
+    Palette,
+    Quit,
+    Play,
+    RenameScene,
+    AddEntity,
+    Viewport,
+    Hierarchy,
+    Inspector,
+    Assets,
+    Console,
+    Mixer,
+    Profiler,
+    AnimationEditor,
+    TileEditor,
+    CodeEditor,
+    InputEditor,
+    ThemeEditor,
+    FontEditor,
+    Colliders,
+    Stats,
+}
+
+impl Command {
+    pub const ALL: [Command; 29] = [
+        Command::NewScene,
+        Command::Open,
+        Command::OpenProject,
+        Command::SaveScene,
+        Command::SaveProject,
+        Command::ExportProject,
+        Command::ExportGame,
+        Command::Preferences,
+        Command::Keymap,
+        Command::Palette,
+        Command::Quit,
+        Command::Play,
+        Command::RenameScene,
+        Command::AddEntity,
+        Command::Viewport,
+        Command::Hierarchy,
+        Command::Inspector,
+        Command::Assets,
+        Command::Console,
+        Command::Mixer,
+        Command::Profiler,
+        Command::AnimationEditor,
+        Command::TileEditor,
+        Command::CodeEditor,
+        Command::InputEditor,
+        Command::ThemeEditor,
+        Command::FontEditor,
+        Command::Colliders,
+        Command::Stats,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Command::NewScene => "New scene",
+            Command::Open => "Open file…",
+            Command::OpenProject => "🗂 Open project…",
+            Command::SaveScene => "Save scene…",
+            Command::SaveProject => "Save project…",
+            Command::ExportProject => "🗜 Export project as zip…",
+            Command::ExportGame => "📦 Export game…",
+            Command::Preferences => "⚙ Preferences…",
+            Command::Keymap => "⌨ Keyboard shortcuts…",
+            Command::Palette => "🔍 Command palette…",
+            Command::Quit => "Quit",
+            Command::Play => "▶ Play / ⏹ Stop",
+            Command::RenameScene => "Rename scene…",
+            Command::AddEntity => "Add entity…",
+            Command::AnimationEditor => "🎞 Animation",
+            Command::TileEditor => "🧱 Tiles",
+            Command::CodeEditor => "📝 Code",
+            Command::InputEditor => "🎮 Input",
+            Command::ThemeEditor => "🎨 Theme",
+            Command::FontEditor => "🗛 Fonts",
+            Command::Colliders => "⬚ Colliders",
+            Command::Stats => "Statistics overlay",
+            panel => panel
+                .panel()
+                .expect("the other commands are panels")
+                .title(),
+        }
+    }
+
+    /// The panel the command opens and closes.
+    pub fn panel(self) -> Option<Panel> {
+        Some(match self {
+            Command::Viewport => Panel::Viewport,
+            Command::Hierarchy => Panel::Hierarchy,
+            Command::Inspector => Panel::Inspector,
+            Command::Assets => Panel::Assets,
+            Command::Console => Panel::Console,
+            Command::Mixer => Panel::Mixer,
+            Command::Profiler => Panel::Profiler,
+            _ => return None,
+        })
+    }
+
+    pub fn for_panel(panel: Panel) -> Command {
+        let command = Command::ALL.into_iter().find(|c| c.panel() == Some(panel));
+        command.expect("every panel has a command")
+    }
+
+    /// What the user is asked for when running the command, for commands which need input.
+    pub fn prompt(self) -> Option<&'static str> {
+        match self {
+            Command::RenameScene => Some("New name of the scene"),
+            Command::AddEntity => Some("Name of the new entity"),
+            _ => None,
+        }
+    }
+
+    /// Whether the command does anything here. There is nothing to quit on the web.
+    pub fn available(self) -> bool {
+        !(cfg!(target_arch = "wasm32") && self == Command::Quit)
+    }
+
+    fn default_shortcut(self) -> Option<KeyboardShortcut> {
+        let (modifiers, key) = match self {
+            Command::NewScene => (Modifiers::COMMAND, Key::N),
+            Command::Open => (Modifiers::COMMAND, Key::O),
+            Command::SaveScene => (Modifiers::COMMAND, Key::S),
+            Command::SaveProject => (Modifiers::COMMAND | Modifiers::SHIFT, Key::S),
+            Command::Palette => (Modifiers::COMMAND | Modifiers::SHIFT, Key::P),
+            Command::Quit => (Modifiers::COMMAND, Key::Q),
+            Command::Play => (Modifiers::NONE, Key::F5),
+            Command::Inspector => (Modifiers::COMMAND, Key::I),
+            // egui has no backtick key.
+            Command::Console => (Modifiers::COMMAND, Key::J),
+            Command::Stats => (Modifiers::NONE, Key::F3),
+            _ => return None,
+        };
+        Some(KeyboardShortcut::new(modifiers, key))
+    }
+}
+
+/// A shortcut as saved, [`KeyboardShortcut`] can't be serialized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Binding {
+    modifiers: Modifiers,
+    key: Key,
+}
+
+impl From<KeyboardShortcut> for Binding {
+    fn from(shortcut: KeyboardShortcut) -> Self {
+        Self {
+            modifiers: shortcut.modifiers,
+            key: shortcut.key,
+        }
+    }
+}
+
+impl From<Binding> for KeyboardShortcut {
+    fn from(binding: Binding) -> Self {
+        KeyboardShortcut::new(binding.modifiers, binding.key)
+    }
+}
+
+/// The shortcut of each command, kept between runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    /// Shortcuts changed from the defaults, `None` for commands the user unbound.
+    #[serde(default)]
+    overrides: BTreeMap<Command, Option<Binding>>,
+}
+
+impl Keymap {
+    pub fn shortcut(&self, command: Command) -> Option<KeyboardShortcut> {
+        match self.overrides.get(&command) {
+            Some(binding) => binding.map(Into::into),
+            None => command.default_shortcut(),
+        }
+    }
+
+    pub fn set(&mut self, command: Command, shortcut: Option<KeyboardShortcut>) {
+        if shortcut == command.default_shortcut() {
+            self.overrides.remove(&command);
+        } else {
+            self.overrides.insert(command, shortcut.map(Into::into));
+        }
+    }
+
+    /// The other commands bound to the same shortcut.
+    pub fn conflicts(&self, command: Command) -> Vec<Command> {
+        let Some(shortcut) = self.shortcut(command) else {
+            return vec![];
+        };
+        Command::ALL
+            .into_iter()
+            .filter(|c| *c != command && self.shortcut(*c) == Some(shortcut))
+            .collect()
+    }
+
+    /// The shortcut of a command as shown to the user, if it has one.
+    pub fn hint(&self, ctx: &Context, command: Command) -> Option<String> {
+        Some(ctx.format_shortcut(&self.shortcut(command)?))
+    }
+
+    /// A menu button for the command, showing its shortcut.
+    pub fn button(&self, ui: &mut Ui, command: Command) -> Response {
+        let hint = self.hint(ui.ctx(), command).unwrap_or_default();
+        ui.add(egui::Button::new(command.title()).shortcut_text(hint))
+    }
+
+    /// Adds the shortcut of the command to the tooltip of a widget.
+    pub fn on_hover(&self, response: Response, command: Command) -> Response {
+        match self.hint(&response.ctx, command) {
+            Some(hint) => response.on_hover_text(hint),
+            None => response,
+        }
+    }
+
+    /// Commands whose shortcuts were pressed this frame, consuming the key presses.
+    ///
+    /// Call it after the UI, so focused widgets get the keys first. Keys which type something
+    /// are left to the text field with the focus.
+    pub fn pressed(&self, ctx: &Context) -> Vec<Command> {
+        let typing = ctx.wants_keyboard_input();
+        let mut pressed = vec![];
+        for command in Command::ALL.into_iter().filter(|c| c.available()) {
+            let Some(shortcut) = self.shortcut(command) else {
+                continue;
+            };
+            if typing && types(shortcut) {
+                continue;
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                pressed.push(command);
+            }
+        }
+        pressed
+    }
+}
+
+/// Whether a text field would take the shortcut as typing or editing.
+fn types(shortcut: KeyboardShortcut) -> bool {
+    let function_key = matches!(
+        shortcut.key,
+        Key::F1
+            | Key::F2
+            | Key::F3
+            | Key::F4
+            | Key::F5
+            | Key::F6
+            | Key::F7
+            | Key::F8
+            | Key::F9
+            | Key::F10
+            | Key::F11
+            | Key::F12
+            | Key::Escape
+    );
+    !function_key && !shortcut.modifiers.command && !shortcut.modifiers.alt
+}
+
+/// The first key pressed this frame with the modifiers held, taken out of the input.
+fn take_pressed_shortcut(ctx: &Context) -> Option<KeyboardShortcut> {
+    ctx.input_mut(|i| {
+        let index = i.events.iter().position(|e| {
+            matches!(
+                e,
+                Event::Key {
+                    pressed: true,
+                    repeat: false,
+                    ..
+                }
+            )
+        })?;
+        let Event::Key { key, modifiers, .. } = i.events.remove(index) else {
+            unreachable!("found above");
+        };
+        // Ctrl on Windows and Linux is Cmd on a Mac.
+        let modifiers = Modifiers {
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            command: modifiers.command,
+            ..Default::default()
+        };
+        Some(KeyboardShortcut::new(modifiers, key))
+    })
+}
+
+/// Window listing the commands, where their shortcuts are changed.
+#[derive(Default)]
+pub struct KeymapEditor {
+    pub open: bool,
+    /// The command bound to the next key pressed.
+    listening: Option<Command>,
+}
+
+impl KeymapEditor {
+    pub fn show(&mut self, ctx: &Context, keymap: &mut Keymap) {
+        if !self.open {
+            self.listening = None;
+        }
+        if let Some(command) = self.listening {
+            if let Some(shortcut) = take_pressed_shortcut(ctx) {
+                self.listening = None;
+                if shortcut.key != Key::Escape {
+                    keymap.set(command, Some(shortcut));
+                }
+            }
+        }
+        let mut open = self.open;
+        egui::Window::new("⌨ Keyboard shortcuts")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.ui(ui, keymap));
+            });
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut Ui, keymap: &mut Keymap) {
+        ui.weak("Click a shortcut, then press the new keys. Escape cancels.");
+        egui::Grid::new("keymap")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for command in Command::ALL.into_iter().filter(|c| c.available()) {
+                    ui.label(command.title());
+                    let active = self.listening == Some(command);
+                    let conflicts = keymap.conflicts(command);
+                    let text = if active {
+                        egui::RichText::new("Press keys…")
+                    } else {
+                        let hint = keymap.hint(ui.ctx(), command);
+                        egui::RichText::new(hint.unwrap_or_else(|| "—".to_owned()))
+                    };
+                    let text = if conflicts.is_empty() {
+                        text
+                    } else {
+                        text.color(ui.visuals().error_fg_color)
+                    };
+                    let mut response = ui.selectable_label(active, text);
+                    if !conflicts.is_empty() {
+                        let names: Vec<_> = conflicts.iter().map(|c| c.title()).collect();
+                        response =
+                            response.on_hover_text(format!("Also bound to {}.", names.join(", ")));
+                    }
+                    if response.clicked() {
+                        self.listening = if active { None } else { Some(command) };
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                keymap.shortcut(command).is_some(),
+                                egui::Button::new("✖").small(),
+                            )
+                            .on_hover_text("Remove the shortcut")
+                            .clicked()
+                        {
+                            keymap.set(command, None);
+                        }
+                        if ui
+                            .add_enabled(
+                                keymap.overrides.contains_key(&command),
+                                egui::Button::new("⟲").small(),
+                            )
+                            .on_hover_text("Reset to the default")
+                            .clicked()
+                        {
+                            keymap.overrides.remove(&command);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+        if ui.button("Reset all").clicked() {
+            *keymap = Keymap::default();
+            self.listening = None;
+        }
+    }
+}
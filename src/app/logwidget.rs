@@ -1,6 +1,6 @@
 use std::{collections::vec_deque::VecDeque, io::Write};
 
-use egui::{ScrollArea, Ui, Color32, RichText, TextEdit};
+use egui::{ScrollArea, Ui, Color32, TextEdit};
 use std::sync::mpsc::{channel, Receiver, Sender};
 
 const MAX_MESSAGES: usize = 256;
@@ -0,0 +1,21 @@
+use egui::{Slider, Ui};
+
+use crate::engine::audio::Audio;
+
+/// The volume of each bus of the mixer.
+pub fn mixer_ui(ui: &mut Ui, audio: &mut Audio) {
+    let mut mixer = audio.mixer.lock().unwrap();
+    ui.weak(format!(
+        "{} voices at {} Hz",
+        mixer.voice_count(),
+        mixer.sample_rate
+    ));
+    egui::Grid::new("buses").num_columns(3).show(ui, |ui| {
+        for bus in &mut mixer.buses {
+            ui.label(&bus.name);
+            ui.add(Slider::new(&mut bus.volume, 0.0..=1.0));
+            ui.toggle_value(&mut bus.muted, "🔇");
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,451 @@
+//! Overlay to find and run any editor command, or to jump to an asset or entity, by typing.
+
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Context, Key, Modifiers, Ui};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::keymap::{Command, Keymap};
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::scene::{EntityId, Scene};
+
+/// Key of the recently used items in the eframe storage.
+pub const PALETTE_KEY: &str = "palette";
+
+/// Number of recently used items remembered for the ranking.
+const RECENT: usize = 20;
+/// Number of matches listed.
+const MATCHES: usize = 50;
+
+/// The kinds of assets listed in the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Texture,
+    Atlas,
+    Tileset,
+    Clip,
+    Prefab,
+    Sound,
+    Script,
+    Font,
+}
+
+impl AssetKind {
+    fn name(self) -> &'static str {
+        match self {
+            AssetKind::Texture => "Texture",
+            AssetKind::Atlas => "Atlas",
+            AssetKind::Tileset => "Tileset",
+            AssetKind::Clip => "Clip",
+            AssetKind::Prefab => "Prefab",
+            AssetKind::Sound => "Sound",
+            AssetKind::Script => "Script",
+            AssetKind::Font => "Font",
+        }
+    }
+}
+
+/// Something the palette lists.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Command(Command),
+    Asset(AssetKind, AssetRef),
+    Entity(EntityId, String),
+}
+
+impl Item {
+    fn category(&self) -> &'static str {
+        match self {
+            Item::Command(c) if c.panel().is_some() => "Panel",
+            Item::Command(_) => "Command",
+            Item::Asset(kind, _) => kind.name(),
+            Item::Entity(..) => "Entity",
+        }
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            Item::Command(c) => c.title(),
+            Item::Asset(_, asset) => asset.path(),
+            Item::Entity(_, name) => name,
+        }
+    }
+
+    /// Identifies the item in the recently used list, across runs.
+    fn key(&self) -> String {
+        match self {
+            Item::Command(c) => format!("command:{c:?}"),
+            Item::Asset(_, asset) => format!("asset:{asset}"),
+            Item::Entity(id, _) => format!("entity:{}", id.0),
+        }
+    }
+}
+
+/// What the user picked in the palette.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Choice {
+    Item(Item),
+    /// A command which needs input, with the text the user entered.
+    Argument(Command, String),
+}
+
+/// Everything the palette can list: the commands, the loaded assets and the scene entities.
+pub fn items(assets: &Assets, scene: &Scene) -> Vec<Item> {
+    let mut items: Vec<_> = Command::ALL
+        .into_iter()
+        .filter(|c| c.available() && *c != Command::Palette)
+        .map(Item::Command)
+        .collect();
+    let mut add = |kind, refs: Vec<&AssetRef>| {
+        items.extend(refs.into_iter().map(|a| Item::Asset(kind, a.clone())));
+    };
+    add(AssetKind::Texture, assets.textures.keys().collect());
+    add(AssetKind::Atlas, assets.atlases.keys().collect());
+    add(AssetKind::Tileset, assets.tilesets.keys().collect());
+    add(AssetKind::Clip, assets.clips.keys().collect());
+    add(AssetKind::Prefab, assets.prefabs.keys().collect());
+    add(AssetKind::Sound, assets.sounds.keys().collect());
+    add(AssetKind::Script, assets.scripts.keys().collect());
+    add(AssetKind::Font, assets.fonts.keys().collect());
+    scene.walk(&mut |e| items.push(Item::Entity(e.id, e.name.clone())));
+    items
+}
+
+/// Scores how well `query` matches `text`, `None` if it doesn't.
+///
+/// The query characters have to appear in the text in order, ignoring case. Runs of
+/// consecutive characters, the start of words and the start of the text score higher.
+/// Also returns the indices of the matched characters, to highlight them.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    // Matching greedily from the first occurrence of the first character would miss better
+    // matches later on, like a word start, so every occurrence is tried.
+    let Some(first) = query.chars().find(|c| !c.is_whitespace()) else {
+        return match_from(query, text, 0);
+    };
+    text.char_indices()
+        .filter(|(_, c)| c.to_lowercase().eq(first.to_lowercase()))
+        .filter_map(|(start, _)| match_from(query, text, start))
+        .max_by_key(|(score, _)| *score)
+}
+
+/// Matches the query greedily, from the character at byte `start` on.
+fn match_from(query: &str, text: &str, start: usize) -> Option<(i32, Vec<usize>)> {
+    let mut score = 0;
+    let mut matched = vec![];
+    let mut chars = text.char_indices().skip_while(|(i, _)| *i < start);
+    let mut previous = text[..start].chars().next_back();
+    let mut last_match: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (i, c) = chars.next()?;
+            let before = previous.replace(c);
+            if !c.to_lowercase().eq(q.to_lowercase()) {
+                continue;
+            }
+            score += 1;
+            if i == 0 {
+                score += 8;
+            } else if before
+                .is_some_and(|b| !b.is_alphanumeric() || b.is_lowercase() && c.is_uppercase())
+            {
+                score += 6;
+            }
+            if last_match.is_some_and(|l| text[l..i].chars().count() == 1) {
+                score += 4;
+            }
+            last_match = Some(i);
+            matched.push(i);
+            break;
+        }
+    }
+    // Among equal matches, shorter texts are closer to what was typed.
+    score -= text.chars().count() as i32 / 8;
+    Some((score, matched))
+}
+
+/// The command palette, with the items the user picked lately.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    /// Keys of the recently used items, latest first.
+    recent: VecDeque<String>,
+    #[serde(skip)]
+    open: bool,
+    #[serde(skip)]
+    query: String,
+    /// Index of the highlighted match.
+    #[serde(skip)]
+    selected: usize,
+    /// The command whose argument is being entered, instead of searching.
+    #[serde(skip)]
+    prompt: Option<Command>,
+    /// Set when opened, to focus the text field and ignore the click which opened it.
+    #[serde(skip)]
+    opened: bool,
+}
+
+impl Palette {
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.open = false;
+        } else {
+            self.open_with(None);
+        }
+    }
+
+    /// Opens the palette to enter the argument of a command.
+    pub fn prompt(&mut self, command: Command) {
+        self.open_with(Some(command));
+    }
+
+    fn open_with(&mut self, prompt: Option<Command>) {
+        self.open = true;
+        self.opened = true;
+        self.query.clear();
+        self.selected = 0;
+        self.prompt = prompt;
+    }
+
+    fn used(&mut self, item: &Item) {
+        let key = item.key();
+        self.recent.retain(|k| *k != key);
+        self.recent.push_front(key);
+        self.recent.truncate(RECENT);
+    }
+
+    /// The items matching the query, best first.
+    fn matches(&self, items: Vec<Item>) -> Vec<(Item, Vec<usize>)> {
+        let recency = |item: &Item| {
+            let key = item.key();
+            let position = self.recent.iter().position(|k| *k == key);
+            position.map_or(0, |p| (RECENT - p) as i32)
+        };
+        let mut scored: Vec<_> = items
+            .into_iter()
+            .filter_map(|item| {
+                let (score, matched) = fuzzy_match(&self.query, item.title())?;
+                let score = if self.query.is_empty() {
+                    recency(&item)
+                } else {
+                    score + recency(&item) / 2
+                };
+                Some((score, item, matched))
+            })
+            .collect();
+        // Stable, so equal scores keep the order of the items.
+        scored.sort_by_key(|(score, ..)| -score);
+        scored.truncate(MATCHES);
+        scored.into_iter().map(|(_, i, m)| (i, m)).collect()
+    }
+
+    /// Shows the palette if it is open, returning what the user picked.
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        keymap: &Keymap,
+        assets: &Assets,
+        scene: &Scene,
+    ) -> Option<Choice> {
+        if !self.open {
+            return None;
+        }
+        let matches = match self.prompt {
+            Some(_) => vec![],
+            None => self.matches(items(assets, scene)),
+        };
+        let opened = std::mem::take(&mut self.opened);
+        let key = |key| ctx.input_mut(|i| i.consume_key(Modifiers::NONE, key));
+        if key(Key::Escape) {
+            self.open = false;
+            return None;
+        }
+        let mut moved = false;
+        if key(Key::ArrowDown) {
+            self.selected += 1;
+            moved = true;
+        }
+        if key(Key::ArrowUp) {
+            self.selected = self.selected.saturating_sub(1);
+            moved = true;
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+        let mut choice = None;
+        if key(Key::Enter) {
+            choice = self.choose(&matches);
+        }
+
+        let response = egui::Window::new("Command palette")
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+            .fixed_size([480.0, 0.0])
+            .show(ctx, |ui| {
+                let focus = opened || self.opened;
+                if let Some(clicked) = self.ui(ui, keymap, &matches, focus, moved) {
+                    self.selected = clicked;
+                    choice = self.choose(&matches);
+                }
+            });
+        let clicked_elsewhere = response.is_some_and(|r| r.response.clicked_elsewhere());
+        if clicked_elsewhere && !opened {
+            self.open = false;
+        }
+        choice
+    }
+
+    fn choose(&mut self, matches: &[(Item, Vec<usize>)]) -> Option<Choice> {
+        let choice = match self.prompt {
+            Some(command) => {
+                let text = self.query.trim();
+                if text.is_empty() {
+                    return None;
+                }
+                Choice::Argument(command, text.to_owned())
+            }
+            None => {
+                let (item, _) = matches.get(self.selected)?;
+                self.used(item);
+                match item {
+                    Item::Command(command) if command.prompt().is_some() => {
+                        self.open_with(Some(*command));
+                        return None;
+                    }
+                    item => Choice::Item(item.clone()),
+                }
+            }
+        };
+        self.open = false;
+        Some(choice)
+    }
+
+    /// Returns the index of a clicked match.
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        keymap: &Keymap,
+        matches: &[(Item, Vec<usize>)],
+        focus: bool,
+        moved: bool,
+    ) -> Option<usize> {
+        let hint = match self.prompt {
+            Some(command) => command.prompt().unwrap_or_default(),
+            None => "Search commands, assets and entities",
+        };
+        let edit = egui::TextEdit::singleline(&mut self.query)
+            .hint_text(hint)
+            .desired_width(f32::INFINITY);
+        let response = ui.add(edit);
+        if focus {
+            response.request_focus();
+        }
+        if response.changed() {
+            self.selected = 0;
+        }
+        if let Some(command) = self.prompt {
+            ui.weak(format!(
+                "{}: Enter to confirm, Escape to cancel.",
+                command.title()
+            ));
+            return None;
+        }
+        if matches.is_empty() {
+            ui.weak("Nothing found.");
+            return None;
+        }
+        let mut clicked = None;
+        egui::ScrollArea::vertical()
+            .max_height(320.0)
+            .show(ui, |ui| {
+                for (i, (item, matched)) in matches.iter().enumerate() {
+                    let shortcut = match item {
+                        Item::Command(command) => keymap.hint(ui.ctx(), *command),
+                        _ => None,
+                    };
+                    let button = egui::Button::new(highlighted(ui, item, matched))
+                        .shortcut_text(shortcut.unwrap_or_default())
+                        .selected(i == self.selected)
+                        .frame(false)
+                        .min_size(egui::vec2(ui.available_width(), 0.0));
+                    let response = ui.add(button);
+                    if moved && i == self.selected {
+                        response.scroll_to_me(None);
+                    }
+                    if response.clicked() {
+                        clicked = Some(i);
+                    }
+                }
+            });
+        clicked
+    }
+}
+
+/// The category and title of an item, with the characters matching the query highlighted.
+fn highlighted(ui: &Ui, item: &Item, matched: &[usize]) -> LayoutJob {
+    let font_id = egui::TextStyle::Button.resolve(ui.style());
+    let visuals = ui.visuals();
+    let plain = TextFormat::simple(font_id.clone(), visuals.text_color());
+    let mut job = LayoutJob::default();
+    job.append(
+        &format!("{}  ", item.category()),
+        0.0,
+        TextFormat::simple(font_id, visuals.weak_text_color()),
+    );
+    let highlight = TextFormat {
+        color: visuals.strong_text_color(),
+        underline: egui::Stroke::new(1.0_f32, visuals.strong_text_color()),
+        ..plain.clone()
+    };
+    for (i, c) in item.title().char_indices() {
+        let format = if matched.contains(&i) {
+            highlight.clone()
+        } else {
+            plain.clone()
+        };
+        job.append(&c.to_string(), 0.0, format);
+    }
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(query: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(query, text).map(|(_, matched)| matched)
+    }
+
+    fn score(query: &str, text: &str) -> i32 {
+        fuzzy_match(query, text).expect("a match").0
+    }
+
+    #[test]
+    fn characters_match_in_order_ignoring_case() {
+        assert_eq!(indices("SP", "Save project"), Some(vec![0, 5]));
+        assert_eq!(indices("s p", "Save project"), Some(vec![0, 5]));
+        assert_eq!(indices("ps", "Save project"), None);
+        assert_eq!(indices("xyz", "Save project"), None);
+        // Indices are byte offsets into the text.
+        assert_eq!(indices("fé", "Café"), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn empty_queries_match_everything() {
+        assert_eq!(indices("", "Save project"), Some(vec![]));
+        assert_eq!(indices("  ", "Save project"), Some(vec![]));
+    }
+
+    #[test]
+    fn word_starts_beat_the_first_occurrence() {
+        assert_eq!(
+            indices("pro", "Open recent project"),
+            Some(vec![12, 13, 14])
+        );
+        assert_eq!(indices("pt", "playTest"), Some(vec![0, 4]));
+    }
+
+    #[test]
+    fn better_matches_score_higher() {
+        assert!(score("sa", "Save") > score("sa", "Disable"));
+        assert!(score("save", "Save") > score("save", "Save the project as a copy"));
+        assert!(score("op", "Open project") > score("op", "Stop"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Settings of the editor itself, kept between runs rather than in the project.
+
+use egui::{style::Spacing, Context, Slider, TextStyle, Ui};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::keymap::{Command, Keymap};
+use super::stats_overlay::Corner;
+use super::style::base_style;
+use super::theme::Theme;
+
+/// Key of the preferences in the eframe storage.
+pub const PREFERENCES_KEY: &str = "preferences";
+
+/// How tightly widgets are packed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Density {
+    /// Smaller gaps and widgets, for small screens.
+    Compact,
+    /// The spacing of the theme.
+    #[default]
+    Comfortable,
+}
+
+impl Density {
+    pub fn spacing(self, theme: &Spacing) -> Spacing {
+        let mut spacing = theme.clone();
+        if self == Density::Compact {
+            spacing.item_spacing *= 0.5;
+            spacing.button_padding *= 0.5;
+            spacing.interact_size.y *= 0.7;
+            spacing.indent *= 0.75;
+            for margin in [&mut spacing.window_margin, &mut spacing.menu_margin] {
+                *margin = egui::style::Margin {
+                    left: margin.left * 0.5,
+                    right: margin.right * 0.5,
+                    top: margin.top * 0.5,
+                    bottom: margin.bottom * 0.5,
+                };
+            }
+        }
+        spacing
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// Size of the UI relative to what the OS asks for, the same as Ctrl + and Ctrl −.
+    pub scale: f32,
+    pub text_sizes: BTreeMap<TextStyle, f32>,
+    pub density: Density,
+    /// Show frame rate, scene and memory statistics over the viewport.
+    pub stats_overlay: bool,
+    pub stats_corner: Corner,
+    /// Zoom factor of the last frame, to notice zooming with the keyboard.
+    #[serde(skip)]
+    zoom: Option<f32>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            text_sizes: base_style()
+                .text_styles
+                .into_iter()
+                .map(|(style, font)| (style, font.size))
+                .collect(),
+            density: Density::default(),
+            stats_overlay: false,
+            stats_corner: Corner::default(),
+            zoom: None,
+        }
+    }
+}
+
+impl Preferences {
+    /// Applies the scale on the first frame, then picks up changes made with Ctrl + and Ctrl −.
+    pub fn sync_zoom(&mut self, ctx: &Context) {
+        let zoom = ctx.zoom_factor();
+        match self.zoom {
+            None => ctx.set_zoom_factor(self.scale),
+            Some(old) if old != zoom => self.scale = zoom,
+            Some(_) => {}
+        }
+        self.zoom = Some(zoom);
+    }
+
+    /// Applies the text sizes and density on top of the theme.
+    pub fn apply(&self, ctx: &Context, theme: &Theme) {
+        ctx.style_mut(|style| {
+            for (text_style, font) in &mut style.text_styles {
+                if let Some(size) = self.text_sizes.get(text_style) {
+                    font.size = *size;
+                }
+            }
+            style.spacing = self.density.spacing(&theme.spacing);
+        });
+    }
+}
+
+/// Window with the preferences, returns true if the style has to be applied again.
+pub fn preferences_window(
+    ctx: &Context,
+    open: &mut bool,
+    preferences: &mut Preferences,
+    keymap: &Keymap,
+) -> bool {
+    let mut changed = false;
+    egui::Window::new("⚙ Preferences")
+        .open(open)
+        .default_width(320.0)
+        .show(ctx, |ui| changed = preferences_ui(ui, preferences, keymap));
+    changed
+}
+
+fn preferences_ui(ui: &mut Ui, preferences: &mut Preferences, keymap: &Keymap) -> bool {
+    let mut changed = false;
+    egui::Grid::new("preferences")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("UI scale");
+            ui.horizontal(|ui| {
+                let scale = Slider::new(&mut preferences.scale, 0.5..=3.0).step_by(0.05);
+                let response = ui.add(scale);
+                // Scaling while dragging would move the slider away from the pointer.
+                if response.drag_released() || (response.changed() && !response.dragged()) {
+                    ui.ctx().set_zoom_factor(preferences.scale);
+                }
+                if ui.button("Reset").clicked() {
+                    preferences.scale = 1.0;
+                    ui.ctx().set_zoom_factor(1.0);
+                }
+            })
+            .response
+            .on_hover_text("Ctrl + and Ctrl − change it too.");
+            ui.end_row();
+
+            ui.label("Density");
+            ui.horizontal(|ui| {
+                for (density, label) in [
+                    (Density::Compact, "Compact"),
+                    (Density::Comfortable, "Comfortable"),
+                ] {
+                    changed |= ui
+                        .radio_value(&mut preferences.density, density, label)
+                        .changed();
+                }
+            });
+            ui.end_row();
+
+            ui.label("Statistics");
+            ui.horizontal(|ui| {
+                let response = ui.checkbox(&mut preferences.stats_overlay, "Show");
+                keymap.on_hover(response, Command::Stats);
+                egui::ComboBox::from_id_source("stats corner")
+                    .selected_text(preferences.stats_corner.name())
+                    .show_ui(ui, |ui| {
+                        for corner in Corner::ALL {
+                            ui.selectable_value(
+                                &mut preferences.stats_corner,
+                                corner,
+                                corner.name(),
+                            );
+                        }
+                    });
+            });
+            ui.end_row();
+
+            for (text_style, size) in &mut preferences.text_sizes {
+                ui.label(format!("{text_style} text"));
+                changed |= ui
+                    .add(Slider::new(size, 8.0..=40.0).suffix(" pt"))
+                    .changed();
+                ui.end_row();
+            }
+        });
+    ui.separator();
+    if ui.button("Reset to defaults").clicked() {
+        *preferences = Preferences {
+            zoom: preferences.zoom,
+            ..Default::default()
+        };
+        ui.ctx().set_zoom_factor(preferences.scale);
+        changed = true;
+    }
+    changed
+}
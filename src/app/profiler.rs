@@ -0,0 +1,209 @@
+use egui::{Color32, Rect, Sense, Stroke, Ui, Vec2};
+
+use super::save_file;
+use crate::engine::profiler::{self, FrameRecord, Profiler, Stats, HISTORY};
+
+const CHART_HEIGHT: f32 = 80.0;
+const ROW_HEIGHT: f32 = 18.0;
+
+/// Frame times of the editor and the game, broken down by the scopes they were spent in.
+#[derive(Default)]
+pub struct ProfilerPanel {
+    /// Frame shown in detail, the latest one if `None`.
+    selected: Option<u64>,
+}
+
+impl ProfilerPanel {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        profiler::with(|p| {
+            egui::ScrollArea::vertical().show(ui, |ui| self.profiler_ui(ui, p));
+        });
+    }
+
+    fn profiler_ui(&mut self, ui: &mut Ui, p: &mut Profiler) {
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut p.paused, "⏸ Pause")
+                .on_hover_text("Stop recording, to look at the frames.");
+            let mut threshold = p.spike_threshold * 1000.0;
+            ui.label("Spikes over");
+            let drag = egui::DragValue::new(&mut threshold)
+                .clamp_range(1.0..=1000.0)
+                .suffix(" ms");
+            if ui.add(drag).changed() {
+                p.spike_threshold = threshold / 1000.0;
+            }
+            if ui.button("🗑 Clear").clicked() {
+                p.clear();
+                self.selected = None;
+            }
+            if ui
+                .add_enabled(!p.frames().is_empty(), egui::Button::new("⬇ Export…"))
+                .on_hover_text(
+                    "Save the frames as a Chrome trace, for chrome://tracing or Perfetto.",
+                )
+                .clicked()
+            {
+                let trace = profiler::chrome_trace(p.frames());
+                save_file("profile.trace.json".to_owned(), trace.into_bytes());
+            }
+        });
+
+        let Some(stats) = Stats::new(p.frames()) else {
+            ui.weak("No frames recorded yet.");
+            return;
+        };
+        ui.horizontal_wrapped(|ui| {
+            for (label, seconds) in [
+                ("min", stats.min),
+                ("avg", stats.avg),
+                ("max", stats.max),
+                ("p50", stats.p50),
+                ("p95", stats.p95),
+                ("p99", stats.p99),
+            ] {
+                ui.weak(label);
+                ui.monospace(format!("{:.2} ms", seconds * 1000.0));
+            }
+        });
+        history_chart(ui, p, &stats, &mut self.selected);
+
+        if !p.spikes().is_empty() {
+            ui.collapsing(format!("Spikes ({})", p.spikes().len()), |ui| {
+                for frame in p.spikes().iter().rev() {
+                    let label = format!("Frame {}: {:.2} ms", frame.index, frame.duration * 1000.0);
+                    let selected = self.selected == Some(frame.index);
+                    if ui.selectable_label(selected, label).clicked() {
+                        self.selected = Some(frame.index);
+                    }
+                }
+                if ui.button("⬇ Export spikes…").clicked() {
+                    let trace = profiler::chrome_trace(p.spikes());
+                    save_file("spikes.trace.json".to_owned(), trace.into_bytes());
+                }
+            });
+        }
+
+        let latest = p.frames().back();
+        let Some(frame) = self.selected.and_then(|i| p.frame(i)).or(latest) else {
+            return;
+        };
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.strong(format!(
+                "Frame {}: {:.2} ms",
+                frame.index,
+                frame.duration * 1000.0
+            ));
+            if self.selected.is_some() && ui.small_button("Latest").clicked() {
+                self.selected = None;
+            }
+        });
+        flame_graph(ui, frame);
+        egui::Grid::new("breakdown")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, seconds) in frame.breakdown() {
+                    ui.label(name);
+                    ui.monospace(format!("{:.3} ms", seconds * 1000.0));
+                    ui.weak(format!("{:.0}%", seconds / frame.duration * 100.0));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Frame times as bars, newest on the right. Clicking a bar selects its frame.
+fn history_chart(ui: &mut Ui, p: &Profiler, stats: &Stats, selected: &mut Option<u64>) {
+    let size = Vec2::new(ui.available_width(), CHART_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    // A single huge spike would flatten all other bars, those are cut off instead.
+    let top = (stats.p99 * 1.5).max(p.spike_threshold * 1.25);
+    let y = |seconds: f64| rect.bottom() - (seconds / top) as f32 * rect.height();
+    let width = rect.width() / HISTORY as f32;
+    let frames = p.frames();
+    let x = |i: usize| rect.right() - (frames.len() - i) as f32 * width;
+    for (i, frame) in frames.iter().enumerate() {
+        let color = if Some(frame.index) == *selected {
+            visuals.selection.bg_fill
+        } else if frame.duration > p.spike_threshold {
+            visuals.error_fg_color
+        } else {
+            visuals.text_color().gamma_multiply(0.6)
+        };
+        let bar = Rect::from_x_y_ranges(x(i)..=x(i) + width, y(frame.duration)..=rect.bottom());
+        painter.rect_filled(bar, 0.0, color);
+    }
+    let threshold = Stroke::new(1.0_f32, visuals.warn_fg_color.gamma_multiply(0.6));
+    painter.hline(rect.x_range(), y(p.spike_threshold), threshold);
+
+    let Some(pos) = response.hover_pos() else {
+        return;
+    };
+    let i = ((pos.x - rect.right()) / width).floor() as isize + frames.len() as isize;
+    let Some(frame) = usize::try_from(i).ok().and_then(|i| frames.get(i)) else {
+        return;
+    };
+    if response.clicked() {
+        *selected = Some(frame.index);
+    }
+    response.on_hover_text_at_pointer(format!(
+        "Frame {}: {:.2} ms",
+        frame.index,
+        frame.duration * 1000.0
+    ));
+}
+
+/// The scopes of a frame, nested scopes below the scope they are in.
+fn flame_graph(ui: &mut Ui, frame: &FrameRecord) {
+    let depth = frame.scopes.iter().map(|s| s.depth + 1).max().unwrap_or(0);
+    let size = Vec2::new(ui.available_width(), depth.max(1) as f32 * ROW_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let scale = rect.width() as f64 / frame.duration.max(f64::EPSILON);
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    let mut hovered = None;
+    for scope in &frame.scopes {
+        let min = rect.min
+            + Vec2::new(
+                (scope.start * scale) as f32,
+                scope.depth as f32 * ROW_HEIGHT,
+            );
+        let size = Vec2::new((scope.duration * scale).max(1.0) as f32, ROW_HEIGHT - 1.0);
+        let bar = Rect::from_min_size(min, size);
+        painter.rect_filled(bar, 2.0, scope_color(scope.name));
+        if bar.width() > 24.0 {
+            painter.with_clip_rect(bar.intersect(rect)).text(
+                bar.left_center() + Vec2::new(4.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                scope.name,
+                font.clone(),
+                Color32::BLACK,
+            );
+        }
+        if response.hover_pos().is_some_and(|pos| bar.contains(pos)) {
+            hovered = Some(scope);
+        }
+    }
+    if let Some(scope) = hovered {
+        response.on_hover_text_at_pointer(format!(
+            "{}: {:.3} ms ({:.0}%)",
+            scope.name,
+            scope.duration * 1000.0,
+            scope.duration / frame.duration * 100.0
+        ));
+    }
+}
+
+/// A stable color for each scope name, so a system looks the same in every frame.
+fn scope_color(name: &str) -> Color32 {
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.45, 0.85, 1.0).into()
+}
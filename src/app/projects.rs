@@ -0,0 +1,341 @@
+//! Whole projects as zip files, to hand them between the web and the native editor, and the
+//! projects the web editor keeps in the browser.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use zip::result::ZipResult;
+
+pub const ZIP_EXTENSION: &str = ".zip";
+
+/// Packs files into a zip file, by their path.
+pub fn zip_files(files: &BTreeMap<String, Vec<u8>>) -> ZipResult<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (path, data) in files {
+        zip.start_file(path.as_str(), options)?;
+        zip.write_all(data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Unpacks a zip file. When all files are in one folder, as in a zipped project folder, the
+/// paths start inside it. Hidden files and paths leading out of the folder are left out.
+pub fn unzip_files(data: &[u8]) -> ZipResult<BTreeMap<String, Vec<u8>>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(parts) = file.enclosed_name().map(|p| {
+            p.iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        }) else {
+            continue;
+        };
+        // macOS adds its own folder to the zip files it makes.
+        let hidden = parts.iter().any(|p| p.starts_with('.') || p == "__MACOSX");
+        if file.is_dir() || hidden {
+            continue;
+        }
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        files.insert(parts.join("/"), data);
+    }
+    let folder = files.keys().next().and_then(|p| p.split_once('/'));
+    let folder = folder.map(|(folder, _)| format!("{folder}/"));
+    if let Some(folder) = folder.filter(|f| files.keys().all(|p| p.starts_with(f.as_str()))) {
+        files = files
+            .into_iter()
+            .map(|(path, data)| (path[folder.len()..].to_owned(), data))
+            .collect();
+    }
+    Ok(files)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::ProjectList;
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use egui::{Context, RichText};
+    use log::{error, info};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::sync::mpsc;
+
+    use crate::engine::project::{Project, PROJECT_FILE};
+    use crate::engine::vfs::{self, FileSystem, IndexedDb};
+
+    /// The IndexedDB database of the projects. The paths of the files of a project start with its
+    /// name.
+    const DATABASE: &str = "archaic_engine";
+
+    enum Update {
+        Opened(IndexedDb),
+        Projects(Vec<String>),
+        /// A project was created or imported, and is opened.
+        Created(String),
+    }
+
+    /// The projects kept in the browser, shown on startup to pick one.
+    pub struct ProjectList {
+        pub open: bool,
+        db: Option<IndexedDb>,
+        projects: Vec<String>,
+        /// The project being edited.
+        current: Option<String>,
+        new_name: String,
+        /// Project the user asked to delete, until they confirm.
+        delete: Option<String>,
+        updates: mpsc::Receiver<Update>,
+        sender: mpsc::Sender<Update>,
+    }
+
+    /// The names of the projects, from the paths of their files.
+    async fn project_names(db: &IndexedDb) -> Vec<String> {
+        match db.list().await {
+            Ok(files) => {
+                let names: BTreeSet<String> = files
+                    .iter()
+                    .filter_map(|p| p.split_once('/'))
+                    .map(|(name, _)| name.to_owned())
+                    .collect();
+                names.into_iter().collect()
+            }
+            Err(e) => {
+                error!("Failed to list the projects: {e}");
+                vec![]
+            }
+        }
+    }
+
+    impl ProjectList {
+        /// Opens the database and lists the projects in it.
+        pub fn new(ctx: &Context) -> Self {
+            let (sender, updates) = mpsc::channel();
+            let (tx, ctx) = (sender.clone(), ctx.clone());
+            vfs::spawn(async move {
+                match IndexedDb::open(DATABASE, "").await {
+                    Ok(db) => {
+                        let projects = project_names(&db).await;
+                        let _ = tx.send(Update::Opened(db));
+                        let _ = tx.send(Update::Projects(projects));
+                    }
+                    Err(e) => error!("Projects can't be kept in this browser: {e}"),
+                }
+                ctx.request_repaint();
+            });
+            Self {
+                open: true,
+                db: None,
+                projects: vec![],
+                current: None,
+                new_name: String::new(),
+                delete: None,
+                updates,
+                sender,
+            }
+        }
+
+        /// A name like `name` which no project has yet. Slashes would split the name.
+        fn unused_name(&self, name: &str) -> String {
+            let name = name.trim().replace('/', "-");
+            let name = if name.is_empty() { "Untitled" } else { &name };
+            let taken = |n: &String| self.projects.contains(n);
+            let mut candidate = name.to_owned();
+            for i in 2.. {
+                if !taken(&candidate) {
+                    break;
+                }
+                candidate = format!("{name} ({i})");
+            }
+            candidate
+        }
+
+        /// Stores files as a new project and opens it.
+        pub fn create(&mut self, ctx: &Context, name: &str, mut files: BTreeMap<String, Vec<u8>>) {
+            let Some(db) = self.db.clone() else {
+                error!("Projects can't be kept in this browser.");
+                return;
+            };
+            let name = self.unused_name(name);
+            files.entry(PROJECT_FILE.to_owned()).or_insert_with(|| {
+                let project = Project {
+                    name: name.clone(),
+                    ..Default::default()
+                };
+                project.save().into_bytes()
+            });
+            self.projects.push(name.clone());
+            self.projects.sort();
+            let (tx, ctx) = (self.sender.clone(), ctx.clone());
+            vfs::spawn(async move {
+                let project = db.with_prefix(&format!("{name}/"));
+                for (path, data) in files {
+                    if let Err(e) = project.write(&path, data).await {
+                        error!("Failed to store {path} of {name}: {e}");
+                    }
+                }
+                info!("Created project {name}.");
+                let _ = tx.send(Update::Created(name));
+                ctx.request_repaint();
+            });
+        }
+
+        /// Deletes a project. The open one is kept, the editor would store its files again.
+        fn remove(&mut self, ctx: &Context, name: String) {
+            let Some(db) = self.db.clone() else {
+                return;
+            };
+            if self.current.as_ref() == Some(&name) {
+                error!("Open another project before deleting {name}.");
+                return;
+            }
+            self.projects.retain(|p| *p != name);
+            let (tx, ctx) = (self.sender.clone(), ctx.clone());
+            vfs::spawn(async move {
+                let project = db.with_prefix(&format!("{name}/"));
+                let files = project.list().await.unwrap_or_default();
+                for path in files {
+                    if let Err(e) = project.remove(&path).await {
+                        error!("Failed to delete {path} of {name}: {e}");
+                    }
+                }
+                info!("Deleted project {name}.");
+                let _ = tx.send(Update::Projects(project_names(&db).await));
+                ctx.request_repaint();
+            });
+        }
+
+        /// Returns the project to open, by its name and its files.
+        pub fn show(&mut self, ctx: &Context) -> Option<(String, IndexedDb)> {
+            let (mut open, mut create, mut remove) = (None, None, None);
+            while let Ok(update) = self.updates.try_recv() {
+                match update {
+                    Update::Opened(db) => self.db = Some(db),
+                    Update::Projects(projects) => self.projects = projects,
+                    Update::Created(name) => open = Some(name),
+                }
+            }
+            let mut window_open = self.open;
+            egui::Window::new("🗂 Projects")
+                .open(&mut window_open)
+                .collapsible(false)
+                .default_width(280.0)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    if self.db.is_none() {
+                        ui.label("Projects are kept in this browser once its storage is open.");
+                        return;
+                    }
+                    if self.projects.is_empty() {
+                        ui.weak("No projects yet.");
+                    }
+                    for name in &self.projects {
+                        ui.horizontal(|ui| {
+                            let current = self.current.as_ref() == Some(name);
+                            if ui.selectable_label(current, name).clicked() {
+                                open = Some(name.clone());
+                            }
+                            if self.delete.as_ref() == Some(name) {
+                                ui.label(
+                                    RichText::new("Delete?").color(ui.visuals().warn_fg_color),
+                                );
+                                if ui.small_button("Yes").clicked() {
+                                    remove = Some(name.clone());
+                                }
+                                if ui.small_button("No").clicked() {
+                                    self.delete = None;
+                                }
+                            } else if ui
+                                .add_enabled(!current, egui::Button::new("🗑").small())
+                                .on_hover_text("Delete the project from this browser.")
+                                .on_disabled_hover_text("The open project can't be deleted.")
+                                .clicked()
+                            {
+                                self.delete = Some(name.clone());
+                            }
+                        });
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.new_name)
+                            .on_hover_text("Name of the new project.");
+                        if ui.button("➕ New").clicked() {
+                            create = Some(std::mem::take(&mut self.new_name));
+                        }
+                    });
+                    ui.label("Open a project zip file with File → Open file… to add it here.");
+                });
+            self.open = window_open;
+            if let Some(name) = remove {
+                self.delete = None;
+                self.remove(ctx, name);
+            }
+            if let Some(name) = create {
+                self.create(ctx, &name, BTreeMap::new());
+            }
+            let name = open?;
+            let db = self.db.as_ref()?;
+            let files = db.with_prefix(&format!("{name}/"));
+            self.current = Some(name.clone());
+            self.open = false;
+            Some((name, files))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(paths: &[&str]) -> BTreeMap<String, Vec<u8>> {
+        paths
+            .iter()
+            .map(|p| (p.to_string(), p.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// A zip file with the given raw entry names, which [`zip_files`] would write as well.
+    fn zip_raw(paths: &[&str]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        for path in paths {
+            zip.start_file(*path, Default::default()).unwrap();
+            zip.write_all(path.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn files_round_trip() {
+        let project = files(&["project.json", "main.scene.json", "sprites/hero.png"]);
+        let zip = zip_files(&project).unwrap();
+        assert_eq!(unzip_files(&zip).unwrap(), project);
+    }
+
+    #[test]
+    fn a_common_folder_is_stripped() {
+        let zip = zip_files(&files(&["game/project.json", "game/sprites/hero.png"])).unwrap();
+        let unzipped = unzip_files(&zip).unwrap();
+        let paths: Vec<_> = unzipped.keys().map(String::as_str).collect();
+        assert_eq!(paths, ["project.json", "sprites/hero.png"]);
+        assert_eq!(unzipped["project.json"], b"game/project.json");
+        // Files in different folders keep their paths.
+        let zip = zip_files(&files(&["a/x.png", "b/y.png"])).unwrap();
+        assert_eq!(unzip_files(&zip).unwrap(), files(&["a/x.png", "b/y.png"]));
+    }
+
+    #[test]
+    fn hidden_and_escaping_paths_are_left_out() {
+        let zip = zip_raw(&[
+            "game/project.json",
+            "game/.git/config",
+            "__MACOSX/game/._project.json",
+            "../outside.txt",
+            "/etc/passwd",
+        ]);
+        let unzipped = unzip_files(&zip).unwrap();
+        let paths: Vec<_> = unzipped.keys().map(String::as_str).collect();
+        assert_eq!(paths, ["project.json"]);
+    }
+}
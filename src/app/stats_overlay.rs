@@ -0,0 +1,152 @@
+use egui::{Align, Rect, RichText, Sense, Ui, Vec2};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::engine::alloc;
+use crate::engine::assets::Assets;
+use crate::engine::render::DrawStats;
+use crate::engine::scene::Scene;
+
+/// Number of frames in the frame time graph.
+const FRAMES: usize = 120;
+
+/// Corner of the viewport the overlay is shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "Top left",
+            Corner::TopRight => "Top right",
+            Corner::BottomLeft => "Bottom left",
+            Corner::BottomRight => "Bottom right",
+        }
+    }
+
+    fn layout(self) -> egui::Layout {
+        match self {
+            Corner::TopLeft => egui::Layout::top_down(Align::Min),
+            Corner::TopRight => egui::Layout::top_down(Align::Max),
+            Corner::BottomLeft => egui::Layout::bottom_up(Align::Min),
+            Corner::BottomRight => egui::Layout::bottom_up(Align::Max),
+        }
+    }
+}
+
+/// Frame rate, scene and memory statistics shown over the viewport.
+#[derive(Default)]
+pub struct StatsOverlay {
+    /// Seconds between the last frames, oldest first.
+    frame_times: VecDeque<f32>,
+}
+
+impl StatsOverlay {
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        rect: Rect,
+        corner: Corner,
+        scene: &Scene,
+        assets: &Assets,
+        draw: &DrawStats,
+    ) {
+        if self.frame_times.len() == FRAMES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(ui.input(|i| i.unstable_dt));
+
+        let mut ui = ui.child_ui(rect.shrink(8.0), corner.layout());
+        egui::Frame::popup(ui.style())
+            .multiply_with_opacity(0.85)
+            .show(&mut ui, |ui| {
+                ui.style_mut().override_text_style = Some(egui::TextStyle::Small);
+                self.stats_ui(ui, scene, assets, draw);
+            });
+    }
+
+    fn stats_ui(&self, ui: &mut Ui, scene: &Scene, assets: &Assets, draw: &DrawStats) {
+        let average = self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32;
+        ui.label(
+            RichText::new(format!(
+                "{:.0} FPS  {:.2} ms",
+                1.0 / average,
+                average * 1000.0
+            ))
+            .strong(),
+        );
+        self.graph(ui);
+
+        let mut entities = 0;
+        scene.walk(&mut |_| entities += 1);
+        egui::Grid::new("stats").num_columns(3).show(ui, |ui| {
+            ui.label("Entities");
+            ui.label(entities.to_string());
+            ui.end_row();
+            ui.label("Draw calls");
+            ui.label(draw.draw_calls.to_string());
+            ui.label(format!("{} shapes", draw.shapes));
+            ui.end_row();
+            ui.label("Triangles");
+            ui.label(draw.triangles.to_string());
+            ui.label(format!("{} vertices", draw.vertices));
+            ui.end_row();
+            for usage in assets.usage().iter().filter(|u| u.count > 0) {
+                ui.label(usage.kind);
+                ui.label(usage.count.to_string());
+                ui.label(usage.bytes.map(bytes).unwrap_or_default());
+                ui.end_row();
+            }
+            if let Some(heap) = alloc::stats() {
+                ui.label("Heap");
+                ui.label(bytes(heap.live_bytes));
+                ui.label(format!("peak {}", bytes(heap.peak_bytes)));
+                ui.end_row();
+                ui.label("Allocations");
+                ui.label(heap.live_allocations.to_string());
+                ui.label(format!("{} total", heap.total_allocations));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Times between the frames as bars, with a line at 60 FPS.
+    fn graph(&self, ui: &mut Ui) {
+        let (rect, _) = ui.allocate_exact_size(Vec2::new(FRAMES as f32, 32.0), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let top = self
+            .frame_times
+            .iter()
+            .fold(1.0 / 30.0, |a: f32, b| a.max(*b));
+        let y = |seconds: f32| rect.bottom() - seconds / top * rect.height();
+        let color = ui.visuals().text_color();
+        for (i, dt) in self.frame_times.iter().rev().enumerate() {
+            let x = rect.right() - i as f32 - 1.0;
+            let bar = Rect::from_x_y_ranges(x..=x + 1.0, y(*dt)..=rect.bottom());
+            painter.rect_filled(bar, 0.0, color.gamma_multiply(0.5));
+        }
+        let target = egui::Stroke::new(1.0_f32, ui.visuals().warn_fg_color);
+        painter.hline(rect.x_range(), y(1.0 / 60.0), target);
+    }
+}
+
+fn bytes(n: usize) -> String {
+    match n {
+        n if n < 1 << 10 => format!("{n} B"),
+        n if n < 1 << 20 => format!("{:.1} KiB", n as f64 / (1 << 10) as f64),
+        n => format!("{:.1} MiB", n as f64 / (1 << 20) as f64),
+    }
+}
@@ -1,16 +1,13 @@
-// Generated by egui-themer (https://github.com/grantshandy/egui-themer).
-
 use eframe::egui;
 
 use egui::FontFamily::{Monospace, Proportional};
-use egui::style::ScrollStyle;
-use egui::{
-    epaint::Shadow,
-    style::{Interaction, Margin, Selection, Spacing, WidgetVisuals, Widgets},
-    Color32, FontData, FontDefinitions, FontId, FontTweak, Rounding, Stroke, Style, TextStyle,
-    Vec2, Visuals,
-};
-pub fn style_dark() -> Style {
+use egui::{style::Interaction, FontData, FontDefinitions, FontId, FontTweak, Style, TextStyle};
+use std::collections::BTreeMap;
+#[cfg(target_arch = "wasm32")]
+use std::sync::mpsc;
+
+/// Text sizes and behaviour of the editor, the colours and spacing come from the theme.
+pub fn base_style() -> Style {
     Style {
         // override the text styles here:
         // override_text_style: Option<TextStyle>
@@ -38,194 +35,11 @@ pub fn style_dark() -> Style {
 
         // set your drag value text style:
         // drag_value_text_style: TextStyle,
-        spacing: Spacing {
-            item_spacing: Vec2 { x: 4.0, y: 2.0 },
-            window_margin: Margin {
-                left: 8.0,
-                right: 8.0,
-                top: 8.0,
-                bottom: 8.0,
-            },
-            button_padding: Vec2 { x: 8.0, y: 8.0 },
-            menu_margin: Margin {
-                left: 4.0,
-                right: 4.0,
-                top: 4.0,
-                bottom: 4.0,
-            },
-            indent: 16.0,
-            interact_size: Vec2 { x: 48.0, y: 32.0 },
-            slider_width: 128.0,
-            combo_width: 64.0,
-            text_edit_width: 256.0,
-            icon_width: 20.0,
-            icon_width_inner: 12.0,
-            icon_spacing: 4.0,
-            tooltip_width: 600.0,
-            indent_ends_with_horizontal_line: false,
-            combo_height: 200.0,
-            scroll: ScrollStyle {
-                bar_width: 16.0,
-                handle_min_length: 16.0,
-                bar_inner_margin: 6.0,
-                bar_outer_margin: 0.0,
-                ..Default::default()
-            },
-        },
         interaction: Interaction {
             resize_grab_radius_side: 5.0,
             resize_grab_radius_corner: 16.0,
             show_tooltips_only_when_still: true,
-            tooltip_delay: 0.1
-        },
-        visuals: Visuals {
-            dark_mode: true,
-            override_text_color: Some(Color32::from_rgb(180, 180, 180)),
-            widgets: Widgets {
-                noninteractive: WidgetVisuals {
-                    bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
-                    weak_bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
-                    bg_stroke: Stroke {
-                        width: 1.0,
-                        color: Color32::from_rgba_premultiplied(51, 50, 50, 255),
-                    },
-                    rounding: Rounding {
-                        nw: 0.0,
-                        ne: 0.0,
-                        sw: 0.0,
-                        se: 0.0,
-                    },
-                    fg_stroke: Stroke {
-                        width: 1.0,
-                        color: Color32::from_rgba_premultiplied(140, 140, 140, 255),
-                    },
-                    expansion: 0.0,
-                },
-                inactive: WidgetVisuals {
-                    bg_fill: Color32::from_rgba_premultiplied(50, 50, 50, 255),
-                    weak_bg_fill: Color32::from_rgba_premultiplied(60, 60, 60, 255),
-                    bg_stroke: Stroke {
-                        width: 0.0,
-                        color: Color32::from_rgba_premultiplied(0, 0, 0, 0),
-                    },
-                    rounding: Rounding {
-                        nw: 0.0,
-                        ne: 0.0,
-                        sw: 0.0,
-                        se: 0.0,
-                    },
-                    fg_stroke: Stroke {
-                        width: 2.0,
-                        color: Color32::from_rgba_premultiplied(180, 180, 180, 255),
-                    },
-                    expansion: 0.0,
-                },
-                hovered: WidgetVisuals {
-                    bg_fill: Color32::from_rgba_premultiplied(70, 70, 70, 255),
-                    weak_bg_fill: Color32::from_rgba_premultiplied(70, 70, 70, 255),
-                    bg_stroke: Stroke {
-                        width: 2.0,
-                        color: Color32::from_rgba_premultiplied(113, 137, 187, 255),
-                    },
-                    rounding: Rounding {
-                        nw: 0.0,
-                        ne: 0.0,
-                        sw: 0.0,
-                        se: 0.0,
-                    },
-                    fg_stroke: Stroke {
-                        width: 2.0,
-                        color: Color32::from_rgba_premultiplied(151, 193, 221, 255),
-                    },
-                    expansion: 2.0,
-                },
-                active: WidgetVisuals {
-                    bg_fill: Color32::from_rgba_premultiplied(55, 55, 55, 255),
-                    weak_bg_fill: Color32::from_rgba_premultiplied(55, 55, 55, 255),
-                    bg_stroke: Stroke {
-                        width: 1.0,
-                        color: Color32::from_rgba_premultiplied(182, 206, 255, 255),
-                    },
-                    rounding: Rounding {
-                        nw: 0.0,
-                        ne: 0.0,
-                        sw: 0.0,
-                        se: 0.0,
-                    },
-                    fg_stroke: Stroke {
-                        width: 2.0,
-                        color: Color32::from_rgba_premultiplied(188, 237, 255, 255),
-                    },
-                    expansion: 1.0,
-                },
-                open: WidgetVisuals {
-                    bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
-                    weak_bg_fill: Color32::from_rgba_premultiplied(64, 64, 64, 255),
-                    bg_stroke: Stroke {
-                        width: 0.0,
-                        color: Color32::from_rgba_premultiplied(60, 60, 60, 255),
-                    },
-                    rounding: Rounding {
-                        nw: 0.0,
-                        ne: 0.0,
-                        sw: 0.0,
-                        se: 0.0,
-                    },
-                    fg_stroke: Stroke {
-                        width: 1.0,
-                        color: Color32::from_rgba_premultiplied(210, 210, 210, 255),
-                    },
-                    expansion: 0.0,
-                },
-            },
-            selection: Selection {
-                bg_fill: Color32::from_rgba_premultiplied(87, 73, 141, 255),
-                stroke: Stroke {
-                    width: 2.0,
-                    color: Color32::from_rgba_premultiplied(116, 134, 155, 149),
-                },
-            },
-            hyperlink_color: Color32::from_rgba_premultiplied(190, 144, 227, 255),
-            faint_bg_color: Color32::from_rgba_premultiplied(2, 2, 2, 0),
-            extreme_bg_color: Color32::from_rgba_premultiplied(28, 28, 28, 255),
-            code_bg_color: Color32::from_rgba_premultiplied(54, 66, 84, 255),
-            warn_fg_color: Color32::from_rgba_premultiplied(255, 143, 0, 255),
-            error_fg_color: Color32::from_rgba_premultiplied(255, 0, 0, 255),
-            window_rounding: Rounding {
-                nw: 0.0,
-                ne: 0.0,
-                sw: 0.0,
-                se: 0.0,
-            },
-            window_shadow: Shadow {
-                extrusion: 8.0,
-                color: Color32::from_rgba_premultiplied(0, 0, 0, 124),
-            },
-            window_fill: Color32::from_rgba_premultiplied(24, 24, 24, 255),
-            window_stroke: Stroke {
-                width: 2.0,
-                color: Color32::from_rgba_premultiplied(36, 36, 36, 255),
-            },
-            menu_rounding: Rounding {
-                nw: 0.0,
-                ne: 0.0,
-                sw: 0.0,
-                se: 0.0,
-            },
-            panel_fill: Color32::from_rgba_premultiplied(30, 30, 30, 255),
-            popup_shadow: Shadow {
-                extrusion: 8.0,
-                color: Color32::from_rgba_premultiplied(0, 0, 0, 108),
-            },
-            resize_corner_size: 8.0,
-            text_cursor_preview: false,
-            clip_rect_margin: 0.0,
-            button_frame: true,
-            collapsing_header_frame: true,
-            indent_has_left_vline: true,
-            striped: false,
-            slider_trailing_fill: true,
-            ..Default::default()
+            tooltip_delay: 0.1,
         },
         animation_time: 0.0,
         explanation_tooltips: false,
@@ -233,91 +47,166 @@ pub fn style_dark() -> Style {
     }
 }
 
-pub fn load_fonts(cc: &eframe::CreationContext<'_>) {
-    let mut fonts = FontDefinitions::empty();
-    fonts.font_data.insert(
-        "NotoSans".to_owned(),
-        FontData::from_static(include_bytes!("../../fonts/NotoSans-Regular.ttf")),
-    );
-    fonts.font_data.insert(
-        "NotoSansMono".to_owned(),
-        FontData::from_static(include_bytes!("../../fonts/NotoSansMono-Regular.ttf")),
-    );
-    fonts.font_data.insert(
-        "NotoSansSymbols".to_owned(),
-        FontData::from_static(include_bytes!("../../fonts/NotoSansSymbols-Regular.ttf")).tweak(
-            FontTweak {
-                scale: 1.25,
-                y_offset_factor: 0.0,
-                y_offset: 0.0,
-                baseline_offset_factor: 0.05,
-            },
-        ),
-    );
-    fonts.font_data.insert(
-        "NotoSansSymbols2".to_owned(),
-        FontData::from_static(include_bytes!("../../fonts/NotoSansSymbols2-Regular.ttf")).tweak(
-            FontTweak {
-                scale: 1.25,
-                y_offset_factor: 0.0,
-                y_offset: 0.0,
-                baseline_offset_factor: 0.05,
-            },
-        ),
-    );
-    fonts.font_data.insert(
-        "NotoEmoji".to_owned(),
-        FontData::from_static(include_bytes!("../../fonts/NotoEmoji-Regular.ttf")).tweak(
-            FontTweak {
-                scale: 1.25,
-                y_offset_factor: 0.0,
-                y_offset: 0.0,
-                baseline_offset_factor: 0.0,
-            },
-        ),
-    );
+/// A font of the editor UI.
+struct EditorFont {
+    /// Name in the font definitions.
+    name: &'static str,
+    /// File in `fonts/`, where web builds fetch it from if it is not embedded.
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    file: &'static str,
+    tweak: FontTweak,
+}
+
+const TEXT_TWEAK: FontTweak = FontTweak {
+    scale: 1.0,
+    y_offset_factor: 0.0,
+    y_offset: 0.0,
+    baseline_offset_factor: 0.0,
+};
 
-    fonts
-        .families
-        .entry(Proportional)
-        .or_default()
-        .push("NotoSans".to_owned());
-    fonts
-        .families
-        .entry(Proportional)
-        .or_default()
-        .push("NotoSansSymbols".to_owned());
-    fonts
-        .families
-        .entry(Proportional)
-        .or_default()
-        .push("NotoSansSymbols2".to_owned());
-    fonts
-        .families
-        .entry(Proportional)
-        .or_default()
-        .push("NotoEmoji".to_owned());
+/// Symbol fonts are drawn a bit bigger, to match the size of the text.
+const FALLBACK_TWEAK: FontTweak = FontTweak {
+    scale: 1.25,
+    y_offset_factor: 0.0,
+    y_offset: 0.0,
+    baseline_offset_factor: 0.05,
+};
 
-    fonts
-        .families
-        .entry(Monospace)
-        .or_default()
-        .push("NotoSansMono".to_owned());
-    fonts
-        .families
-        .entry(Monospace)
-        .or_default()
-        .push("NotoSansSymbols".to_owned());
-    fonts
-        .families
-        .entry(Monospace)
-        .or_default()
-        .push("NotoSansSymbols2".to_owned());
-    fonts
-        .families
-        .entry(Monospace)
-        .or_default()
-        .push("NotoEmoji".to_owned());
+/// The text fonts, followed by the fallbacks for the characters they don't have.
+const EDITOR_FONTS: [EditorFont; 5] = [
+    EditorFont {
+        name: "NotoSans",
+        file: "NotoSans-Regular.ttf",
+        tweak: TEXT_TWEAK,
+    },
+    EditorFont {
+        name: "NotoSansMono",
+        file: "NotoSansMono-Regular.ttf",
+        tweak: TEXT_TWEAK,
+    },
+    EditorFont {
+        name: "NotoSansSymbols",
+        file: "NotoSansSymbols-Regular.ttf",
+        tweak: FALLBACK_TWEAK,
+    },
+    EditorFont {
+        name: "NotoSansSymbols2",
+        file: "NotoSansSymbols2-Regular.ttf",
+        tweak: FALLBACK_TWEAK,
+    },
+    EditorFont {
+        name: "NotoEmoji",
+        file: "NotoEmoji-Regular.ttf",
+        tweak: FontTweak {
+            baseline_offset_factor: 0.0,
+            ..FALLBACK_TWEAK
+        },
+    },
+];
 
-    cc.egui_ctx.set_fonts(fonts);
+/// The data of an editor font, if it is built into the binary.
+fn embedded(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "NotoSans" => Some(include_bytes!("../../fonts/NotoSans-Regular.ttf")),
+        "NotoSansMono" => Some(include_bytes!("../../fonts/NotoSansMono-Regular.ttf")),
+        #[cfg(feature = "font-symbols")]
+        "NotoSansSymbols" => Some(include_bytes!("../../fonts/NotoSansSymbols-Regular.ttf")),
+        #[cfg(feature = "font-symbols")]
+        "NotoSansSymbols2" => Some(include_bytes!("../../fonts/NotoSansSymbols2-Regular.ttf")),
+        #[cfg(feature = "font-emoji")]
+        "NotoEmoji" => Some(include_bytes!("../../fonts/NotoEmoji-Regular.ttf")),
+        _ => None,
+    }
+}
+
+/// The fonts of the editor UI, which project fonts are added to.
+///
+/// Fonts left out of the build by the `font-symbols` and `font-emoji` features are fetched by
+/// web builds once the editor is up, until then their characters are drawn as boxes.
+#[derive(Default)]
+pub struct EditorFonts {
+    fetched: BTreeMap<&'static str, Vec<u8>>,
+    #[cfg(target_arch = "wasm32")]
+    incoming: Option<mpsc::Receiver<(&'static str, Vec<u8>)>>,
+}
+
+impl EditorFonts {
+    pub fn definitions(&self) -> FontDefinitions {
+        let mut fonts = FontDefinitions::empty();
+        for font in &EDITOR_FONTS {
+            let data = match (embedded(font.name), self.fetched.get(font.name)) {
+                (Some(data), _) => FontData::from_static(data),
+                (None, Some(data)) => FontData::from_owned(data.clone()),
+                (None, None) => continue,
+            };
+            fonts
+                .font_data
+                .insert(font.name.to_owned(), data.tweak(font.tweak));
+        }
+        for (family, text_font) in [(Proportional, "NotoSans"), (Monospace, "NotoSansMono")] {
+            let chain = EDITOR_FONTS[2..]
+                .iter()
+                .map(|font| font.name)
+                .filter(|name| fonts.font_data.contains_key(*name));
+            let chain = std::iter::once(text_font).chain(chain);
+            fonts
+                .families
+                .insert(family, chain.map(str::to_owned).collect());
+        }
+        fonts
+    }
+
+    /// Collects fetched fonts, returns true if the fonts have to be installed again.
+    #[cfg(target_arch = "wasm32")]
+    pub fn poll(&mut self, ctx: &egui::Context) -> bool {
+        // Fetching only starts after the first frame, to not hold up showing the editor.
+        if ctx.frame_nr() == 0 {
+            return false;
+        }
+        let incoming = self.incoming.get_or_insert_with(|| fetch_missing(ctx));
+        let mut changed = false;
+        while let Ok((name, data)) = incoming.try_recv() {
+            self.fetched.insert(name, data);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Collects fetched fonts, returns true if the fonts have to be installed again.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn poll(&mut self, _ctx: &egui::Context) -> bool {
+        false
+    }
+}
+
+/// Starts fetching the editor fonts which are not embedded.
+#[cfg(target_arch = "wasm32")]
+fn fetch_missing(ctx: &egui::Context) -> mpsc::Receiver<(&'static str, Vec<u8>)> {
+    let (tx, rx) = mpsc::channel();
+    for font in EDITOR_FONTS.iter().filter(|f| embedded(f.name).is_none()) {
+        let (tx, ctx, name) = (tx.clone(), ctx.clone(), font.name);
+        let request = ehttp::Request::get(format!("fonts/{}", font.file));
+        ehttp::fetch(request, move |result| {
+            let data = match result {
+                Ok(response) if response.ok => response.bytes,
+                Ok(response) => {
+                    log::warn!("Failed to fetch font {name}: {}", response.status_text);
+                    return;
+                }
+                Err(e) => {
+                    log::warn!("Failed to fetch font {name}: {e}");
+                    return;
+                }
+            };
+            // Bad font data would panic when egui installs it.
+            if let Err(e) = ab_glyph::FontRef::try_from_slice(&data) {
+                log::warn!("Fetched font {name} is broken: {e}");
+                return;
+            }
+            if tx.send((name, data)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+    }
+    rx
 }
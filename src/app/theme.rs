@@ -0,0 +1,584 @@
+//! Named colour schemes and spacing of the editor, which can be edited and saved as files.
+
+use egui::style::ScrollStyle;
+use egui::{
+    epaint::Shadow,
+    style::{Margin, Selection, Spacing, WidgetVisuals, Widgets},
+    Color32, Context, Rounding, Stroke, Ui, Vec2, Visuals,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::format::{self, FormatError, Migration};
+
+use super::save_file;
+
+pub const THEME_EXTENSION: &str = ".theme.json";
+pub const THEME_VERSION: u32 = 1;
+pub const THEME_MIGRATIONS: &[Migration] = &[];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub version: u32,
+    pub name: String,
+    pub visuals: Visuals,
+    pub spacing: Spacing,
+}
+
+impl Theme {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, THEME_VERSION, THEME_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+
+    pub fn apply(&self, ctx: &Context) {
+        ctx.style_mut(|style| {
+            style.visuals = self.visuals.clone();
+            style.spacing = self.spacing.clone();
+        });
+    }
+}
+
+/// The original theme of the editor, generated by egui-themer
+/// (https://github.com/grantshandy/egui-themer).
+pub fn dark() -> Theme {
+    let spacing = Spacing {
+        item_spacing: Vec2 { x: 4.0, y: 2.0 },
+        window_margin: Margin {
+            left: 8.0,
+            right: 8.0,
+            top: 8.0,
+            bottom: 8.0,
+        },
+        button_padding: Vec2 { x: 8.0, y: 8.0 },
+        menu_margin: Margin {
+            left: 4.0,
+            right: 4.0,
+            top: 4.0,
+            bottom: 4.0,
+        },
+        indent: 16.0,
+        interact_size: Vec2 { x: 48.0, y: 32.0 },
+        slider_width: 128.0,
+        combo_width: 64.0,
+        text_edit_width: 256.0,
+        icon_width: 20.0,
+        icon_width_inner: 12.0,
+        icon_spacing: 4.0,
+        tooltip_width: 600.0,
+        indent_ends_with_horizontal_line: false,
+        combo_height: 200.0,
+        scroll: ScrollStyle {
+            bar_width: 16.0,
+            handle_min_length: 16.0,
+            bar_inner_margin: 6.0,
+            bar_outer_margin: 0.0,
+            ..Default::default()
+        },
+    };
+    let visuals = Visuals {
+        dark_mode: true,
+        override_text_color: Some(Color32::from_rgb(180, 180, 180)),
+        widgets: Widgets {
+            noninteractive: WidgetVisuals {
+                bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
+                weak_bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
+                bg_stroke: Stroke {
+                    width: 1.0,
+                    color: Color32::from_rgba_premultiplied(51, 50, 50, 255),
+                },
+                rounding: Rounding {
+                    nw: 0.0,
+                    ne: 0.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fg_stroke: Stroke {
+                    width: 1.0,
+                    color: Color32::from_rgba_premultiplied(140, 140, 140, 255),
+                },
+                expansion: 0.0,
+            },
+            inactive: WidgetVisuals {
+                bg_fill: Color32::from_rgba_premultiplied(50, 50, 50, 255),
+                weak_bg_fill: Color32::from_rgba_premultiplied(60, 60, 60, 255),
+                bg_stroke: Stroke {
+                    width: 0.0,
+                    color: Color32::from_rgba_premultiplied(0, 0, 0, 0),
+                },
+                rounding: Rounding {
+                    nw: 0.0,
+                    ne: 0.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fg_stroke: Stroke {
+                    width: 2.0,
+                    color: Color32::from_rgba_premultiplied(180, 180, 180, 255),
+                },
+                expansion: 0.0,
+            },
+            hovered: WidgetVisuals {
+                bg_fill: Color32::from_rgba_premultiplied(70, 70, 70, 255),
+                weak_bg_fill: Color32::from_rgba_premultiplied(70, 70, 70, 255),
+                bg_stroke: Stroke {
+                    width: 2.0,
+                    color: Color32::from_rgba_premultiplied(113, 137, 187, 255),
+                },
+                rounding: Rounding {
+                    nw: 0.0,
+                    ne: 0.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fg_stroke: Stroke {
+                    width: 2.0,
+                    color: Color32::from_rgba_premultiplied(151, 193, 221, 255),
+                },
+                expansion: 2.0,
+            },
+            active: WidgetVisuals {
+                bg_fill: Color32::from_rgba_premultiplied(55, 55, 55, 255),
+                weak_bg_fill: Color32::from_rgba_premultiplied(55, 55, 55, 255),
+                bg_stroke: Stroke {
+                    width: 1.0,
+                    color: Color32::from_rgba_premultiplied(182, 206, 255, 255),
+                },
+                rounding: Rounding {
+                    nw: 0.0,
+                    ne: 0.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fg_stroke: Stroke {
+                    width: 2.0,
+                    color: Color32::from_rgba_premultiplied(188, 237, 255, 255),
+                },
+                expansion: 1.0,
+            },
+            open: WidgetVisuals {
+                bg_fill: Color32::from_rgba_premultiplied(27, 27, 27, 255),
+                weak_bg_fill: Color32::from_rgba_premultiplied(64, 64, 64, 255),
+                bg_stroke: Stroke {
+                    width: 0.0,
+                    color: Color32::from_rgba_premultiplied(60, 60, 60, 255),
+                },
+                rounding: Rounding {
+                    nw: 0.0,
+                    ne: 0.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fg_stroke: Stroke {
+                    width: 1.0,
+                    color: Color32::from_rgba_premultiplied(210, 210, 210, 255),
+                },
+                expansion: 0.0,
+            },
+        },
+        selection: Selection {
+            bg_fill: Color32::from_rgba_premultiplied(87, 73, 141, 255),
+            stroke: Stroke {
+                width: 2.0,
+                color: Color32::from_rgba_premultiplied(116, 134, 155, 149),
+            },
+        },
+        hyperlink_color: Color32::from_rgba_premultiplied(190, 144, 227, 255),
+        faint_bg_color: Color32::from_rgba_premultiplied(2, 2, 2, 0),
+        extreme_bg_color: Color32::from_rgba_premultiplied(28, 28, 28, 255),
+        code_bg_color: Color32::from_rgba_premultiplied(54, 66, 84, 255),
+        warn_fg_color: Color32::from_rgba_premultiplied(255, 143, 0, 255),
+        error_fg_color: Color32::from_rgba_premultiplied(255, 0, 0, 255),
+        window_rounding: Rounding {
+            nw: 0.0,
+            ne: 0.0,
+            sw: 0.0,
+            se: 0.0,
+        },
+        window_shadow: Shadow {
+            extrusion: 8.0,
+            color: Color32::from_rgba_premultiplied(0, 0, 0, 124),
+        },
+        window_fill: Color32::from_rgba_premultiplied(24, 24, 24, 255),
+        window_stroke: Stroke {
+            width: 2.0,
+            color: Color32::from_rgba_premultiplied(36, 36, 36, 255),
+        },
+        menu_rounding: Rounding {
+            nw: 0.0,
+            ne: 0.0,
+            sw: 0.0,
+            se: 0.0,
+        },
+        panel_fill: Color32::from_rgba_premultiplied(30, 30, 30, 255),
+        popup_shadow: Shadow {
+            extrusion: 8.0,
+            color: Color32::from_rgba_premultiplied(0, 0, 0, 108),
+        },
+        resize_corner_size: 8.0,
+        text_cursor_preview: false,
+        clip_rect_margin: 0.0,
+        button_frame: true,
+        collapsing_header_frame: true,
+        indent_has_left_vline: true,
+        striped: false,
+        slider_trailing_fill: true,
+        ..Default::default()
+    };
+    Theme {
+        version: THEME_VERSION,
+        name: "Dark".to_owned(),
+        visuals,
+        spacing,
+    }
+}
+
+pub fn light() -> Theme {
+    let dark = dark();
+    let mut visuals = Visuals {
+        override_text_color: Some(Color32::from_rgb(50, 50, 50)),
+        window_rounding: Rounding::ZERO,
+        menu_rounding: Rounding::ZERO,
+        window_shadow: dark.visuals.window_shadow,
+        popup_shadow: dark.visuals.popup_shadow,
+        resize_corner_size: dark.visuals.resize_corner_size,
+        text_cursor_preview: false,
+        clip_rect_margin: 0.0,
+        slider_trailing_fill: true,
+        ..Visuals::light()
+    };
+    visuals.window_shadow.color = Color32::from_black_alpha(40);
+    visuals.popup_shadow.color = Color32::from_black_alpha(40);
+    visuals.selection.bg_fill = Color32::from_rgb(196, 186, 240);
+    visuals.hyperlink_color = Color32::from_rgb(118, 60, 170);
+    for widget in widgets_mut(&mut visuals) {
+        widget.rounding = Rounding::ZERO;
+    }
+    Theme {
+        version: THEME_VERSION,
+        name: "Light".to_owned(),
+        visuals,
+        spacing: dark.spacing,
+    }
+}
+
+/// Black and white with thick outlines, for low vision and bright rooms.
+pub fn high_contrast() -> Theme {
+    let mut theme = dark();
+    theme.name = "High contrast".to_owned();
+    let yellow = Color32::from_rgb(255, 221, 0);
+    let v = &mut theme.visuals;
+    v.override_text_color = Some(Color32::WHITE);
+    v.panel_fill = Color32::BLACK;
+    v.window_fill = Color32::BLACK;
+    v.extreme_bg_color = Color32::BLACK;
+    v.faint_bg_color = Color32::from_gray(24);
+    v.code_bg_color = Color32::from_gray(32);
+    v.window_stroke = Stroke {
+        width: 2.0,
+        color: Color32::WHITE,
+    };
+    v.hyperlink_color = Color32::from_rgb(0, 234, 255);
+    v.error_fg_color = Color32::from_rgb(255, 90, 90);
+    v.selection = Selection {
+        bg_fill: Color32::from_rgb(0, 70, 190),
+        stroke: Stroke {
+            width: 2.0,
+            color: Color32::WHITE,
+        },
+    };
+    for widget in widgets_mut(v) {
+        widget.bg_fill = Color32::BLACK;
+        widget.weak_bg_fill = Color32::BLACK;
+        widget.bg_stroke = Stroke {
+            width: 1.0,
+            color: Color32::WHITE,
+        };
+        widget.fg_stroke.color = Color32::WHITE;
+    }
+    v.widgets.noninteractive.bg_stroke.color = Color32::from_gray(160);
+    v.widgets.hovered.bg_stroke = Stroke {
+        width: 2.0,
+        color: yellow,
+    };
+    v.widgets.hovered.fg_stroke.color = yellow;
+    v.widgets.active.bg_stroke = Stroke {
+        width: 3.0,
+        color: yellow,
+    };
+    v.widgets.active.fg_stroke.color = yellow;
+    theme
+}
+
+fn widgets_mut(visuals: &mut Visuals) -> [&mut WidgetVisuals; 5] {
+    let w = &mut visuals.widgets;
+    [
+        &mut w.noninteractive,
+        &mut w.inactive,
+        &mut w.hovered,
+        &mut w.active,
+        &mut w.open,
+    ]
+}
+
+/// The available themes and which one is used.
+pub struct Themes {
+    pub list: Vec<Theme>,
+    /// Switch between the `dark` and `light` theme with the OS or browser.
+    pub follow_system: bool,
+    /// Theme used when not following the system.
+    pub current: String,
+    /// Last picked theme of each kind, used when following the system.
+    pub dark: String,
+    pub light: String,
+    /// What is shown now, to apply changes only once.
+    applied: Option<Theme>,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self {
+            list: vec![dark(), light(), high_contrast()],
+            follow_system: true,
+            current: "Dark".to_owned(),
+            dark: "Dark".to_owned(),
+            light: "Light".to_owned(),
+            applied: None,
+        }
+    }
+}
+
+impl Themes {
+    fn find(&self, name: &str) -> Option<&Theme> {
+        self.list.iter().find(|t| t.name == name)
+    }
+
+    /// Name of the theme to show, `system_dark` is `None` if the system doesn't say.
+    pub fn active_name(&self, system_dark: Option<bool>) -> &str {
+        match system_dark.filter(|_| self.follow_system) {
+            Some(true) => &self.dark,
+            Some(false) => &self.light,
+            None => &self.current,
+        }
+    }
+
+    /// Picks a theme, which also becomes the theme used for its kind when following the system.
+    pub fn select(&mut self, name: &str) {
+        let Some(dark_mode) = self.find(name).map(|t| t.visuals.dark_mode) else {
+            return;
+        };
+        if dark_mode {
+            self.dark = name.to_owned();
+        } else {
+            self.light = name.to_owned();
+        }
+        self.current = name.to_owned();
+    }
+
+    /// Adds a theme or replaces the one with the same name, and picks it.
+    pub fn insert(&mut self, theme: Theme) {
+        let name = theme.name.clone();
+        match self.list.iter_mut().find(|t| t.name == name) {
+            Some(old) => *old = theme,
+            None => self.list.push(theme),
+        }
+        self.select(&name);
+    }
+
+    /// The theme to show, `system_dark` is `None` if the system doesn't say.
+    pub fn active(&self, system_dark: Option<bool>) -> Option<&Theme> {
+        self.find(self.active_name(system_dark))
+            .or_else(|| self.list.first())
+    }
+
+    /// Applies the active theme if it changed since the last frame, returns true if it did.
+    pub fn update(&mut self, ctx: &Context, system_dark: Option<bool>) -> bool {
+        let Some(theme) = self.active(system_dark) else {
+            return false;
+        };
+        // eframe resets the visuals when the system theme changes, so they are compared too.
+        let visuals_reset = ctx.style().visuals != theme.visuals;
+        if self.applied.as_ref() == Some(theme) && !visuals_reset {
+            return false;
+        }
+        theme.apply(ctx);
+        self.applied = Some(theme.clone());
+        true
+    }
+}
+
+/// Window for picking and tweaking the themes.
+#[derive(Default)]
+pub struct ThemeEditor {
+    pub open: bool,
+}
+
+impl ThemeEditor {
+    pub fn show(&mut self, ctx: &Context, themes: &mut Themes, system_dark: Option<bool>) {
+        let mut open = self.open;
+        egui::Window::new("🎨 Theme")
+            .open(&mut open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.ui(ui, themes, system_dark));
+            });
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut Ui, themes: &mut Themes, system_dark: Option<bool>) {
+        let active = themes.active_name(system_dark).to_owned();
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("theme")
+                .selected_text(&active)
+                .show_ui(ui, |ui| {
+                    let names: Vec<String> = themes.list.iter().map(|t| t.name.clone()).collect();
+                    for name in names {
+                        if ui.selectable_label(name == active, &name).clicked() {
+                            themes.select(&name);
+                        }
+                    }
+                });
+            if ui.button("Duplicate").clicked() {
+                if let Some(theme) = themes.find(&active) {
+                    let mut copy = theme.clone();
+                    let mut n = 2;
+                    while themes.find(&format!("{} {n}", theme.name)).is_some() {
+                        n += 1;
+                    }
+                    copy.name = format!("{} {n}", theme.name);
+                    themes.insert(copy);
+                }
+            }
+        });
+        ui.checkbox(
+            &mut themes.follow_system,
+            "Follow the system's light or dark mode",
+        )
+        .on_hover_text(format!(
+            "Uses '{}' in dark mode and '{}' in light mode.",
+            themes.dark, themes.light
+        ));
+        if themes.follow_system && system_dark.is_none() {
+            ui.weak("The system doesn't tell its mode here.");
+        }
+
+        let Some(theme) = themes.list.iter_mut().find(|t| t.name == active) else {
+            return;
+        };
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("⬇ Save…").clicked() {
+                let name = format!("{}{THEME_EXTENSION}", theme.name.to_lowercase());
+                save_file(name, theme.save().into_bytes());
+            }
+            ui.weak("Open theme files with File → Open file….");
+        });
+        let v = &mut theme.visuals;
+        ui.checkbox(&mut v.dark_mode, "Dark")
+            .on_hover_text("Whether this is used in dark or light mode.");
+
+        egui::CollapsingHeader::new("Colours")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("theme colours")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        let mut text = v.text_color();
+                        color_row(ui, "Text", &mut text);
+                        v.override_text_color = Some(text);
+                        color_row(ui, "Panels", &mut v.panel_fill);
+                        color_row(ui, "Windows", &mut v.window_fill);
+                        color_row(ui, "Window outline", &mut v.window_stroke.color);
+                        color_row(ui, "Text fields", &mut v.extreme_bg_color);
+                        color_row(ui, "Stripes", &mut v.faint_bg_color);
+                        color_row(ui, "Code", &mut v.code_bg_color);
+                        color_row(ui, "Selection", &mut v.selection.bg_fill);
+                        color_row(ui, "Selection outline", &mut v.selection.stroke.color);
+                        color_row(ui, "Links", &mut v.hyperlink_color);
+                        color_row(ui, "Warnings", &mut v.warn_fg_color);
+                        color_row(ui, "Errors", &mut v.error_fg_color);
+                    });
+            });
+        egui::CollapsingHeader::new("Widgets").show(ui, |ui| {
+            egui::Grid::new("theme widgets")
+                .num_columns(5)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Fill");
+                    ui.label("Weak fill");
+                    ui.label("Outline");
+                    ui.label("Text");
+                    ui.end_row();
+                    let states = ["Label", "Idle", "Hovered", "Pressed", "Open"];
+                    for (state, widget) in states.into_iter().zip(widgets_mut(v)) {
+                        ui.label(state);
+                        ui.color_edit_button_srgba(&mut widget.bg_fill);
+                        ui.color_edit_button_srgba(&mut widget.weak_bg_fill);
+                        ui.color_edit_button_srgba(&mut widget.bg_stroke.color);
+                        ui.color_edit_button_srgba(&mut widget.fg_stroke.color);
+                        ui.end_row();
+                    }
+                });
+        });
+        egui::CollapsingHeader::new("Shape").show(ui, |ui| {
+            egui::Grid::new("theme shape")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Rounding");
+                    let mut rounding = v.window_rounding.nw;
+                    if ui
+                        .add(egui::Slider::new(&mut rounding, 0.0..=12.0))
+                        .changed()
+                    {
+                        let rounding = Rounding::same(rounding);
+                        v.window_rounding = rounding;
+                        v.menu_rounding = rounding;
+                        for widget in widgets_mut(v) {
+                            widget.rounding = rounding;
+                        }
+                    }
+                    ui.end_row();
+                    ui.label("Shadow");
+                    ui.add(egui::Slider::new(
+                        &mut v.window_shadow.extrusion,
+                        0.0..=32.0,
+                    ));
+                    ui.end_row();
+
+                    let s = &mut theme.spacing;
+                    ui.label("Item spacing");
+                    vec2_row(ui, &mut s.item_spacing, 0.0..=20.0);
+                    ui.label("Button padding");
+                    vec2_row(ui, &mut s.button_padding, 0.0..=20.0);
+                    ui.label("Window margin");
+                    let mut margin = s.window_margin.left;
+                    if ui.add(egui::Slider::new(&mut margin, 0.0..=20.0)).changed() {
+                        s.window_margin = Margin::same(margin);
+                    }
+                    ui.end_row();
+                    ui.label("Indent");
+                    ui.add(egui::Slider::new(&mut s.indent, 0.0..=40.0));
+                    ui.end_row();
+                    ui.label("Widget height");
+                    ui.add(egui::Slider::new(&mut s.interact_size.y, 12.0..=48.0));
+                    ui.end_row();
+                });
+        });
+    }
+}
+
+fn color_row(ui: &mut Ui, label: &str, color: &mut Color32) {
+    ui.label(label);
+    ui.color_edit_button_srgba(color);
+    ui.end_row();
+}
+
+fn vec2_row(ui: &mut Ui, value: &mut Vec2, range: std::ops::RangeInclusive<f32>) {
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut value.x).clamp_range(range.clone()));
+        ui.add(egui::DragValue::new(&mut value.y).clamp_range(range));
+    });
+    ui.end_row();
+}
@@ -0,0 +1,497 @@
+use egui::{
+    Color32, Context, Image, Painter, PointerButton, Rect, Response, Sense, Shape, Stroke, Ui, Vec2,
+};
+
+use crate::engine::assets::{AssetRef, Assets};
+use crate::engine::render::Camera;
+use crate::engine::scene::{Component, EntityId, Scene, Transform};
+use crate::engine::tilemap::{TileLayer, Tilemap, Tileset, CHUNK_SIZE};
+
+const TILE_BUTTON: f32 = 32.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileTool {
+    Brush,
+    /// Paints a random tile of the selection in each cell.
+    Random,
+    Rectangle,
+    Fill,
+    Eraser,
+    /// Picks the tile under the cursor.
+    Eyedropper,
+}
+
+impl TileTool {
+    const ALL: [TileTool; 6] = [
+        TileTool::Brush,
+        TileTool::Random,
+        TileTool::Rectangle,
+        TileTool::Fill,
+        TileTool::Eraser,
+        TileTool::Eyedropper,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            TileTool::Brush => "🖌 Brush",
+            TileTool::Random => "🎲 Random",
+            TileTool::Rectangle => "⬛ Rectangle",
+            TileTool::Fill => "🌊 Fill",
+            TileTool::Eraser => "⌫ Eraser",
+            TileTool::Eyedropper => "💧 Pick",
+        }
+    }
+}
+
+/// Window with the tile palette and layers of the selected tilemap. While it is open, the
+/// viewport paints tiles with the primary button instead of selecting entities.
+pub struct TileEditor {
+    pub open: bool,
+    tool: TileTool,
+    layer: usize,
+    /// Selected tiles, the brush uses the first one.
+    selection: Vec<u32>,
+    /// Clicking tiles in the palette toggles their collision instead of selecting them.
+    edit_collision: bool,
+    /// Cell where the current stroke started and the last painted cell.
+    stroke: Option<((i32, i32), (i32, i32))>,
+    /// Changes for every stroke, so random strokes differ.
+    seed: u32,
+    new_tile_size: [u32; 2],
+}
+
+impl Default for TileEditor {
+    fn default() -> Self {
+        Self {
+            open: false,
+            tool: TileTool::Brush,
+            layer: 0,
+            selection: vec![0],
+            edit_collision: false,
+            stroke: None,
+            seed: 0,
+            new_tile_size: [16, 16],
+        }
+    }
+}
+
+fn tilemap_mut(scene: &mut Scene, id: EntityId) -> Option<&mut Tilemap> {
+    scene
+        .find_mut(id)?
+        .components
+        .iter_mut()
+        .find_map(|c| match c {
+            Component::Tilemap(t) => Some(t),
+            _ => None,
+        })
+}
+
+/// A cheap, well mixed hash, enough to scatter random tiles.
+fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x9E37_79B1) ^ (y as u32).wrapping_mul(0x85EB_CA77) ^ seed;
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    h
+}
+
+/// Cells on the line between two cells, so fast strokes leave no gaps.
+fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+    (0..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            (
+                (from.0 as f32 + (to.0 - from.0) as f32 * t).round() as i32,
+                (from.1 as f32 + (to.1 - from.1) as f32 * t).round() as i32,
+            )
+        })
+        .collect()
+}
+
+fn rect_cells(a: (i32, i32), b: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    let (x0, x1) = (a.0.min(b.0), a.0.max(b.0));
+    let (y0, y1) = (a.1.min(b.1), a.1.max(b.1));
+    (y0..=y1).flat_map(move |y| (x0..=x1).map(move |x| (x, y)))
+}
+
+impl TileEditor {
+    /// The tile painted into a cell by the current tool.
+    fn tile_for(&self, x: i32, y: i32) -> Option<u32> {
+        match self.tool {
+            TileTool::Eraser => None,
+            TileTool::Random if !self.selection.is_empty() => {
+                let i = hash(x, y, self.seed) as usize % self.selection.len();
+                Some(self.selection[i])
+            }
+            _ => self.selection.first().copied(),
+        }
+    }
+
+    /// Returns true if the tilemap changed. A tileset the user saved is put into `save`.
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        scene: &mut Scene,
+        assets: &mut Assets,
+        selected: Option<EntityId>,
+        save: &mut Option<AssetRef>,
+    ) -> bool {
+        let mut open = self.open;
+        let mut changed = false;
+        egui::Window::new("🧱 Tiles")
+            .open(&mut open)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                let Some(tilemap) = selected.and_then(|id| tilemap_mut(scene, id)) else {
+                    ui.weak("Select an entity with a Tilemap component.");
+                    return;
+                };
+                changed = self.ui(ui, tilemap, assets, save);
+            });
+        self.open = open;
+        changed
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        tilemap: &mut Tilemap,
+        assets: &mut Assets,
+        save: &mut Option<AssetRef>,
+    ) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Tileset");
+            egui::ComboBox::from_id_source("tileset")
+                .selected_text(tilemap.tileset.path())
+                .show_ui(ui, |ui| {
+                    for asset in assets.tilesets.keys() {
+                        changed |= ui
+                            .selectable_value(&mut tilemap.tileset, asset.clone(), asset.path())
+                            .changed();
+                    }
+                });
+        });
+        ui.menu_button("➕ New tileset from image", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Tile size");
+                ui.add(egui::DragValue::new(&mut self.new_tile_size[0]).clamp_range(1..=1024));
+                ui.add(egui::DragValue::new(&mut self.new_tile_size[1]).clamp_range(1..=1024));
+            });
+            for image in assets.textures.keys() {
+                if ui.button(image.path()).clicked() {
+                    let stem = image.path().trim_end_matches(".png");
+                    let asset = AssetRef::new(format!("{stem}.tileset.json"));
+                    let tileset = Tileset {
+                        image: image.clone(),
+                        tile_size: self.new_tile_size,
+                        ..Default::default()
+                    };
+                    assets.tilesets.insert(asset.clone(), tileset);
+                    tilemap.tileset = asset;
+                    changed = true;
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.separator();
+
+        changed |= self.layers_ui(ui, tilemap);
+        ui.separator();
+
+        ui.horizontal_wrapped(|ui| {
+            for tool in TileTool::ALL {
+                ui.selectable_value(&mut self.tool, tool, tool.label());
+            }
+        });
+        ui.separator();
+
+        let asset = tilemap.tileset.clone();
+        let Some(tileset) = assets.tilesets.get_mut(&asset) else {
+            ui.weak("Choose or create a tileset.");
+            return changed;
+        };
+        let Some(texture) = assets.textures.get(&tileset.image) else {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Image {} is not loaded.", tileset.image),
+            );
+            return changed;
+        };
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.edit_collision, "Edit collision")
+                .on_hover_text("Click tiles to toggle whether they are solid.");
+            if ui.button("💾 Save tileset…").clicked() {
+                *save = Some(asset.clone());
+            }
+        });
+        if !self.edit_collision {
+            ui.weak("Ctrl+click to select several tiles for the random brush.");
+        }
+
+        let columns = tileset.columns(texture.size);
+        let count = tileset.tile_count(texture.size);
+        egui::ScrollArea::both().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = Vec2::splat(1.0);
+            egui::Grid::new("palette").show(ui, |ui| {
+                for tile in 0..count {
+                    let uv = texture.uv(tileset.tile_rect(tile, texture.size));
+                    let response = ui.add(
+                        Image::new(&texture.handle)
+                            .uv(uv)
+                            .fit_to_exact_size(Vec2::splat(TILE_BUTTON))
+                            .sense(Sense::click()),
+                    );
+                    let solid = tileset.solid.contains(&tile);
+                    if solid && self.edit_collision {
+                        ui.painter().rect_filled(
+                            response.rect,
+                            0.0,
+                            Color32::from_rgba_unmultiplied(255, 0, 0, 80),
+                        );
+                    }
+                    if self.selection.contains(&tile) {
+                        ui.painter().rect_stroke(
+                            response.rect,
+                            0.0,
+                            Stroke {
+                                width: 2.0,
+                                color: ui.visuals().selection.stroke.color,
+                            },
+                        );
+                    }
+                    if response.clicked() {
+                        if self.edit_collision {
+                            if solid {
+                                tileset.solid.remove(&tile);
+                            } else {
+                                tileset.solid.insert(tile);
+                            }
+                        } else if ui.input(|i| i.modifiers.command) {
+                            match self.selection.iter().position(|t| *t == tile) {
+                                Some(i) if self.selection.len() > 1 => {
+                                    self.selection.remove(i);
+                                }
+                                Some(_) => {}
+                                None => self.selection.push(tile),
+                            }
+                        } else {
+                            self.selection = vec![tile];
+                            if self.tool == TileTool::Eraser || self.tool == TileTool::Eyedropper {
+                                self.tool = TileTool::Brush;
+                            }
+                        }
+                    }
+                    response.on_hover_text(format!("Tile {tile}"));
+                    if (tile + 1) % columns == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+        changed
+    }
+
+    fn layers_ui(&mut self, ui: &mut Ui, tilemap: &mut Tilemap) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.strong("Layers");
+            if ui.small_button("➕").clicked() {
+                tilemap.layers.push(TileLayer::new(format!(
+                    "Layer {}",
+                    tilemap.layers.len() + 1
+                )));
+                self.layer = tilemap.layers.len() - 1;
+                changed = true;
+            }
+            let i = self.layer;
+            if ui
+                .add_enabled(
+                    i > 0 && i < tilemap.layers.len(),
+                    egui::Button::new("⏶").small(),
+                )
+                .on_hover_text("Draw earlier")
+                .clicked()
+            {
+                tilemap.layers.swap(i, i - 1);
+                self.layer -= 1;
+                changed = true;
+            }
+            if ui
+                .add_enabled(i + 1 < tilemap.layers.len(), egui::Button::new("⏷").small())
+                .on_hover_text("Draw later")
+                .clicked()
+            {
+                tilemap.layers.swap(i, i + 1);
+                self.layer += 1;
+                changed = true;
+            }
+            if ui
+                .add_enabled(i < tilemap.layers.len(), egui::Button::new("🗑").small())
+                .clicked()
+            {
+                tilemap.layers.remove(i);
+                changed = true;
+            }
+        });
+        self.layer = self.layer.min(tilemap.layers.len().saturating_sub(1));
+        for (i, layer) in tilemap.layers.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut layer.visible, "").changed();
+                    ui.radio_value(&mut self.layer, i, "");
+                    changed |= ui.text_edit_singleline(&mut layer.name).changed();
+                });
+            });
+        }
+        changed
+    }
+
+    /// Whether the viewport should hand its pointer input to [`Self::viewport_ui`].
+    pub fn painting(&self, scene: &Scene, selected: Option<EntityId>) -> bool {
+        self.open
+            && selected.and_then(|id| scene.find(id)).is_some_and(|e| {
+                e.components
+                    .iter()
+                    .any(|c| matches!(c, Component::Tilemap(_)))
+            })
+    }
+
+    /// Paints into the selected tilemap with the pointer and draws the cursor. Returns true
+    /// if tiles were changed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn viewport_ui(
+        &mut self,
+        ui: &Ui,
+        response: &Response,
+        painter: &Painter,
+        camera: &Camera,
+        viewport: Rect,
+        scene: &mut Scene,
+        assets: &Assets,
+        id: EntityId,
+    ) -> bool {
+        let Some(world) = scene.world_transform(id) else {
+            return false;
+        };
+        let Some(tilemap) = tilemap_mut(scene, id) else {
+            return false;
+        };
+        let Some(tileset) = assets.tilesets.get(&tilemap.tileset) else {
+            return false;
+        };
+        let size = tileset.tile_size();
+        let Some(pointer) = response.hover_pos().or(response.interact_pointer_pos()) else {
+            self.stroke = None;
+            return false;
+        };
+        let local = world.inverse_apply(camera.screen_to_world(viewport, pointer)) / size;
+        let cell = (local.x.floor() as i32, local.y.floor() as i32);
+
+        let (pressed, down) = ui.input(|i| {
+            (
+                i.pointer.button_pressed(PointerButton::Primary),
+                i.pointer.button_down(PointerButton::Primary),
+            )
+        });
+        if pressed && response.hovered() {
+            self.stroke = Some((cell, cell));
+            self.seed = self.seed.wrapping_add(1);
+        }
+        let mut changed = false;
+        let Some(layer) = tilemap.layers.get_mut(self.layer) else {
+            self.stroke = None;
+            return false;
+        };
+
+        match self.stroke {
+            Some((start, last)) if down => {
+                match self.tool {
+                    TileTool::Brush | TileTool::Random | TileTool::Eraser => {
+                        for (x, y) in line(if pressed { cell } else { last }, cell) {
+                            changed |= layer.set(x, y, self.tile_for(x, y));
+                        }
+                    }
+                    TileTool::Fill if pressed => {
+                        let chunk = (
+                            cell.0.div_euclid(CHUNK_SIZE) * CHUNK_SIZE,
+                            cell.1.div_euclid(CHUNK_SIZE) * CHUNK_SIZE,
+                        );
+                        let ((x0, y0), (x1, y1)) = layer.bounds().unwrap_or((chunk, chunk));
+                        let bounds = (
+                            (x0.min(chunk.0), y0.min(chunk.1)),
+                            (
+                                x1.max(chunk.0 + CHUNK_SIZE - 1),
+                                y1.max(chunk.1 + CHUNK_SIZE - 1),
+                            ),
+                        );
+                        changed |=
+                            layer.fill(cell.0, cell.1, bounds, |x, y| self.tile_for(x, y)) > 0;
+                    }
+                    TileTool::Eyedropper if pressed => {
+                        if let Some(tile) = layer.get(cell.0, cell.1) {
+                            self.selection = vec![tile];
+                            self.tool = TileTool::Brush;
+                        }
+                    }
+                    _ => {}
+                }
+                self.stroke = Some((start, cell));
+            }
+            Some((start, _)) => {
+                // The button was released.
+                if self.tool == TileTool::Rectangle {
+                    for (x, y) in rect_cells(start, cell) {
+                        changed |= layer.set(x, y, self.tile_for(x, y));
+                    }
+                }
+                self.stroke = None;
+            }
+            None => {}
+        }
+
+        // The cursor: the cell under the pointer, or the rectangle being dragged.
+        let cells: Vec<(i32, i32)> = match self.stroke {
+            Some((start, _)) if self.tool == TileTool::Rectangle => {
+                vec![
+                    (start.0.min(cell.0), start.1.min(cell.1)),
+                    (start.0.max(cell.0), start.1.max(cell.1)),
+                ]
+            }
+            _ => vec![cell, cell],
+        };
+        let (min, max) = (cells[0], cells[1]);
+        let min = Vec2::new(min.0 as f32, min.1 as f32) * size;
+        let max = Vec2::new((max.0 + 1) as f32, (max.1 + 1) as f32) * size;
+        draw_cell_outline(
+            painter,
+            camera,
+            viewport,
+            &world,
+            min,
+            max,
+            ui.visuals().selection.stroke.color,
+        );
+        if changed {
+            ui.ctx().request_repaint();
+        }
+        changed
+    }
+}
+
+fn draw_cell_outline(
+    painter: &Painter,
+    camera: &Camera,
+    viewport: Rect,
+    world: &Transform,
+    min: Vec2,
+    max: Vec2,
+    color: Color32,
+) {
+    let points = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+        .iter()
+        .map(|c| camera.world_to_screen(viewport, world.apply(*c)))
+        .collect();
+    painter.add(Shape::closed_line(points, Stroke { width: 1.5, color }));
+}
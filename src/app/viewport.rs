@@ -0,0 +1,157 @@
+use egui::{Color32, PointerButton, Sense, Shape, Stroke, Ui, Vec2};
+
+use super::stats_overlay::{Corner, StatsOverlay};
+use super::tile_editor::TileEditor;
+use crate::engine::assets::Assets;
+use crate::engine::physics;
+use crate::engine::render::{self, Camera, DrawStats};
+use crate::engine::scene::{EntityId, Scene};
+
+/// The 2D scene view of the editor.
+#[derive(Default)]
+pub struct Viewport {
+    pub camera: Camera,
+    /// Draw the outlines of colliders.
+    pub show_colliders: bool,
+    /// Whether the pointer was over the view in the last frame, for the game input.
+    pub hovered: bool,
+    stats: StatsOverlay,
+}
+
+impl Viewport {
+    /// Forgets the frame times, when another project is opened.
+    pub fn reset_stats(&mut self) {
+        self.stats = StatsOverlay::default();
+    }
+
+    /// Shows the scene. Drag with the middle or right button to pan, scroll to zoom and click
+    /// a sprite to select its entity, or paint tiles into the selected tilemap while the tile
+    /// editor is open. The statistics are shown in the `overlay` corner, if any. Returns true if
+    /// the scene was changed.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        scene: &mut Scene,
+        assets: &Assets,
+        selected: &mut Option<EntityId>,
+        tiles: &mut TileEditor,
+        overlay: Option<Corner>,
+    ) -> bool {
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        self.hovered = response.hovered();
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        if response.dragged_by(PointerButton::Middle)
+            || response.dragged_by(PointerButton::Secondary)
+        {
+            self.camera.center -= response.drag_delta() / self.camera.zoom;
+        }
+        if let Some(hover) = response.hover_pos() {
+            let scroll = ui.input(|i| i.scroll_delta.y);
+            if scroll != 0.0 {
+                // Zoom around the cursor.
+                let before = self.camera.screen_to_world(rect, hover);
+                self.camera.zoom = (self.camera.zoom * (scroll / 200.0).exp()).clamp(0.05, 64.0);
+                let after = self.camera.screen_to_world(rect, hover);
+                self.camera.center += before - after;
+            }
+        }
+
+        // Axes through the world origin.
+        let origin = self.camera.world_to_screen(rect, Vec2::ZERO);
+        let axis = Stroke {
+            width: 1.0,
+            color: ui.visuals().widgets.noninteractive.bg_stroke.color,
+        };
+        painter.hline(rect.x_range(), origin.y, axis);
+        painter.vline(origin.x, rect.y_range(), axis);
+
+        let quads = render::sprite_quads(scene, assets, self.camera.visible(rect));
+        let mut shapes = render::sprite_shapes(&self.camera, rect, &quads);
+        shapes.extend(render::text_shapes(ui.ctx(), &self.camera, rect, scene));
+        let draw = overlay.map(|_| DrawStats::measure(&shapes));
+        painter.extend(shapes);
+
+        // Entities without a sprite are shown as small crosses.
+        let marker = Stroke {
+            width: 1.0,
+            color: ui.visuals().weak_text_color(),
+        };
+        scene.walk_world(&mut |e, world| {
+            if quads.iter().all(|q| q.entity != e.id) {
+                let p = self.camera.world_to_screen(rect, world.position);
+                painter.line_segment([p - Vec2::X * 4.0, p + Vec2::X * 4.0], marker);
+                painter.line_segment([p - Vec2::Y * 4.0, p + Vec2::Y * 4.0], marker);
+            }
+        });
+
+        if self.show_colliders {
+            for (outline, trigger) in
+                physics::debug_shapes(scene, assets, self.camera.visible(rect))
+            {
+                let color = if trigger {
+                    Color32::from_rgb(255, 200, 0)
+                } else {
+                    Color32::from_rgb(0, 220, 120)
+                };
+                let points = outline
+                    .iter()
+                    .map(|p| self.camera.world_to_screen(rect, *p))
+                    .collect();
+                painter.add(Shape::closed_line(points, Stroke { width: 1.0, color }));
+            }
+        }
+
+        if let Some(id) = *selected {
+            let highlight = Stroke {
+                width: 2.0,
+                color: ui.visuals().selection.stroke.color,
+            };
+            for q in quads.iter().filter(|q| q.entity == id) {
+                let points = q
+                    .corners
+                    .iter()
+                    .map(|c| self.camera.world_to_screen(rect, *c))
+                    .collect();
+                painter.add(Shape::closed_line(points, highlight));
+            }
+        }
+
+        let mut changed = false;
+        if let Some(id) = selected.filter(|_| tiles.painting(scene, *selected)) {
+            changed = tiles.viewport_ui(
+                ui,
+                &response,
+                &painter,
+                &self.camera,
+                rect,
+                scene,
+                assets,
+                id,
+            );
+        } else if response.clicked_by(PointerButton::Primary) {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let world = self.camera.screen_to_world(rect, pos);
+                // The topmost sprite is drawn last.
+                *selected = quads
+                    .iter()
+                    .rev()
+                    .find(|q| q.contains(world))
+                    .map(|q| q.entity);
+            }
+        }
+
+        painter.text(
+            rect.left_bottom() + Vec2::new(4.0, -4.0),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.0}%", self.camera.zoom * 100.0),
+            egui::TextStyle::Small.resolve(ui.style()),
+            Color32::GRAY,
+        );
+        if let (Some(corner), Some(draw)) = (overlay, draw) {
+            self.stats.ui(ui, rect, corner, scene, assets, &draw);
+        }
+        changed
+    }
+}
@@ -0,0 +1,112 @@
+//! Plays a pack exported from the editor, without the editor.
+#![warn(clippy::all, rust_2018_idioms)]
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+use archaic_engine::app::logwidget::new_logger;
+use archaic_engine::engine::vfs::Vfs;
+use archaic_engine::player::{CONSOLE_KEY, FILES_MOUNT};
+use simplelog::*;
+
+fn log_config() -> Config {
+    ConfigBuilder::new()
+        .set_time_level(LevelFilter::Trace)
+        .set_thread_level(LevelFilter::Trace)
+        .build()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli {
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    /// Plays a game exported from the Archaic Engine editor.
+    #[derive(Debug, Parser)]
+    #[command(version, about)]
+    pub struct Cli {
+        /// The pack of the game.
+        #[arg(default_value = "game.pack")]
+        pub pack: PathBuf,
+        /// Play in a window instead of full screen.
+        #[arg(long)]
+        pub windowed: bool,
+        /// Show the log with the console key. Debug builds always have it.
+        #[arg(long)]
+        pub console: bool,
+    }
+}
+
+// When compiling natively:
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> eframe::Result<()> {
+    use clap::Parser;
+
+    let cli = cli::Cli::parse();
+    let console = (cfg!(debug_assertions) || cli.console).then(|| {
+        let (log_widget, log_writer) = new_logger();
+        let _ = CombinedLogger::init(vec![
+            WriteLogger::new(LevelFilter::Debug, log_config(), log_writer),
+            TermLogger::new(
+                LevelFilter::Debug,
+                Config::default(),
+                TerminalMode::Mixed,
+                ColorChoice::Auto,
+            ),
+        ]);
+        log::info!("Press {} for the console.", CONSOLE_KEY.name());
+        log_widget
+    });
+
+    use archaic_engine::engine::vfs::NativeDir;
+
+    // The folder of the pack is mounted, the game is read from there.
+    let vfs = Vfs::default();
+    let folder = match cli.pack.parent() {
+        Some(folder) if !folder.as_os_str().is_empty() => folder.to_owned(),
+        _ => ".".into(),
+    };
+    vfs.mount(FILES_MOUNT, NativeDir::new(folder));
+    let name = cli.pack.file_name().unwrap_or_default().to_string_lossy();
+    let pack = format!("{FILES_MOUNT}/{name}");
+
+    let mut native_options = eframe::NativeOptions::default();
+    if cli.windowed {
+        native_options.viewport.inner_size = Some(egui::vec2(1280.0, 720.0));
+    } else {
+        native_options.viewport.fullscreen = Some(true);
+    }
+    eframe::run_native(
+        "Archaic Engine",
+        native_options,
+        Box::new(move |cc| Box::new(archaic_engine::Player::new(cc, vfs, pack, console))),
+    )
+}
+
+// When compiling to web using trunk:
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    // The console is there in debug builds only.
+    let console = cfg!(debug_assertions).then(|| {
+        let (log_widget, log_writer) = new_logger();
+        let _ = WriteLogger::init(LevelFilter::Debug, log_config(), log_writer);
+        log::info!("Press {} for the console.", CONSOLE_KEY.name());
+        log_widget
+    });
+
+    use archaic_engine::engine::vfs::HttpFs;
+
+    // The pack lies next to the page.
+    let vfs = Vfs::default();
+    vfs.mount(FILES_MOUNT, HttpFs::new(""));
+    let pack = format!("{FILES_MOUNT}/game.pack");
+
+    let web_options = eframe::WebOptions::default();
+    wasm_bindgen_futures::spawn_local(async {
+        eframe::WebRunner::new()
+            .start(
+                "the_canvas_id", // hardcode it
+                web_options,
+                Box::new(|cc| Box::new(archaic_engine::Player::new(cc, vfs, pack, console))),
+            )
+            .await
+            .expect("failed to start eframe");
+    });
+}
@@ -0,0 +1,268 @@
+//! Command line of the native editor, and what it does without a window.
+
+use archaic_engine::engine::assets::Assets;
+use archaic_engine::engine::audio::Audio;
+use archaic_engine::engine::import;
+use archaic_engine::engine::pack::{self, PACK_EXTENSION};
+use archaic_engine::engine::project::{ProjectDir, PROJECT_FILE};
+use archaic_engine::engine::runtime::Runtime;
+use archaic_engine::engine::scene::Scene;
+use clap::{Parser, Subcommand};
+use log::{error, info, LevelFilter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Frame time of the game when played without a window.
+const HEADLESS_DT: f32 = 1.0 / 60.0;
+
+/// The Archaic Engine editor.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Project folder, project file or any other file the editor opens.
+    pub path: Option<PathBuf>,
+    /// Play this scene on startup, given by its file in the project folder or by its name.
+    #[arg(long, value_name = "SCENE")]
+    pub play: Option<String>,
+    /// Run without a window: import the assets of the project and play the `--play` scene.
+    #[arg(long, requires = "path")]
+    pub headless: bool,
+    /// Number of frames to play without a window.
+    #[arg(long, default_value_t = 600)]
+    pub frames: u32,
+    /// Open a window of this size, like 1280x720, instead of a maximized one.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    pub window_size: Option<egui::Vec2>,
+    /// Least severe log messages shown: off, error, warn, info, debug or trace.
+    #[arg(long, global = true, value_name = "LEVEL", default_value_t = LevelFilter::Debug)]
+    pub log_level: LevelFilter,
+    /// Also write the log into this file.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Batch jobs, run without a window.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Copy files into a project folder, checking that they import.
+    Import {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// The project folder.
+        #[arg(long, default_value = ".")]
+        project: PathBuf,
+    },
+    /// Copy a project folder, upgrading the project and scene files to the current format.
+    Export { project: PathBuf, out: PathBuf },
+    /// Cook a project folder into a pack, with all scenes and the assets they use.
+    Pack {
+        project: PathBuf,
+        /// The pack file, named after the project by default.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Scene the game starts with, by its file or by its name. The first scene by default.
+        #[arg(long, value_name = "SCENE")]
+        start: Option<String>,
+    },
+}
+
+impl Cli {
+    /// Whether the run has no window.
+    pub fn is_headless(&self) -> bool {
+        self.headless || self.command.is_some()
+    }
+
+    /// The project folder `path` is or is in.
+    pub fn project_root(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        if path.is_dir() {
+            Some(path.clone())
+        } else if path.ends_with(PROJECT_FILE) {
+            Some(path.parent()?.to_owned())
+        } else {
+            None
+        }
+    }
+
+    /// Runs the batch job or the headless game.
+    pub fn run(&self) -> Result<(), String> {
+        match &self.command {
+            Some(Command::Import { files, project }) => import_files(files, project),
+            Some(Command::Export { project, out }) => export(project, out),
+            Some(Command::Pack {
+                project,
+                out,
+                start,
+            }) => cook(project, out.as_deref(), start.as_deref()),
+            None => self.play_headless(),
+        }
+    }
+
+    fn play_headless(&self) -> Result<(), String> {
+        let root = self
+            .project_root()
+            .ok_or("--headless needs a project folder")?;
+        let dir = ProjectDir::read(&root).map_err(|e| format!("{}: {e}", root.display()))?;
+        let project = dir.load_project()?;
+        let mut assets = Assets::default();
+        import_assets(&dir, &mut assets)?;
+        let Some(name) = &self.play else {
+            info!("Imported the assets of '{}'.", project.name);
+            return Ok(());
+        };
+        let path = dir
+            .find_scene(name)
+            .ok_or_else(|| format!("No scene '{name}' in {}.", root.display()))?;
+        let scene = load_scene(path)?;
+        info!("Playing scene '{}' for {} frames.", scene.name, self.frames);
+        let mut runtime = Runtime::new(scene, &project, Audio::null().mixer);
+        for _ in 0..self.frames {
+            runtime.update(&mut assets, HEADLESS_DT);
+        }
+        info!("Played {:.1} seconds.", runtime.time);
+        Ok(())
+    }
+}
+
+/// Parses a window size like `1280x720`.
+fn parse_size(text: &str) -> Result<egui::Vec2, String> {
+    let (width, height) = text.split_once('x').ok_or("expected WIDTHxHEIGHT")?;
+    let parse = |n: &str| n.trim().parse::<f32>().map_err(|e| e.to_string());
+    Ok(egui::vec2(parse(width)?, parse(height)?))
+}
+
+fn load_scene(path: &Path) -> Result<Scene, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Scene::load(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Imports a file. `ctx` only holds the textures, it never draws.
+fn import_file(
+    ctx: &egui::Context,
+    assets: &mut Assets,
+    name: &str,
+    path: &Path,
+) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    import::import(ctx, assets, name, &data).map_err(|e| e.to_string())
+}
+
+/// Imports all assets of a project, logging the ones which fail.
+fn import_assets(dir: &ProjectDir, assets: &mut Assets) -> Result<(), String> {
+    let ctx = egui::Context::default();
+    let mut failed = 0;
+    for path in &dir.assets {
+        let name = dir.reference(path);
+        if let Err(e) = import_file(&ctx, assets, &name, path) {
+            error!("Failed to import {name}: {e}");
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!(
+            "{n} of {} assets failed to import.",
+            dir.assets.len()
+        )),
+    }
+}
+
+fn import_files(files: &[PathBuf], project: &Path) -> Result<(), String> {
+    let ctx = egui::Context::default();
+    let mut failed = 0;
+    for path in files {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let result = if import::is_importable(&name) {
+            import_file(&ctx, &mut Assets::default(), &name, path)
+                .and_then(|()| copy(path, &project.join(&name)))
+        } else {
+            Err("unsupported file type".to_owned())
+        };
+        match result {
+            Ok(()) => info!("Imported {name}."),
+            Err(e) => {
+                error!("Failed to import {}: {e}", path.display());
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{n} of {} files failed to import.", files.len())),
+    }
+}
+
+fn export(project: &Path, out: &Path) -> Result<(), String> {
+    let dir = ProjectDir::read(project).map_err(|e| format!("{}: {e}", project.display()))?;
+    let mut assets = Assets::default();
+    import_assets(&dir, &mut assets)?;
+    if dir.project.is_some() {
+        write(&out.join(PROJECT_FILE), dir.load_project()?.save())?;
+    }
+    for path in &dir.scenes {
+        write(&out.join(dir.reference(path)), load_scene(path)?.save())?;
+    }
+    for path in &dir.assets {
+        copy(path, &out.join(dir.reference(path)))?;
+    }
+    info!(
+        "Exported {} scenes and {} assets to {}.",
+        dir.scenes.len(),
+        dir.assets.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+fn cook(project: &Path, out: Option<&Path>, start: Option<&str>) -> Result<(), String> {
+    let dir = ProjectDir::read(project).map_err(|e| format!("{}: {e}", project.display()))?;
+    let mut assets = Assets::default();
+    import_assets(&dir, &mut assets)?;
+    // The game starts with the first scene.
+    let mut scenes: Vec<&Path> = dir.scenes.iter().map(PathBuf::as_path).collect();
+    if let Some(start) = start {
+        let path = dir
+            .find_scene(start)
+            .ok_or_else(|| format!("No scene '{start}' in {}.", project.display()))?;
+        scenes.retain(|p| *p != path);
+        scenes.insert(0, path);
+    }
+    let scenes = scenes
+        .into_iter()
+        .map(|p| Ok((dir.reference(p), load_scene(p)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let game = dir.load_project()?;
+    let (data, report) = pack::cook(&game, &scenes, &assets).map_err(|e| e.to_string())?;
+    report.log();
+    let out = out.map_or_else(
+        || PathBuf::from(format!("{}{PACK_EXTENSION}", game.name)),
+        Path::to_owned,
+    );
+    fs::write(&out, data).map_err(|e| format!("{}: {e}", out.display()))?;
+    info!("Wrote {}.", out.display());
+    Ok(())
+}
+
+fn write(path: &Path, text: String) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+    }
+    fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn copy(from: &Path, to: &Path) -> Result<(), String> {
+    // Copying a file onto itself would empty it.
+    if to.exists() && from.canonicalize().ok() == to.canonicalize().ok() {
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+    }
+    fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|e| format!("{}: {e}", to.display()))
+}
@@ -0,0 +1,22 @@
+//! The runtime half of the engine: data and systems a game needs without the editor UI.
+
+pub mod alloc;
+pub mod animation;
+pub mod assets;
+pub mod audio;
+pub mod format;
+pub mod import;
+pub mod input;
+pub mod pack;
+pub mod physics;
+pub mod prefab;
+pub mod profiler;
+pub mod project;
+pub mod render;
+pub mod runtime;
+pub mod scene;
+pub mod script;
+pub mod text;
+pub mod tilemap;
+pub mod vfs;
+pub mod watch;
@@ -0,0 +1,81 @@
+//! A global allocator which counts the memory in use, for the statistics of the editor.
+//!
+//! Binaries opt in with
+//! `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static TOTAL_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting what goes through it.
+pub struct CountingAllocator;
+
+/// Heap usage seen by the [`CountingAllocator`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Bytes currently allocated.
+    pub live_bytes: usize,
+    /// Most bytes allocated at once.
+    pub peak_bytes: usize,
+    /// Allocations not freed yet.
+    pub live_allocations: usize,
+    /// Allocations made since the start.
+    pub total_allocations: usize,
+}
+
+/// The heap usage, `None` if the [`CountingAllocator`] is not the global allocator.
+pub fn stats() -> Option<AllocStats> {
+    let total_allocations = TOTAL_ALLOCATIONS.load(Relaxed);
+    (total_allocations > 0).then(|| AllocStats {
+        live_bytes: LIVE_BYTES.load(Relaxed),
+        peak_bytes: PEAK_BYTES.load(Relaxed),
+        live_allocations: LIVE_ALLOCATIONS.load(Relaxed),
+        total_allocations,
+    })
+}
+
+fn allocated(size: usize) {
+    let live = LIVE_BYTES.fetch_add(size, Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+            LIVE_ALLOCATIONS.fetch_add(1, Relaxed);
+            TOTAL_ALLOCATIONS.fetch_add(1, Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+            LIVE_ALLOCATIONS.fetch_add(1, Relaxed);
+            TOTAL_ALLOCATIONS.fetch_add(1, Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Relaxed);
+        LIVE_ALLOCATIONS.fetch_sub(1, Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Relaxed);
+            allocated(new_size);
+        }
+        new
+    }
+}
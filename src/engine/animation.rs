@@ -0,0 +1,342 @@
+//! Sprite-sheet animation: clips are sequences of images played by the [`Animator`] component.
+
+use serde::{Deserialize, Serialize};
+
+use super::assets::{AssetRef, Assets};
+use super::format::{self, FormatError, Migration};
+use super::runtime::GameEvent;
+use super::scene::{Component, Scene};
+
+pub const CLIP_VERSION: u32 = 1;
+pub const CLIP_MIGRATIONS: &[Migration] = &[];
+
+/// Most frames an animator enters in one update, so a tiny looping clip cannot stall a long step.
+const MAX_ENTERED_FRAMES: usize = 256;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoopMode {
+    /// Stop on the last frame.
+    Once,
+    #[default]
+    Loop,
+    /// Play forwards, then backwards.
+    PingPong,
+}
+
+impl LoopMode {
+    pub const ALL: [LoopMode; 3] = [LoopMode::Once, LoopMode::Loop, LoopMode::PingPong];
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationFrame {
+    /// An image or atlas and the frame in it, like [`super::scene::Sprite`].
+    pub image: AssetRef,
+    #[serde(default)]
+    pub region: String,
+    /// How long the frame is shown, in seconds.
+    pub duration: f32,
+}
+
+/// A named event fired when the animation enters `frame`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationEvent {
+    pub frame: usize,
+    pub name: String,
+}
+
+/// An `*.anim.json` asset.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnimationClip {
+    pub version: u32,
+    #[serde(default)]
+    pub mode: LoopMode,
+    #[serde(default)]
+    pub frames: Vec<AnimationFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<AnimationEvent>,
+}
+
+impl Default for AnimationClip {
+    fn default() -> Self {
+        Self {
+            version: CLIP_VERSION,
+            mode: LoopMode::Loop,
+            frames: vec![],
+            events: vec![],
+        }
+    }
+}
+
+impl AnimationClip {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, CLIP_VERSION, CLIP_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    /// Index of the frame shown `time` seconds after the start, `None` for an empty clip.
+    pub fn frame_at(&self, time: f32) -> Option<usize> {
+        let total = self.duration();
+        if self.frames.is_empty() || total <= 0.0 {
+            return (!self.frames.is_empty()).then_some(0);
+        }
+        let t = match self.mode {
+            LoopMode::Once => time.min(total),
+            LoopMode::Loop => time.rem_euclid(total),
+            LoopMode::PingPong => {
+                let t = time.rem_euclid(2.0 * total);
+                if t > total {
+                    2.0 * total - t
+                } else {
+                    t
+                }
+            }
+        };
+        let mut end = 0.0;
+        for (i, f) in self.frames.iter().enumerate() {
+            end += f.duration;
+            if t < end {
+                return Some(i);
+            }
+        }
+        Some(self.frames.len() - 1)
+    }
+
+    /// Calls `f` with every frame entered after `from` seconds, up to and including `to`, in
+    /// order. Frames shown for a shorter time than the step, or not at all, are entered too, so
+    /// their events still fire. With `from` being `None` the clip just started, so the frames at
+    /// time zero are entered as well.
+    pub fn entered_frames(&self, from: Option<f32>, to: f32, mut f: impl FnMut(usize)) {
+        let n = self.frames.len();
+        let total = self.duration();
+        if n == 0 {
+            return;
+        }
+        // When the frames are entered within one period, and how long a period is.
+        let mut starts = Vec::with_capacity(2 * n);
+        let mut start = 0.0;
+        for (i, frame) in self.frames.iter().enumerate() {
+            starts.push((start, i));
+            start += frame.duration;
+        }
+        let period = match self.mode {
+            _ if total <= 0.0 => None,
+            LoopMode::Once => None,
+            LoopMode::Loop => Some(total),
+            LoopMode::PingPong => {
+                // Backwards each frame is entered where it ended going forwards. The last frame
+                // is shown on through the turn, the first one on into the next period.
+                let mut end = total;
+                for i in (0..n - 1).rev() {
+                    end -= self.frames[i + 1].duration;
+                    starts.push((2.0 * total - end, i));
+                }
+                Some(2.0 * total)
+            }
+        };
+        let first = match (from, period) {
+            (Some(from), Some(period)) => from.div_euclid(period).max(0.0) as u64,
+            _ => 0,
+        };
+        let mut entered = 0;
+        for k in first.. {
+            let offset = period.map_or(0.0, |p| k as f32 * p);
+            for (j, &(start, i)) in starts.iter().enumerate() {
+                let time = offset + start;
+                if time > to {
+                    return;
+                }
+                // A ping-pong clip is already showing its first frame when a period starts.
+                let turned = k > 0 && j == 0 && self.mode == LoopMode::PingPong;
+                if turned || from.is_some_and(|from| time <= from) {
+                    continue;
+                }
+                f(i);
+                entered += 1;
+                if entered >= MAX_ENTERED_FRAMES {
+                    return;
+                }
+            }
+            if period.is_none() {
+                return;
+            }
+        }
+    }
+
+    /// Whether a clip played for `time` seconds has finished, only possible for [`LoopMode::Once`].
+    pub fn finished(&self, time: f32) -> bool {
+        self.mode == LoopMode::Once && time >= self.duration()
+    }
+}
+
+/// Plays a clip on the sprite of its entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animator {
+    pub clip: AssetRef,
+    /// Playback speed multiplier.
+    pub speed: f32,
+    pub playing: bool,
+    /// Seconds since the clip started, not saved.
+    #[serde(skip)]
+    pub time: f32,
+    #[serde(skip)]
+    pub frame: Option<usize>,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            clip: AssetRef::default(),
+            speed: 1.0,
+            playing: true,
+            time: 0.0,
+            frame: None,
+        }
+    }
+}
+
+/// Advances all animators by `dt` seconds and updates the sprites they drive.
+pub fn update(scene: &mut Scene, assets: &Assets, dt: f32, events: &mut Vec<GameEvent>) {
+    scene.walk_mut(&mut |e| {
+        let mut shown = None;
+        for c in &mut e.components {
+            let Component::Animator(animator) = c else {
+                continue;
+            };
+            let Some(clip) = assets.clips.get(&animator.clip) else {
+                continue;
+            };
+            let from = animator.frame.map(|_| animator.time);
+            if animator.playing {
+                animator.time += dt * animator.speed;
+            }
+            clip.entered_frames(from, animator.time, |i| {
+                for ev in clip.events.iter().filter(|ev| ev.frame == i) {
+                    events.push(GameEvent::Animation {
+                        entity: e.id,
+                        name: ev.name.clone(),
+                    });
+                }
+            });
+            let frame = clip.frame_at(animator.time);
+            animator.frame = frame;
+            if clip.finished(animator.time) {
+                animator.playing = false;
+            }
+            shown = frame.and_then(|i| clip.frames.get(i));
+        }
+        let Some(frame) = shown else {
+            return;
+        };
+        for c in &mut e.components {
+            if let Component::Sprite(sprite) = c {
+                sprite.image = frame.image.clone();
+                sprite.region = frame.region.clone();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(mode: LoopMode, durations: &[f32]) -> AnimationClip {
+        AnimationClip {
+            mode,
+            frames: durations
+                .iter()
+                .map(|&duration| AnimationFrame {
+                    image: AssetRef::default(),
+                    region: String::new(),
+                    duration,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn entered(clip: &AnimationClip, from: Option<f32>, to: f32) -> Vec<usize> {
+        let mut frames = vec![];
+        clip.entered_frames(from, to, |i| frames.push(i));
+        frames
+    }
+
+    #[test]
+    fn enters_the_first_frame_on_start() {
+        let clip = clip(LoopMode::Loop, &[0.5, 0.5]);
+        assert_eq!(entered(&clip, None, 0.0), [0]);
+        assert_eq!(entered(&clip, Some(0.0), 0.1), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn enters_skipped_and_empty_frames() {
+        let clip = clip(LoopMode::Once, &[0.1, 0.0, 0.1, 0.1]);
+        assert_eq!(entered(&clip, Some(0.05), 0.25), [1, 2, 3]);
+        assert_eq!(entered(&clip, Some(0.25), 10.0), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn enters_frames_across_loops() {
+        let two = clip(LoopMode::Loop, &[0.1, 0.1]);
+        assert_eq!(entered(&two, Some(0.15), 0.45), [0, 1, 0]);
+        // A single frame clip enters its frame again on every loop.
+        let one = clip(LoopMode::Loop, &[0.1]);
+        assert_eq!(entered(&one, Some(0.05), 0.25), [0, 0]);
+    }
+
+    #[test]
+    fn ping_pong_turns_without_entering_the_end_frames_twice() {
+        let clip = clip(LoopMode::PingPong, &[0.1, 0.1, 0.1]);
+        assert_eq!(entered(&clip, None, 0.95), [0, 1, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn update_fires_events_of_every_crossed_frame() {
+        let mut assets = Assets::default();
+        let asset = AssetRef::new("walk.anim.json");
+        let mut walk = clip(LoopMode::Loop, &[0.1, 0.1, 0.1]);
+        walk.events = vec![
+            AnimationEvent {
+                frame: 0,
+                name: "left".to_owned(),
+            },
+            AnimationEvent {
+                frame: 2,
+                name: "right".to_owned(),
+            },
+        ];
+        assets.clips.insert(asset.clone(), walk);
+        let mut scene = Scene::default();
+        let id = scene.spawn("Hero", None);
+        scene
+            .find_mut(id)
+            .unwrap()
+            .components
+            .push(Component::Animator(Animator {
+                clip: asset,
+                ..Default::default()
+            }));
+
+        let mut names = |dt| {
+            let mut events = vec![];
+            update(&mut scene, &assets, dt, &mut events);
+            events
+                .into_iter()
+                .map(|ev| match ev {
+                    GameEvent::Animation { name, .. } => name,
+                    other => panic!("unexpected {other:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(0.0), ["left"]);
+        assert_eq!(names(0.65), ["right", "left", "right", "left"]);
+    }
+}
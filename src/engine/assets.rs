@@ -0,0 +1,135 @@
+use egui::{Rect, TextureHandle};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::animation::AnimationClip;
+use super::audio::Sound;
+use super::prefab::Prefab;
+use super::tilemap::Tileset;
+
+/// Reference to an asset by its path relative to the project root, e.g. `sprites/hero.png`.
+///
+/// Scenes and other assets only ever store these paths, never the asset data itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AssetRef(pub String);
+
+impl AssetRef {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self(path.into())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn path(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for AssetRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An image uploaded to the GPU.
+pub struct Texture {
+    pub handle: TextureHandle,
+    pub size: [usize; 2],
+}
+
+impl Texture {
+    /// Converts a region in pixels to texture coordinates.
+    pub fn uv(&self, region: Rect) -> Rect {
+        let size = self.handle.size_vec2();
+        Rect::from_min_max(
+            (region.min.to_vec2() / size).to_pos2(),
+            (region.max.to_vec2() / size).to_pos2(),
+        )
+    }
+}
+
+/// Named frames (in pixels) of an image, loaded from a sidecar JSON file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Atlas {
+    pub image: AssetRef,
+    pub frames: BTreeMap<String, Rect>,
+}
+
+/// All loaded assets, keyed by their path.
+#[derive(Default)]
+pub struct Assets {
+    pub prefabs: BTreeMap<AssetRef, Prefab>,
+    pub textures: BTreeMap<AssetRef, Texture>,
+    pub atlases: BTreeMap<AssetRef, Atlas>,
+    pub clips: BTreeMap<AssetRef, AnimationClip>,
+    pub tilesets: BTreeMap<AssetRef, Tileset>,
+    /// Shared with the mixer while playing.
+    pub sounds: BTreeMap<AssetRef, Arc<Sound>>,
+    /// Script sources. A new `Arc` means the script changed and is reloaded by running games.
+    pub scripts: BTreeMap<AssetRef, Arc<str>>,
+    /// TTF and OTF files, installed into the egui fonts by [`super::text::FontSettings`].
+    pub fonts: BTreeMap<AssetRef, Arc<[u8]>>,
+    /// The files the assets were imported from, for cooking them into a pack.
+    pub sources: BTreeMap<AssetRef, Arc<[u8]>>,
+}
+
+impl Assets {
+    /// A tileset together with its image, if both are loaded.
+    pub fn tileset(&self, tileset: &AssetRef) -> Option<(&Tileset, &Texture)> {
+        let tileset = self.tilesets.get(tileset)?;
+        Some((tileset, self.textures.get(&tileset.image)?))
+    }
+
+    /// Resolves an image or atlas reference to a texture and the region to draw, in pixels.
+    ///
+    /// An empty `region` of an atlas means its first frame.
+    pub fn image_region(&self, image: &AssetRef, region: &str) -> Option<(&Texture, Rect)> {
+        if let Some(texture) = self.textures.get(image) {
+            let [w, h] = texture.size;
+            return Some((
+                texture,
+                Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(w as f32, h as f32)),
+            ));
+        }
+        let atlas = self.atlases.get(image)?;
+        let frame = if region.is_empty() {
+            atlas.frames.values().next()?
+        } else {
+            atlas.frames.get(region)?
+        };
+        Some((self.textures.get(&atlas.image)?, *frame))
+    }
+
+    /// How many assets of each kind are loaded, and how big the bulk data of those which have
+    /// any is. Textures count 4 bytes per pixel, as uploaded to the GPU.
+    pub fn usage(&self) -> [AssetUsage; 8] {
+        let pixels = self.textures.values().map(|t| t.size[0] * t.size[1] * 4);
+        let samples = self.sounds.values().map(|s| s.samples.len() * 4);
+        let sources = self.scripts.values().map(|s| s.len());
+        let font_data = self.fonts.values().map(|f| f.len());
+        let usage = |kind, count, bytes| AssetUsage { kind, count, bytes };
+        [
+            usage("Textures", self.textures.len(), Some(pixels.sum())),
+            usage("Atlases", self.atlases.len(), None),
+            usage("Tilesets", self.tilesets.len(), None),
+            usage("Clips", self.clips.len(), None),
+            usage("Prefabs", self.prefabs.len(), None),
+            usage("Sounds", self.sounds.len(), Some(samples.sum())),
+            usage("Scripts", self.scripts.len(), Some(sources.sum())),
+            usage("Fonts", self.fonts.len(), Some(font_data.sum())),
+        ]
+    }
+}
+
+/// Number and memory of the loaded assets of one kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssetUsage {
+    pub kind: &'static str,
+    pub count: usize,
+    /// Bytes of pixels, samples, source text or font data, `None` for kinds without bulk data.
+    pub bytes: Option<usize>,
+}
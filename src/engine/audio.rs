@@ -0,0 +1,596 @@
+//! Sound playback: decoded [`Sound`] assets are played as voices of the [`Mixer`], which
+//! sums them through its buses into a stereo buffer.
+//!
+//! Where the buffer goes is up to the [`Audio`] backend: the sound card, nowhere (the null
+//! backend, for headless runs) or the caller, who renders it offline, e.g. in tests.
+
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use super::assets::{AssetRef, Assets};
+use super::scene::{Component, EntityId, Scene};
+
+/// Sample rate of the mixer until a device asks for another one.
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+pub const MASTER_BUS: &str = "Master";
+
+/// Decoded audio, interleaved samples in `-1.0..=1.0`.
+#[derive(Debug)]
+pub struct Sound {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+impl Sound {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Length in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate.max(1) as f32
+    }
+
+    pub fn from_wav(data: &[u8]) -> Result<Self, hound::Error> {
+        let mut reader = hound::WavReader::new(Cursor::new(data))?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 * scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(Self {
+            channels: spec.channels,
+            sample_rate: spec.sample_rate,
+            samples,
+        })
+    }
+
+    pub fn from_ogg(data: &[u8]) -> Result<Self, lewton::VorbisError> {
+        let mut reader = lewton::inside_ogg::OggStreamReader::new(Cursor::new(data))?;
+        let mut samples = vec![];
+        while let Some(packet) = reader.read_dec_packet_itl()? {
+            samples.extend(packet.into_iter().map(|s: i16| s as f32 / 32768.0));
+        }
+        Ok(Self {
+            channels: reader.ident_hdr.audio_channels as u16,
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            samples,
+        })
+    }
+
+    /// Sample of a channel at a fractional frame, linearly interpolated.
+    fn sample(&self, channel: usize, frame: f64, looping: bool) -> f32 {
+        let channels = self.channels as usize;
+        let frames = self.frames();
+        let channel = channel.min(channels - 1);
+        let i = frame as usize;
+        let next = if i + 1 < frames {
+            i + 1
+        } else if looping {
+            0
+        } else {
+            i
+        };
+        let t = frame.fract() as f32;
+        let a = self.samples[i * channels + channel];
+        let b = self.samples[next * channels + channel];
+        a + (b - a) * t
+    }
+}
+
+/// A group of voices with a common volume, e.g. music or effects. All buses feed the master.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bus {
+    pub name: String,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Bus {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            volume: 1.0,
+            muted: false,
+        }
+    }
+
+    fn gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VoiceId(u64);
+
+/// How to play a sound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayParams {
+    pub volume: f32,
+    /// -1 is left, 1 is right.
+    pub pan: f32,
+    pub looping: bool,
+    /// Name of the bus, unknown names play on the master bus.
+    pub bus: String,
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.0,
+            looping: false,
+            bus: MASTER_BUS.to_owned(),
+        }
+    }
+}
+
+struct Voice {
+    id: VoiceId,
+    sound: Arc<Sound>,
+    params: PlayParams,
+    /// Position in frames of the sound.
+    position: f64,
+    paused: bool,
+}
+
+/// Sums the playing voices into a stereo signal.
+pub struct Mixer {
+    pub sample_rate: u32,
+    /// The first bus is the master bus.
+    pub buses: Vec<Bus>,
+    voices: Vec<Voice>,
+    next_id: u64,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            buses: vec![Bus::new(MASTER_BUS), Bus::new("Music"), Bus::new("Effects")],
+            voices: vec![],
+            next_id: 0,
+        }
+    }
+}
+
+/// The mixer shared between the game and the audio thread.
+pub type MixerHandle = Arc<Mutex<Mixer>>;
+
+impl Mixer {
+    pub fn play(&mut self, sound: Arc<Sound>, params: PlayParams) -> VoiceId {
+        let id = VoiceId(self.next_id);
+        self.next_id += 1;
+        if sound.channels > 0 && sound.frames() > 0 {
+            self.voices.push(Voice {
+                id,
+                sound,
+                params,
+                position: 0.0,
+                paused: false,
+            });
+        }
+        id
+    }
+
+    /// Plays a sound once at full volume on a bus.
+    pub fn one_shot(&mut self, sound: Arc<Sound>, bus: &str) -> VoiceId {
+        self.play(
+            sound,
+            PlayParams {
+                bus: bus.to_owned(),
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.retain(|v| v.id != id);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Whether the voice is still playing (or paused), one-shots end by themselves.
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voices.iter().any(|v| v.id == id)
+    }
+
+    pub fn set_paused(&mut self, id: VoiceId, paused: bool) {
+        if let Some(v) = self.voices.iter_mut().find(|v| v.id == id) {
+            v.paused = paused;
+        }
+    }
+
+    /// Changes the volume, pan, looping or bus of a playing voice.
+    pub fn set_params(&mut self, id: VoiceId, params: PlayParams) {
+        if let Some(v) = self.voices.iter_mut().find(|v| v.id == id) {
+            v.params = params;
+        }
+    }
+
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn bus_mut(&mut self, name: &str) -> Option<&mut Bus> {
+        self.buses.iter_mut().find(|b| b.name == name)
+    }
+
+    fn bus_gain(&self, name: &str) -> f32 {
+        let master = self.buses.first().map_or(1.0, Bus::gain);
+        match self.buses.iter().skip(1).find(|b| b.name == name) {
+            Some(bus) => master * bus.gain(),
+            None => master,
+        }
+    }
+
+    /// Mixes the next `out.len() / 2` frames of interleaved stereo into `out`.
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        let gains: Vec<f32> = self
+            .voices
+            .iter()
+            .map(|v| self.bus_gain(&v.params.bus) * v.params.volume)
+            .collect();
+        for (v, gain) in self.voices.iter_mut().zip(gains) {
+            if v.paused {
+                continue;
+            }
+            let frames = v.sound.frames() as f64;
+            let step = v.sound.sample_rate as f64 / self.sample_rate as f64;
+            // Balance: the center plays both sides at full volume.
+            let pan = v.params.pan.clamp(-1.0, 1.0);
+            let left = gain * (1.0 - pan).min(1.0);
+            let right = gain * (1.0 + pan).min(1.0);
+            for frame in out.as_chunks_mut::<2>().0 {
+                if v.position >= frames {
+                    if !v.params.looping {
+                        break;
+                    }
+                    // A step can be longer than a short sound.
+                    v.position = v.position.rem_euclid(frames);
+                }
+                let looping = v.params.looping;
+                frame[0] += v.sound.sample(0, v.position, looping) * left;
+                frame[1] += v.sound.sample(1, v.position, looping) * right;
+                v.position += step;
+            }
+        }
+        self.voices
+            .retain(|v| v.params.looping || v.position < v.sound.frames() as f64);
+    }
+}
+
+enum Backend {
+    /// Discards the sound, but keeps the voices moving.
+    Null,
+    /// The caller pulls the sound with [`Audio::render`].
+    Offline,
+    /// Plays on the sound card as long as the stream is kept.
+    #[cfg(feature = "audio-device")]
+    Device { _stream: cpal::Stream },
+}
+
+/// The mixer and where its output goes.
+pub struct Audio {
+    pub mixer: MixerHandle,
+    backend: Backend,
+    /// Set when opening the device failed, so it is not tried every frame.
+    device_failed: bool,
+    scratch: Vec<f32>,
+    /// Leftover fraction of a frame of the null backend.
+    null_time: f64,
+    preview: Option<VoiceId>,
+}
+
+impl Audio {
+    fn new(backend: Backend) -> Self {
+        Self {
+            mixer: Default::default(),
+            backend,
+            device_failed: false,
+            scratch: vec![],
+            null_time: 0.0,
+            preview: None,
+        }
+    }
+
+    /// Plays nothing, the mixer advances with [`Self::update`].
+    pub fn null() -> Self {
+        Self::new(Backend::Null)
+    }
+
+    /// Renders only when asked to with [`Self::render`].
+    pub fn offline(sample_rate: u32) -> Self {
+        let audio = Self::new(Backend::Offline);
+        audio.mixer.lock().unwrap().sample_rate = sample_rate;
+        audio
+    }
+
+    /// Renders `frames` frames of interleaved stereo.
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let mut out = vec![0.0; frames * 2];
+        self.mixer.lock().unwrap().render(&mut out);
+        out
+    }
+
+    /// Switches from the null backend to the default output device, if there is one.
+    ///
+    /// Browsers only allow sound after a user action, so call this when the user starts
+    /// something that plays sound rather than at startup.
+    pub fn start_device(&mut self) {
+        if !matches!(self.backend, Backend::Null) || self.device_failed {
+            return;
+        }
+        #[cfg(feature = "audio-device")]
+        match device::open(self.mixer.clone()) {
+            Ok(stream) => {
+                log::info!(
+                    "Audio output at {} Hz.",
+                    self.mixer.lock().unwrap().sample_rate
+                );
+                self.backend = Backend::Device { _stream: stream };
+                return;
+            }
+            Err(e) => log::warn!("No audio output: {e}"),
+        }
+        self.device_failed = true;
+    }
+
+    /// Advances the null backend by `dt` seconds, the other backends are driven by the device
+    /// or the caller.
+    pub fn update(&mut self, dt: f32) {
+        if !matches!(self.backend, Backend::Null) {
+            return;
+        }
+        let mut mixer = self.mixer.lock().unwrap();
+        self.null_time += dt as f64 * mixer.sample_rate as f64;
+        let frames = self.null_time as usize;
+        self.null_time -= frames as f64;
+        self.scratch.resize(frames * 2, 0.0);
+        mixer.render(&mut self.scratch);
+    }
+
+    /// Plays a sound for the user, stopping the previous preview.
+    pub fn preview(&mut self, sound: Arc<Sound>) {
+        self.start_device();
+        self.stop_preview();
+        self.preview = Some(self.mixer.lock().unwrap().one_shot(sound, MASTER_BUS));
+    }
+
+    pub fn stop_preview(&mut self) {
+        if let Some(voice) = self.preview.take() {
+            self.mixer.lock().unwrap().stop(voice);
+        }
+    }
+
+    pub fn is_previewing(&self) -> bool {
+        self.preview
+            .is_some_and(|v| self.mixer.lock().unwrap().is_playing(v))
+    }
+}
+
+#[cfg(feature = "audio-device")]
+mod device {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SizedSample};
+
+    use super::MixerHandle;
+
+    pub fn open(mixer: MixerHandle) -> Result<cpal::Stream, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no output device")?;
+        let config = device.default_output_config().map_err(|e| e.to_string())?;
+        mixer.lock().unwrap().sample_rate = config.sample_rate().0;
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => build::<f32>(&device, &config.into(), mixer),
+            cpal::SampleFormat::I16 => build::<i16>(&device, &config.into(), mixer),
+            cpal::SampleFormat::U16 => build::<u16>(&device, &config.into(), mixer),
+            format => return Err(format!("unsupported sample format {format}")),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok(stream)
+    }
+
+    fn build<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mixer: MixerHandle,
+    ) -> Result<cpal::Stream, String> {
+        let channels = config.channels as usize;
+        let mut buffer = vec![];
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], _| {
+                    buffer.resize(data.len() / channels * 2, 0.0);
+                    mixer.lock().unwrap().render(&mut buffer);
+                    for (out, frame) in data.chunks_mut(channels).zip(buffer.chunks(2)) {
+                        for (c, sample) in out.iter_mut().enumerate() {
+                            *sample = T::from_sample(frame[c.min(1)].clamp(-1.0, 1.0));
+                        }
+                    }
+                },
+                |e| log::error!("Audio output: {e}"),
+                None,
+            )
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Plays a sound at the entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSource {
+    pub sound: AssetRef,
+    pub bus: String,
+    pub volume: f32,
+    pub pan: f32,
+    pub looping: bool,
+    /// Start playing when the game starts.
+    pub autoplay: bool,
+    /// The voice while playing, not saved.
+    #[serde(skip)]
+    pub voice: Option<VoiceId>,
+}
+
+impl Default for AudioSource {
+    fn default() -> Self {
+        Self {
+            sound: AssetRef::default(),
+            bus: "Effects".to_owned(),
+            volume: 1.0,
+            pan: 0.0,
+            looping: false,
+            autoplay: true,
+            voice: None,
+        }
+    }
+}
+
+impl AudioSource {
+    fn params(&self) -> PlayParams {
+        PlayParams {
+            volume: self.volume,
+            pan: self.pan,
+            looping: self.looping,
+            bus: self.bus.clone(),
+        }
+    }
+
+    /// Starts playing the sound from the beginning.
+    pub fn play(&mut self, assets: &Assets, mixer: &mut Mixer) {
+        if let Some(voice) = self.voice.take() {
+            mixer.stop(voice);
+        }
+        if let Some(sound) = assets.sounds.get(&self.sound) {
+            self.voice = Some(mixer.play(sound.clone(), self.params()));
+        }
+    }
+}
+
+/// Starts the autoplay sources on the first update and keeps the voices in sync with the
+/// source settings.
+pub fn update(scene: &mut Scene, assets: &Assets, mixer: &mut Mixer, first: bool) {
+    scene.walk_mut(&mut |e| {
+        for c in &mut e.components {
+            let Component::AudioSource(source) = c else {
+                continue;
+            };
+            match source.voice {
+                Some(voice) if mixer.is_playing(voice) => mixer.set_params(voice, source.params()),
+                Some(_) => source.voice = None,
+                None if first && source.autoplay => source.play(assets, mixer),
+                None => {}
+            }
+        }
+    });
+}
+
+/// Plays the sources of the entities, from the beginning.
+pub fn play(scene: &mut Scene, assets: &Assets, mixer: &mut Mixer, entities: &[EntityId]) {
+    for id in entities {
+        if let Some(e) = scene.find_mut(*id) {
+            for c in &mut e.components {
+                if let Component::AudioSource(source) = c {
+                    source.play(assets, mixer);
+                }
+            }
+        }
+    }
+}
+
+/// Stops the voices of all sources, when the game stops.
+pub fn stop(scene: &mut Scene, mixer: &mut Mixer) {
+    scene.walk_mut(&mut |e| {
+        for c in &mut e.components {
+            if let Component::AudioSource(source) = c {
+                if let Some(voice) = source.voice.take() {
+                    mixer.stop(voice);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8_000;
+
+    /// A mono sound of `frames` frames counting up from 1.
+    fn ramp(frames: usize, sample_rate: u32) -> Arc<Sound> {
+        Arc::new(Sound {
+            channels: 1,
+            sample_rate,
+            samples: (1..=frames).map(|i| i as f32).collect(),
+        })
+    }
+
+    fn play(audio: &Audio, sound: Arc<Sound>, looping: bool) -> VoiceId {
+        let params = PlayParams {
+            looping,
+            ..Default::default()
+        };
+        audio.mixer.lock().unwrap().play(sound, params)
+    }
+
+    #[test]
+    fn one_shot_ends_after_the_sound() {
+        let mut audio = Audio::offline(RATE);
+        let voice = play(&audio, ramp(4, RATE), false);
+        let out = audio.render(6);
+        assert_eq!(out.len(), 12);
+        let left: Vec<f32> = out.iter().step_by(2).copied().collect();
+        assert_eq!(left, [1.0, 2.0, 3.0, 4.0, 0.0, 0.0]);
+        assert!(!audio.mixer.lock().unwrap().is_playing(voice));
+    }
+
+    #[test]
+    fn looping_wraps_around() {
+        let mut audio = Audio::offline(RATE);
+        let voice = play(&audio, ramp(3, RATE), true);
+        let out = audio.render(7);
+        assert_eq!(out.len(), 14);
+        let left: Vec<f32> = out.iter().step_by(2).copied().collect();
+        assert_eq!(left, [1.0, 2.0, 3.0, 1.0, 2.0, 3.0, 1.0]);
+        assert!(audio.mixer.lock().unwrap().is_playing(voice));
+    }
+
+    #[test]
+    fn pitched_voice_skips_frames() {
+        // Twice the mixer rate plays every other frame.
+        let mut audio = Audio::offline(RATE);
+        play(&audio, ramp(8, RATE * 2), false);
+        let out = audio.render(6);
+        assert_eq!(out.len(), 12);
+        let left: Vec<f32> = out.iter().step_by(2).copied().collect();
+        assert_eq!(left, [1.0, 3.0, 5.0, 7.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn looping_wraps_when_a_step_is_longer_than_the_sound() {
+        let mut audio = Audio::offline(RATE);
+        let voice = play(&audio, ramp(2, RATE * 5), true);
+        let out = audio.render(4);
+        assert_eq!(out.len(), 8);
+        assert!(out.iter().all(|s| (1.0..=2.0).contains(s)));
+        assert!(audio.mixer.lock().unwrap().is_playing(voice));
+    }
+}
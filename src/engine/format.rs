@@ -0,0 +1,217 @@
+//! Versioned, human-readable file formats.
+//!
+//! Every engine file (scenes, prefabs, ...) is a pretty-printed JSON object with a top-level
+//! `version` field. Older files are upgraded by running [`Migration`]s over the raw JSON before
+//! it is deserialized, so renamed fields never need `#[serde(alias)]` clutter on the types.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Upgrades a document from version `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub apply: fn(&mut Value),
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// The text is not valid JSON, or does not match the expected structure.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The document was migrated but still does not match the expected structure.
+    Migrated {
+        from: u32,
+        message: String,
+    },
+    MissingVersion,
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {line}, column {column}: {message}"),
+            FormatError::Migrated { from, message } => {
+                write!(f, "after migrating from version {from}: {message}")
+            }
+            FormatError::MissingVersion => write!(f, "missing `version` field"),
+            FormatError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "version {found} is newer than the supported version {supported}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(e: serde_json::Error) -> Self {
+        let message = e.to_string();
+        // serde_json appends " at line X column Y" to the message, we report it separately.
+        let message = match message.rfind(" at line ") {
+            Some(i) => message[..i].to_owned(),
+            None => message,
+        };
+        FormatError::Parse {
+            line: e.line(),
+            column: e.column(),
+            message,
+        }
+    }
+}
+
+/// Parses a versioned document, migrating it to `version` if it is older.
+pub fn from_str<T: DeserializeOwned>(
+    text: &str,
+    version: u32,
+    migrations: &[Migration],
+) -> Result<T, FormatError> {
+    let mut value: Value = serde_json::from_str(text)?;
+    let found = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(FormatError::MissingVersion)? as u32;
+    if found > version {
+        return Err(FormatError::UnsupportedVersion {
+            found,
+            supported: version,
+        });
+    }
+    if found == version {
+        // Parse the text again so that structural errors point at a line and column.
+        return Ok(serde_json::from_str(text)?);
+    }
+    for v in found..version {
+        for m in migrations.iter().filter(|m| m.from == v) {
+            (m.apply)(&mut value);
+        }
+    }
+    value["version"] = version.into();
+    serde_json::from_value(value).map_err(|e| FormatError::Migrated {
+        from: found,
+        message: e.to_string(),
+    })
+}
+
+pub fn to_string<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("engine formats always serialize")
+}
+
+/// Migration helper: renames the field `from` to `to` in every object for which `filter` returns true.
+pub fn rename_field(
+    value: &mut Value,
+    filter: &dyn Fn(&Map<String, Value>) -> bool,
+    from: &str,
+    to: &str,
+) {
+    match value {
+        Value::Object(map) => {
+            if filter(map) {
+                if let Some(v) = map.remove(from) {
+                    map.insert(to.to_owned(), v);
+                }
+            }
+            for v in map.values_mut() {
+                rename_field(v, filter, from, to);
+            }
+        }
+        Value::Array(items) => {
+            for v in items {
+                rename_field(v, filter, from, to);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Doc {
+        version: u32,
+        name: String,
+    }
+
+    /// Version 1 called the name `title`.
+    const MIGRATIONS: &[Migration] = &[Migration {
+        from: 1,
+        apply: |v| rename_field(v, &|_| true, "title", "name"),
+    }];
+
+    #[test]
+    fn current_version_parses_as_is() {
+        let doc: Doc = from_str(r#"{"version": 2, "name": "a"}"#, 2, MIGRATIONS).unwrap();
+        assert_eq!(
+            doc,
+            Doc {
+                version: 2,
+                name: "a".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn older_versions_are_migrated() {
+        let doc: Doc = from_str(r#"{"version": 1, "title": "a"}"#, 2, MIGRATIONS).unwrap();
+        assert_eq!(doc.version, 2);
+        assert_eq!(doc.name, "a");
+    }
+
+    #[test]
+    fn rename_field_reaches_nested_objects() {
+        let mut value = serde_json::json!({"a": [{"kind": "x", "old": 1}, {"old": 2}]});
+        rename_field(&mut value, &|m| m.get("kind").is_some(), "old", "new");
+        assert_eq!(
+            value,
+            serde_json::json!({"a": [{"kind": "x", "new": 1}, {"old": 2}]})
+        );
+    }
+
+    #[test]
+    fn syntax_errors_have_line_and_column() {
+        let err =
+            from_str::<Doc>("{\n  \"version\": 2,\n  \"name\": }", 2, MIGRATIONS).unwrap_err();
+        let FormatError::Parse { line, column, .. } = err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert_eq!((line, column), (3, 11));
+    }
+
+    #[test]
+    fn structural_errors_point_into_the_text() {
+        let err =
+            from_str::<Doc>("{\n  \"version\": 2,\n  \"name\": 5\n}", 2, MIGRATIONS).unwrap_err();
+        assert!(matches!(err, FormatError::Parse { line: 3, .. }), "{err:?}");
+    }
+
+    #[test]
+    fn version_errors() {
+        let missing = from_str::<Doc>(r#"{"name": "a"}"#, 2, MIGRATIONS).unwrap_err();
+        assert!(matches!(missing, FormatError::MissingVersion));
+        let newer = from_str::<Doc>(r#"{"version": 3, "name": "a"}"#, 2, MIGRATIONS).unwrap_err();
+        assert!(matches!(
+            newer,
+            FormatError::UnsupportedVersion {
+                found: 3,
+                supported: 2
+            }
+        ));
+        let migrated = from_str::<Doc>(r#"{"version": 1, "name": 5}"#, 2, MIGRATIONS).unwrap_err();
+        assert!(matches!(migrated, FormatError::Migrated { from: 1, .. }));
+    }
+}
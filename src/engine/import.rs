@@ -0,0 +1,222 @@
+//! Turns files into assets, based on their extension.
+
+use egui::{ColorImage, Context, Rect, TextureOptions};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::animation::AnimationClip;
+use super::assets::{AssetRef, Assets, Atlas, Texture};
+use super::audio::Sound;
+use super::format::FormatError;
+use super::prefab::Prefab;
+use super::project::PROJECT_FILE;
+use super::tilemap::Tileset;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Font(ab_glyph::InvalidFont),
+    Format(FormatError),
+    Image(image::ImageError),
+    Json(serde_json::Error),
+    Ogg(lewton::VorbisError),
+    Wav(hound::Error),
+    Unsupported,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Font(e) => e.fmt(f),
+            ImportError::Format(e) => e.fmt(f),
+            ImportError::Image(e) => e.fmt(f),
+            ImportError::Json(e) => e.fmt(f),
+            ImportError::Ogg(e) => e.fmt(f),
+            ImportError::Wav(e) => e.fmt(f),
+            ImportError::Unsupported => write!(f, "unsupported file type"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<ab_glyph::InvalidFont> for ImportError {
+    fn from(e: ab_glyph::InvalidFont) -> Self {
+        ImportError::Font(e)
+    }
+}
+
+impl From<FormatError> for ImportError {
+    fn from(e: FormatError) -> Self {
+        ImportError::Format(e)
+    }
+}
+
+impl From<image::ImageError> for ImportError {
+    fn from(e: image::ImageError) -> Self {
+        ImportError::Image(e)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(e: serde_json::Error) -> Self {
+        ImportError::Json(e)
+    }
+}
+
+impl From<lewton::VorbisError> for ImportError {
+    fn from(e: lewton::VorbisError) -> Self {
+        ImportError::Ogg(e)
+    }
+}
+
+impl From<hound::Error> for ImportError {
+    fn from(e: hound::Error) -> Self {
+        ImportError::Wav(e)
+    }
+}
+
+/// Whether [`import`] knows the file type. Scenes are not assets, they are opened by the editor.
+pub fn is_importable(path: &str) -> bool {
+    path.ends_with(".png")
+        || path.ends_with(".wav")
+        || path.ends_with(".ogg")
+        || path.ends_with(".rhai")
+        || is_font(path)
+        || (path.ends_with(".json")
+            && !path.ends_with(".scene.json")
+            && path.rsplit('/').next() != Some(PROJECT_FILE))
+}
+
+fn is_font(path: &str) -> bool {
+    path.ends_with(".ttf") || path.ends_with(".otf")
+}
+
+/// Imports the file into `assets` under the reference `path`, replacing an older version.
+pub fn import(
+    ctx: &Context,
+    assets: &mut Assets,
+    path: &str,
+    data: &[u8],
+) -> Result<(), ImportError> {
+    let asset = AssetRef::new(path);
+    if path.ends_with(".png") {
+        let texture = load_texture(ctx, path, data)?;
+        assets.textures.insert(asset, texture);
+    } else if path.ends_with(".wav") {
+        assets
+            .sounds
+            .insert(asset, Arc::new(Sound::from_wav(data)?));
+    } else if path.ends_with(".ogg") {
+        assets
+            .sounds
+            .insert(asset, Arc::new(Sound::from_ogg(data)?));
+    } else if path.ends_with(".rhai") {
+        let source = String::from_utf8_lossy(data);
+        assets.scripts.insert(asset, source.into());
+    } else if is_font(path) {
+        // egui panics on broken fonts, so they are checked here.
+        ab_glyph::FontRef::try_from_slice(data)?;
+        assets.fonts.insert(asset, data.into());
+    } else if path.ends_with(".prefab.json") {
+        let prefab = Prefab::load(&String::from_utf8_lossy(data))?;
+        assets.prefabs.insert(asset, prefab);
+    } else if path.ends_with(".anim.json") {
+        let clip = AnimationClip::load(&String::from_utf8_lossy(data))?;
+        assets.clips.insert(asset, clip);
+    } else if path.ends_with(".tileset.json") {
+        let tileset = Tileset::load(&String::from_utf8_lossy(data))?;
+        assets.tilesets.insert(asset, tileset);
+    } else if path.ends_with(".json") {
+        let atlas = load_atlas(path, data)?;
+        assets.atlases.insert(asset, atlas);
+    } else {
+        return Err(ImportError::Unsupported);
+    }
+    assets.sources.insert(AssetRef::new(path), data.into());
+    Ok(())
+}
+
+fn load_texture(ctx: &Context, name: &str, data: &[u8]) -> Result<Texture, ImportError> {
+    let image = image::load_from_memory(data)?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    let image = ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    // Retro games want crisp pixels.
+    let handle = ctx.load_texture(name, image, TextureOptions::NEAREST);
+    Ok(Texture { handle, size })
+}
+
+// The sidecar format written by TexturePacker and Aseprite ("JSON Hash" or "JSON Array").
+
+#[derive(Deserialize)]
+struct SheetRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+struct SheetFrame {
+    #[serde(default)]
+    filename: String,
+    frame: SheetRect,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrames {
+    Hash(BTreeMap<String, SheetFrame>),
+    Array(Vec<SheetFrame>),
+}
+
+#[derive(Deserialize)]
+struct SheetMeta {
+    image: String,
+}
+
+#[derive(Deserialize)]
+struct Sheet {
+    frames: SheetFrames,
+    meta: SheetMeta,
+}
+
+fn load_atlas(path: &str, data: &[u8]) -> Result<Atlas, ImportError> {
+    let sheet: Sheet = serde_json::from_slice(data)?;
+    let frames: Vec<(String, SheetFrame)> = match sheet.frames {
+        SheetFrames::Hash(map) => map.into_iter().collect(),
+        SheetFrames::Array(list) => list.into_iter().map(|f| (f.filename.clone(), f)).collect(),
+    };
+    let frames = frames
+        .into_iter()
+        .map(|(name, f)| {
+            let r = f.frame;
+            (
+                name,
+                Rect::from_min_size((r.x, r.y).into(), (r.w, r.h).into()),
+            )
+        })
+        .collect();
+    // The image path is relative to the sidecar file.
+    let dir = path.rfind('/').map_or("", |i| &path[..=i]);
+    Ok(Atlas {
+        image: AssetRef::new(format!("{dir}{}", sheet.meta.image)),
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_project_file_itself_is_not_importable() {
+        assert!(!is_importable(PROJECT_FILE));
+        assert!(!is_importable(&format!("project/{PROJECT_FILE}")));
+        assert!(!is_importable("levels/one.scene.json"));
+        assert!(is_importable(&format!("my{PROJECT_FILE}")));
+        assert!(is_importable(&format!("project/data/my{PROJECT_FILE}")));
+        assert!(is_importable("project/hero.anim.json"));
+    }
+}
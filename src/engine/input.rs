@@ -0,0 +1,671 @@
+//! Named actions and axes, so game code asks for "jump" instead of polling the space key.
+//!
+//! The [`InputMap`] of the project binds actions to chords of keys, mouse and gamepad
+//! buttons, and axes to pairs of buttons or to gamepad sticks. Each frame the editor captures
+//! an [`InputFrame`] of what is held and hands it to [`Input::update`].
+
+use egui::{Key, PointerButton, Pos2};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// How far an analog input must be pushed to count as pressed.
+const PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 14] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftShoulder,
+        GamepadButton::RightShoulder,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::LeftStick,
+        GamepadButton::RightStick,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+}
+
+/// Analog gamepad inputs. Sticks go from -1 to 1 with y pointing down like the world,
+/// triggers from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX,
+        GamepadAxis::LeftY,
+        GamepadAxis::RightX,
+        GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+}
+
+impl MouseButton {
+    pub const ALL: [(MouseButton, PointerButton); 5] = [
+        (MouseButton::Left, PointerButton::Primary),
+        (MouseButton::Right, PointerButton::Secondary),
+        (MouseButton::Middle, PointerButton::Middle),
+        (MouseButton::Back, PointerButton::Extra1),
+        (MouseButton::Forward, PointerButton::Extra2),
+    ];
+}
+
+/// Something the player can hold: a key, a modifier, a mouse or gamepad button, or a
+/// gamepad axis pushed in one direction.
+///
+/// Saved as its name, e.g. `Space`, `Ctrl`, `MouseLeft`, `PadSouth` or `PadLeftX-`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum InputSource {
+    Key(Key),
+    Modifier(Modifier),
+    Mouse(MouseButton),
+    Pad(GamepadButton),
+    /// The axis pushed towards positive (`true`) or negative values.
+    PadAxis(GamepadAxis, bool),
+}
+
+impl InputSource {
+    /// All mouse and gamepad sources, which can't be bound by pressing them in the editor.
+    pub fn buttons() -> Vec<InputSource> {
+        let mut all: Vec<_> = MouseButton::ALL
+            .iter()
+            .map(|(b, _)| InputSource::Mouse(*b))
+            .collect();
+        all.extend(GamepadButton::ALL.map(InputSource::Pad));
+        for axis in GamepadAxis::ALL {
+            all.push(InputSource::PadAxis(axis, false));
+            all.push(InputSource::PadAxis(axis, true));
+        }
+        all
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Key(key) => write!(f, "{key:?}"),
+            InputSource::Modifier(m) => write!(f, "{m:?}"),
+            InputSource::Mouse(button) => write!(f, "Mouse{button:?}"),
+            InputSource::Pad(button) => write!(f, "Pad{button:?}"),
+            InputSource::PadAxis(axis, positive) => {
+                write!(f, "Pad{axis:?}{}", if *positive { "+" } else { "-" })
+            }
+        }
+    }
+}
+
+impl FromStr for InputSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Key and gamepad names are the names of the enum variants.
+        let variant = |name: &str| serde_json::Value::String(name.to_owned());
+        for m in [Modifier::Ctrl, Modifier::Shift, Modifier::Alt] {
+            if s == format!("{m:?}") {
+                return Ok(InputSource::Modifier(m));
+            }
+        }
+        if let Some(mouse) = s.strip_prefix("Mouse") {
+            if let Ok(button) = serde_json::from_value(variant(mouse)) {
+                return Ok(InputSource::Mouse(button));
+            }
+        }
+        if let Some(pad) = s.strip_prefix("Pad") {
+            if let Some(axis) = pad.strip_suffix('+') {
+                if let Ok(axis) = serde_json::from_value(variant(axis)) {
+                    return Ok(InputSource::PadAxis(axis, true));
+                }
+            }
+            if let Some(axis) = pad.strip_suffix('-') {
+                if let Ok(axis) = serde_json::from_value(variant(axis)) {
+                    return Ok(InputSource::PadAxis(axis, false));
+                }
+            }
+            if let Ok(button) = serde_json::from_value(variant(pad)) {
+                return Ok(InputSource::Pad(button));
+            }
+        }
+        serde_json::from_value(variant(s))
+            .map(InputSource::Key)
+            .map_err(|_| format!("unknown input `{s}`"))
+    }
+}
+
+impl From<InputSource> for String {
+    fn from(source: InputSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl TryFrom<String> for InputSource {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Inputs which must all be held at once, saved as e.g. `Ctrl+Shift+S`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Chord(pub Vec<InputSource>);
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.0.iter().map(|s| s.to_string()).collect();
+        f.write_str(&names.join("+"))
+    }
+}
+
+impl From<Chord> for String {
+    fn from(chord: Chord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl TryFrom<String> for Chord {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        // `+` also ends positive axis names, like `PadLeftX+`.
+        let mut sources = vec![];
+        let mut rest = s.as_str();
+        while !rest.is_empty() {
+            let (name, tail) = match rest.find('+') {
+                Some(i) if rest[..i].parse::<InputSource>().is_ok() => (&rest[..i], &rest[i + 1..]),
+                Some(i) if rest[..=i].parse::<InputSource>().is_ok() => {
+                    (&rest[..=i], rest[i + 1..].trim_start_matches('+'))
+                }
+                _ => (rest, ""),
+            };
+            sources.push(name.parse()?);
+            rest = tail;
+        }
+        Ok(Chord(sources))
+    }
+}
+
+impl From<InputSource> for Chord {
+    fn from(source: InputSource) -> Self {
+        Chord(vec![source])
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    /// The action is held while any of its chords is.
+    pub bindings: Vec<Chord>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum AxisBinding {
+    /// -1 while `negative` is held, 1 while `positive` is.
+    Buttons {
+        negative: InputSource,
+        positive: InputSource,
+    },
+    Analog {
+        axis: GamepadAxis,
+        #[serde(default)]
+        invert: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Axis {
+    /// Analog values closer to zero than this are zero, so worn sticks don't drift.
+    #[serde(default)]
+    pub dead_zone: f32,
+    pub bindings: Vec<AxisBinding>,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.2,
+            bindings: vec![],
+        }
+    }
+}
+
+/// All actions and axes of a game, saved in the project.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    pub actions: BTreeMap<String, Action>,
+    pub axes: BTreeMap<String, Axis>,
+}
+
+impl Default for InputMap {
+    /// Moving with WASD, the arrows or the left stick and jumping with space or the south button.
+    fn default() -> Self {
+        let buttons = |negative, positive| AxisBinding::Buttons {
+            negative: InputSource::Key(negative),
+            positive: InputSource::Key(positive),
+        };
+        let axis = |bindings| Axis {
+            bindings,
+            ..Default::default()
+        };
+        Self {
+            actions: BTreeMap::from([(
+                "jump".to_owned(),
+                Action {
+                    bindings: vec![
+                        InputSource::Key(Key::Space).into(),
+                        InputSource::Pad(GamepadButton::South).into(),
+                    ],
+                },
+            )]),
+            axes: BTreeMap::from([
+                (
+                    "move_x".to_owned(),
+                    axis(vec![
+                        buttons(Key::A, Key::D),
+                        buttons(Key::ArrowLeft, Key::ArrowRight),
+                        AxisBinding::Analog {
+                            axis: GamepadAxis::LeftX,
+                            invert: false,
+                        },
+                    ]),
+                ),
+                (
+                    "move_y".to_owned(),
+                    axis(vec![
+                        buttons(Key::W, Key::S),
+                        buttons(Key::ArrowUp, Key::ArrowDown),
+                        AxisBinding::Analog {
+                            axis: GamepadAxis::LeftY,
+                            invert: false,
+                        },
+                    ]),
+                ),
+            ]),
+        }
+    }
+}
+
+/// Everything held during one frame.
+#[derive(Clone, Debug, Default)]
+pub struct InputFrame {
+    pub keys: BTreeSet<Key>,
+    pub modifiers: egui::Modifiers,
+    pub mouse: BTreeSet<MouseButton>,
+    /// Pointer position on screen.
+    pub pointer: Option<Pos2>,
+    pub pad_buttons: BTreeSet<GamepadButton>,
+    pub pad_axes: BTreeMap<GamepadAxis, f32>,
+}
+
+impl InputFrame {
+    /// Reads the keyboard and the mouse from egui. Either can be left out, e.g. while a text
+    /// field has the focus or the pointer is outside of the game view.
+    pub fn capture(ctx: &egui::Context, keyboard: bool, pointer: bool) -> Self {
+        let mut frame = Self::default();
+        ctx.input(|i| {
+            if keyboard {
+                frame.keys = i.keys_down.iter().copied().collect();
+                frame.modifiers = i.modifiers;
+            }
+            if pointer {
+                frame.pointer = i.pointer.hover_pos();
+                for (button, pointer) in MouseButton::ALL {
+                    if i.pointer.button_down(pointer) {
+                        frame.mouse.insert(button);
+                    }
+                }
+            }
+        });
+        frame
+    }
+
+    /// How far the source is pushed, from 0 to 1.
+    pub fn value(&self, source: InputSource) -> f32 {
+        let held = match source {
+            InputSource::Key(key) => self.keys.contains(&key),
+            InputSource::Modifier(Modifier::Ctrl) => self.modifiers.ctrl,
+            InputSource::Modifier(Modifier::Shift) => self.modifiers.shift,
+            InputSource::Modifier(Modifier::Alt) => self.modifiers.alt,
+            InputSource::Mouse(button) => self.mouse.contains(&button),
+            InputSource::Pad(button) => self.pad_buttons.contains(&button),
+            InputSource::PadAxis(axis, positive) => {
+                let v = self.pad_axes.get(&axis).copied().unwrap_or_default();
+                return if positive { v.max(0.0) } else { (-v).max(0.0) };
+            }
+        };
+        if held {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn held(&self, source: InputSource) -> bool {
+        self.value(source) >= PRESS_THRESHOLD
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ActionState {
+    down: bool,
+    was_down: bool,
+}
+
+/// The input state of a running game.
+#[derive(Default)]
+pub struct Input {
+    pub map: InputMap,
+    frame: InputFrame,
+    actions: BTreeMap<String, ActionState>,
+    axes: BTreeMap<String, f32>,
+}
+
+fn dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone).max(f32::EPSILON)
+    }
+}
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            ..Default::default()
+        }
+    }
+
+    /// Updates the actions and axes from what is held this frame.
+    pub fn update(&mut self, frame: InputFrame) {
+        // A chord hides the chords it contains, so Ctrl+S doesn't also trigger S.
+        let held: Vec<&Chord> = self
+            .map
+            .actions
+            .values()
+            .flat_map(|a| &a.bindings)
+            .filter(|c| !c.0.is_empty() && c.0.iter().all(|s| frame.held(*s)))
+            .collect();
+        let hidden = |chord: &Chord| {
+            held.iter().any(|other| {
+                other.0.len() > chord.0.len() && chord.0.iter().all(|s| other.0.contains(s))
+            })
+        };
+        for (name, action) in &self.map.actions {
+            let down = action
+                .bindings
+                .iter()
+                .any(|c| held.contains(&c) && !hidden(c));
+            let state = self.actions.entry(name.clone()).or_default();
+            state.was_down = state.down;
+            state.down = down;
+        }
+        self.actions
+            .retain(|name, _| self.map.actions.contains_key(name));
+
+        self.axes.clear();
+        for (name, axis) in &self.map.axes {
+            let value: f32 = axis
+                .bindings
+                .iter()
+                .map(|b| match b {
+                    AxisBinding::Buttons { negative, positive } => {
+                        frame.value(*positive) - frame.value(*negative)
+                    }
+                    AxisBinding::Analog { axis: a, invert } => {
+                        let v = frame.pad_axes.get(a).copied().unwrap_or_default();
+                        let v = dead_zone(v, axis.dead_zone);
+                        if *invert {
+                            -v
+                        } else {
+                            v
+                        }
+                    }
+                })
+                .sum();
+            self.axes.insert(name.clone(), value.clamp(-1.0, 1.0));
+        }
+        self.frame = frame;
+    }
+
+    /// Whether the action is held.
+    pub fn pressed(&self, action: &str) -> bool {
+        self.actions.get(action).is_some_and(|s| s.down)
+    }
+
+    /// Whether the action was pressed this frame.
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|s| s.down && !s.was_down)
+    }
+
+    /// Whether the action was released this frame.
+    pub fn just_released(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|s| !s.down && s.was_down)
+    }
+
+    /// Value of the axis from -1 to 1, 0 for unknown axes.
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axes.get(axis).copied().unwrap_or_default()
+    }
+
+    /// Pointer position on screen, while it is over the game.
+    pub fn pointer(&self) -> Option<Pos2> {
+        self.frame.pointer
+    }
+
+    pub fn frame(&self) -> &InputFrame {
+        &self.frame
+    }
+}
+
+/// Connected gamepads. Without the `gamepad` feature there are none.
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(not(feature = "gamepad"))]
+impl Gamepads {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn poll(&mut self, _frame: &mut InputFrame) {}
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = gilrs::Gilrs::new()
+            .map_err(|e| log::warn!("Gamepads are not available: {e}"))
+            .ok();
+        Self { gilrs }
+    }
+
+    /// Adds the buttons and axes of all connected gamepads to `frame`.
+    pub fn poll(&mut self, frame: &mut InputFrame) {
+        use gilrs::{Axis as A, Button as B};
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while gilrs.next_event().is_some() {}
+        for (_, pad) in gilrs.gamepads() {
+            for button in GamepadButton::ALL {
+                let b = match button {
+                    GamepadButton::South => B::South,
+                    GamepadButton::East => B::East,
+                    GamepadButton::West => B::West,
+                    GamepadButton::North => B::North,
+                    GamepadButton::LeftShoulder => B::LeftTrigger,
+                    GamepadButton::RightShoulder => B::RightTrigger,
+                    GamepadButton::Select => B::Select,
+                    GamepadButton::Start => B::Start,
+                    GamepadButton::LeftStick => B::LeftThumb,
+                    GamepadButton::RightStick => B::RightThumb,
+                    GamepadButton::DPadUp => B::DPadUp,
+                    GamepadButton::DPadDown => B::DPadDown,
+                    GamepadButton::DPadLeft => B::DPadLeft,
+                    GamepadButton::DPadRight => B::DPadRight,
+                };
+                if pad.is_pressed(b) {
+                    frame.pad_buttons.insert(button);
+                }
+            }
+            for axis in GamepadAxis::ALL {
+                // gilrs has y pointing up, and the triggers as analog buttons.
+                let value = match axis {
+                    GamepadAxis::LeftX => pad.value(A::LeftStickX),
+                    GamepadAxis::LeftY => -pad.value(A::LeftStickY),
+                    GamepadAxis::RightX => pad.value(A::RightStickX),
+                    GamepadAxis::RightY => -pad.value(A::RightStickY),
+                    GamepadAxis::LeftTrigger => {
+                        pad.button_data(B::LeftTrigger2).map_or(0.0, |d| d.value())
+                    }
+                    GamepadAxis::RightTrigger => {
+                        pad.button_data(B::RightTrigger2).map_or(0.0, |d| d.value())
+                    }
+                };
+                // With several gamepads, the one pushed furthest wins.
+                let v = frame.pad_axes.entry(axis).or_default();
+                if value.abs() > v.abs() {
+                    *v = value;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(s: &str) -> Result<Chord, String> {
+        Chord::try_from(s.to_owned())
+    }
+
+    #[test]
+    fn chords_are_parsed_by_their_names() {
+        assert_eq!(
+            chord("Ctrl+Shift+S"),
+            Ok(Chord(vec![
+                InputSource::Modifier(Modifier::Ctrl),
+                InputSource::Modifier(Modifier::Shift),
+                InputSource::Key(Key::S),
+            ]))
+        );
+        assert_eq!(
+            chord("MouseRight+PadSouth"),
+            Ok(Chord(vec![
+                InputSource::Mouse(MouseButton::Right),
+                InputSource::Pad(GamepadButton::South),
+            ]))
+        );
+    }
+
+    #[test]
+    fn axis_signs_are_not_separators() {
+        assert_eq!(
+            chord("PadLeftX++Space"),
+            Ok(Chord(vec![
+                InputSource::PadAxis(GamepadAxis::LeftX, true),
+                InputSource::Key(Key::Space),
+            ]))
+        );
+        assert_eq!(
+            chord("Alt+PadRightY+"),
+            Ok(Chord(vec![
+                InputSource::Modifier(Modifier::Alt),
+                InputSource::PadAxis(GamepadAxis::RightY, true),
+            ]))
+        );
+        assert_eq!(
+            chord("PadLeftTrigger-"),
+            Ok(Chord(vec![InputSource::PadAxis(
+                GamepadAxis::LeftTrigger,
+                false
+            )]))
+        );
+    }
+
+    #[test]
+    fn chords_round_trip() {
+        for text in [
+            "Ctrl+Shift+S",
+            "PadLeftX++Space",
+            "Alt+PadRightY-",
+            "MouseBack",
+        ] {
+            assert_eq!(chord(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn unknown_inputs_are_errors() {
+        assert_eq!(chord("Ctrl+Nope"), Err("unknown input `Nope`".to_owned()));
+        assert_eq!(
+            chord("PadLeftX*"),
+            Err("unknown input `PadLeftX*`".to_owned())
+        );
+    }
+}
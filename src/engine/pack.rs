@@ -0,0 +1,587 @@
+//! Cooked games: the project, its scenes and the assets they use in a single file, in formats
+//! which load without parsing.
+//!
+//! A pack starts with [`MAGIC`], the format version and the length of the index, followed by the
+//! index and the entries. Every entry is deflated. Text is stored as is and the other assets as
+//! MessagePack.
+//!
+//! Images and sounds are not converted to GPU or audio codecs, there is no encoder for those among
+//! the dependencies. Images are stored as RGBA pixels so loading needs no decoder, which deflates
+//! about as small as PNG for pixel art but is bigger for photos. Sounds are stored as the files
+//! they were imported from: OGG stays compressed, WAV is only deflated.
+
+use egui::{ColorImage, Context, TextureOptions};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+use super::assets::{AssetRef, Assets, Texture};
+use super::audio::Sound;
+use super::import;
+use super::project::Project;
+use super::scene::Scene;
+
+pub const PACK_EXTENSION: &str = ".pack";
+pub const PACK_VERSION: u32 = 2;
+const MAGIC: &[u8; 8] = b"ARCHPACK";
+/// Name of the project entry.
+const PROJECT_NAME: &str = "project";
+
+#[derive(Debug)]
+pub enum PackError {
+    NotAPack,
+    Version(u32),
+    /// An entry points outside the file or does not inflate.
+    Corrupt(String),
+    /// A loaded asset whose file is not kept, so there is nothing to cook it from.
+    NoSource(AssetRef),
+    Decode(rmp_serde::decode::Error),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::NotAPack => write!(f, "not a pack file"),
+            PackError::Version(v) => write!(f, "pack version {v}, expected {PACK_VERSION}"),
+            PackError::Corrupt(name) => write!(f, "entry {name} is corrupt"),
+            PackError::NoSource(asset) => write!(f, "the file of {asset} is not loaded"),
+            PackError::Decode(e) => e.fmt(f),
+            PackError::Image(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<rmp_serde::decode::Error> for PackError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        PackError::Decode(e)
+    }
+}
+
+impl From<image::ImageError> for PackError {
+    fn from(e: image::ImageError) -> Self {
+        PackError::Image(e)
+    }
+}
+
+/// What an entry of a pack holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    Project,
+    Scene,
+    Texture,
+    Atlas,
+    Tileset,
+    Clip,
+    Prefab,
+    Sound,
+    Script,
+    Font,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackEntry {
+    /// The asset path, or the file name of a scene.
+    pub name: String,
+    pub kind: EntryKind,
+    /// Position of the deflated data after the index.
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Which assets were packed, and what the project uses but doesn't have.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CookReport {
+    /// Loaded assets nothing refers to, left out of the pack.
+    pub unused: Vec<AssetRef>,
+    /// Referenced assets which are not loaded, with the first scene or asset referring to them.
+    pub missing: Vec<(AssetRef, String)>,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+impl CookReport {
+    /// Warns about the missing assets and lists the unused ones.
+    pub fn log(&self) {
+        for (asset, from) in &self.missing {
+            log::warn!("{from} refers to {asset}, which is missing.");
+        }
+        if !self.unused.is_empty() {
+            let unused: Vec<_> = self.unused.iter().map(AssetRef::path).collect();
+            log::info!("Left out unused assets: {}.", unused.join(", "));
+        }
+        log::info!(
+            "Packed {} entries into {:.1} KiB.",
+            self.entries,
+            self.bytes as f64 / 1024.0
+        );
+    }
+}
+
+/// Writes the project, the scenes and the assets they use into a pack.
+///
+/// The first scene is where the game starts. Editor data, like the panel layout and the links
+/// of prefab instances, is left out.
+pub fn cook(
+    project: &Project,
+    scenes: &[(String, Scene)],
+    assets: &Assets,
+) -> Result<(Vec<u8>, CookReport), PackError> {
+    let mut project = project.clone();
+    project.layout = None;
+    let scenes: Vec<_> = scenes
+        .iter()
+        .map(|(name, scene)| {
+            let mut scene = scene.clone();
+            scene.walk_mut(&mut |e| e.prefab = None);
+            (name, scene)
+        })
+        .collect();
+
+    // Every string which names an asset is a reference, the formats need no special cases.
+    let mut referenced = BTreeMap::new();
+    let mut pending = vec![(PROJECT_NAME.to_owned(), to_json(&project))];
+    pending.extend(scenes.iter().map(|(n, s)| (n.to_string(), to_json(s))));
+    while let Some((from, value)) = pending.pop() {
+        let mut found = vec![];
+        json_references(&value, &mut found);
+        if let Some(source) = assets.scripts.get(&AssetRef::new(&from)) {
+            script_references(source, &mut found);
+        }
+        for asset in found {
+            if referenced.contains_key(&asset) {
+                continue;
+            }
+            referenced.insert(asset.clone(), from.clone());
+            if let Some(value) = asset_json(assets, &asset) {
+                pending.push((asset.path().to_owned(), value));
+            } else if assets.scripts.contains_key(&asset) {
+                pending.push((asset.path().to_owned(), serde_json::Value::Null));
+            }
+        }
+    }
+
+    let mut writer = PackWriter::default();
+    writer.add(PROJECT_NAME, EntryKind::Project, &to_msgpack(&project));
+    for (name, scene) in &scenes {
+        writer.add(name, EntryKind::Scene, &to_msgpack(scene));
+    }
+    let mut report = CookReport::default();
+    for (asset, from) in &referenced {
+        match cook_asset(assets, asset)? {
+            Some((kind, data)) => writer.add(asset.path(), kind, &data),
+            None => report.missing.push((asset.clone(), from.clone())),
+        }
+    }
+    report.unused = loaded(assets)
+        .filter(|a| !referenced.contains_key(a))
+        .cloned()
+        .collect();
+    report.entries = writer.entries.len();
+    let pack = writer.finish();
+    report.bytes = pack.len();
+    Ok((pack, report))
+}
+
+fn to_json(value: &impl Serialize) -> serde_json::Value {
+    serde_json::to_value(value).expect("assets serialize to JSON")
+}
+
+fn to_msgpack(value: &impl Serialize) -> Vec<u8> {
+    // With field names, so skipped and defaulted fields work as in JSON.
+    rmp_serde::to_vec_named(value).expect("assets serialize to MessagePack")
+}
+
+/// Strings in a serialized asset which are paths of assets.
+fn json_references(value: &serde_json::Value, found: &mut Vec<AssetRef>) {
+    match value {
+        serde_json::Value::String(s) if import::is_importable(s) => found.push(AssetRef::new(s)),
+        serde_json::Value::Array(values) => values.iter().for_each(|v| json_references(v, found)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| json_references(v, found)),
+        _ => {}
+    }
+}
+
+/// String literals in a script which are paths of assets, like prefabs it spawns.
+fn script_references(source: &str, found: &mut Vec<AssetRef>) {
+    let literals = source.split('"').skip(1).step_by(2);
+    found.extend(
+        literals
+            .filter(|s| import::is_importable(s))
+            .map(AssetRef::new),
+    );
+}
+
+/// The loaded asset as JSON, for the assets which refer to others.
+fn asset_json(assets: &Assets, asset: &AssetRef) -> Option<serde_json::Value> {
+    let prefab = assets.prefabs.get(asset).map(to_json);
+    prefab
+        .or_else(|| assets.clips.get(asset).map(to_json))
+        .or_else(|| assets.tilesets.get(asset).map(to_json))
+        .or_else(|| assets.atlases.get(asset).map(to_json))
+}
+
+fn loaded(assets: &Assets) -> impl Iterator<Item = &AssetRef> {
+    let mut all = BTreeSet::new();
+    all.extend(assets.textures.keys());
+    all.extend(assets.atlases.keys());
+    all.extend(assets.tilesets.keys());
+    all.extend(assets.clips.keys());
+    all.extend(assets.prefabs.keys());
+    all.extend(assets.sounds.keys());
+    all.extend(assets.scripts.keys());
+    all.extend(assets.fonts.keys());
+    all.into_iter()
+}
+
+/// The asset in its runtime format, `None` if it is not loaded.
+fn cook_asset(
+    assets: &Assets,
+    asset: &AssetRef,
+) -> Result<Option<(EntryKind, Vec<u8>)>, PackError> {
+    let source = || {
+        assets
+            .sources
+            .get(asset)
+            .ok_or_else(|| PackError::NoSource(asset.clone()))
+    };
+    let cooked = if assets.textures.contains_key(asset) {
+        let image = image::load_from_memory(source()?)?.to_rgba8();
+        let mut data = Vec::with_capacity(8 + image.as_raw().len());
+        data.extend(image.width().to_le_bytes());
+        data.extend(image.height().to_le_bytes());
+        data.extend(image.as_raw());
+        (EntryKind::Texture, data)
+    } else if assets.sounds.contains_key(asset) {
+        (EntryKind::Sound, source()?.to_vec())
+    } else if let Some(source) = assets.scripts.get(asset) {
+        (EntryKind::Script, source.as_bytes().to_vec())
+    } else if let Some(font) = assets.fonts.get(asset) {
+        (EntryKind::Font, font.to_vec())
+    } else if let Some(prefab) = assets.prefabs.get(asset) {
+        (EntryKind::Prefab, to_msgpack(prefab))
+    } else if let Some(clip) = assets.clips.get(asset) {
+        (EntryKind::Clip, to_msgpack(clip))
+    } else if let Some(tileset) = assets.tilesets.get(asset) {
+        (EntryKind::Tileset, to_msgpack(tileset))
+    } else if let Some(atlas) = assets.atlases.get(asset) {
+        (EntryKind::Atlas, to_msgpack(atlas))
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(cooked))
+}
+
+#[derive(Default)]
+struct PackWriter {
+    entries: Vec<PackEntry>,
+    data: Vec<u8>,
+}
+
+impl PackWriter {
+    fn add(&mut self, name: &str, kind: EntryKind, data: &[u8]) {
+        let deflated = miniz_oxide::deflate::compress_to_vec(data, 6);
+        self.entries.push(PackEntry {
+            name: name.to_owned(),
+            kind,
+            offset: self.data.len() as u64,
+            len: deflated.len() as u64,
+        });
+        self.data.extend(deflated);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let index = rmp_serde::to_vec(&self.entries).expect("the index serializes");
+        let mut pack = Vec::with_capacity(16 + index.len() + self.data.len());
+        pack.extend(MAGIC);
+        pack.extend(PACK_VERSION.to_le_bytes());
+        pack.extend((index.len() as u32).to_le_bytes());
+        pack.extend(index);
+        pack.extend(self.data);
+        pack
+    }
+}
+
+/// A pack read into memory.
+pub struct Pack {
+    entries: Vec<PackEntry>,
+    data: Vec<u8>,
+}
+
+impl Pack {
+    pub fn read(mut bytes: Vec<u8>) -> Result<Self, PackError> {
+        if bytes.len() < 16 || &bytes[..8] != MAGIC {
+            return Err(PackError::NotAPack);
+        }
+        let word = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let version = word(8);
+        if version != PACK_VERSION {
+            return Err(PackError::Version(version));
+        }
+        let corrupt = || PackError::Corrupt("index".to_owned());
+        let index_end = usize::try_from(word(12))
+            .ok()
+            .and_then(|len| len.checked_add(16))
+            .ok_or_else(corrupt)?;
+        let index = bytes.get(16..index_end).ok_or_else(corrupt)?;
+        let entries = rmp_serde::from_slice(index)?;
+        bytes.drain(..index_end);
+        Ok(Self {
+            entries,
+            data: bytes,
+        })
+    }
+
+    pub fn entries(&self) -> &[PackEntry] {
+        &self.entries
+    }
+
+    /// The cooked data of an entry, as read from a mounted [`super::vfs::PackFs`].
+    pub fn inflate(&self, entry: &PackEntry) -> Result<Vec<u8>, PackError> {
+        let corrupt = || PackError::Corrupt(entry.name.clone());
+        let start = usize::try_from(entry.offset).map_err(|_| corrupt())?;
+        let end = start
+            .checked_add(usize::try_from(entry.len).map_err(|_| corrupt())?)
+            .ok_or_else(corrupt)?;
+        let deflated = self.data.get(start..end).ok_or_else(corrupt)?;
+        miniz_oxide::inflate::decompress_to_vec(deflated).map_err(|_| corrupt())
+    }
+
+    pub fn project(&self) -> Result<Project, PackError> {
+        let entry = self.entries.iter().find(|e| e.kind == EntryKind::Project);
+        let entry = entry.ok_or_else(|| PackError::Corrupt(PROJECT_NAME.to_owned()))?;
+        decode(&self.inflate(entry)?)
+    }
+
+    /// The scenes with the names of their files, the one the game starts with first.
+    pub fn scenes(&self) -> Result<Vec<(String, Scene)>, PackError> {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == EntryKind::Scene)
+            .map(|e| Ok((e.name.clone(), decode(&self.inflate(e)?)?)))
+            .collect()
+    }
+
+    /// Loads the assets, uploading the images as textures of `ctx`.
+    pub fn assets(&self, ctx: &Context) -> Result<Assets, PackError> {
+        let mut assets = Assets::default();
+        for entry in &self.entries {
+            load_asset(ctx, &mut assets, entry, self.inflate(entry)?)?;
+        }
+        Ok(assets)
+    }
+}
+
+/// Decodes the cooked data of the project, a scene or an asset stored as MessagePack.
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, PackError> {
+    Ok(rmp_serde::from_slice(data)?)
+}
+
+/// Adds the asset of an entry from its cooked `data`, uploading images as textures of `ctx`.
+/// The project and the scenes are no assets, they are skipped.
+pub fn load_asset(
+    ctx: &Context,
+    assets: &mut Assets,
+    entry: &PackEntry,
+    data: Vec<u8>,
+) -> Result<(), PackError> {
+    let asset = AssetRef::new(&entry.name);
+    match entry.kind {
+        EntryKind::Project | EntryKind::Scene => {}
+        EntryKind::Texture => {
+            let texture = texture(ctx, entry, &data)?;
+            assets.textures.insert(asset, texture);
+        }
+        EntryKind::Sound => {
+            let sound = sound(entry, &data)?;
+            assets.sounds.insert(asset, Arc::new(sound));
+        }
+        EntryKind::Script => {
+            let source = String::from_utf8_lossy(&data).into();
+            assets.scripts.insert(asset, source);
+        }
+        EntryKind::Font => {
+            assets.fonts.insert(asset, data.into());
+        }
+        EntryKind::Prefab => {
+            assets.prefabs.insert(asset, decode(&data)?);
+        }
+        EntryKind::Clip => {
+            assets.clips.insert(asset, decode(&data)?);
+        }
+        EntryKind::Tileset => {
+            assets.tilesets.insert(asset, decode(&data)?);
+        }
+        EntryKind::Atlas => {
+            assets.atlases.insert(asset, decode(&data)?);
+        }
+    }
+    Ok(())
+}
+
+fn texture(ctx: &Context, entry: &PackEntry, data: &[u8]) -> Result<Texture, PackError> {
+    let corrupt = || PackError::Corrupt(entry.name.clone());
+    let word = |i: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(data.get(i..i + 4)?.try_into().ok()?) as usize)
+    };
+    let size = [word(0).ok_or_else(corrupt)?, word(4).ok_or_else(corrupt)?];
+    let pixels = &data[8..];
+    let len = size[0].checked_mul(size[1]).and_then(|n| n.checked_mul(4));
+    if len != Some(pixels.len()) {
+        return Err(corrupt());
+    }
+    let image = ColorImage::from_rgba_unmultiplied(size, pixels);
+    let handle = ctx.load_texture(&entry.name, image, TextureOptions::NEAREST);
+    Ok(Texture { handle, size })
+}
+
+/// Decodes a sound, which is packed as the file it was imported from.
+fn sound(entry: &PackEntry, data: &[u8]) -> Result<Sound, PackError> {
+    let sound = if entry.name.ends_with(".ogg") {
+        Sound::from_ogg(data).ok()
+    } else {
+        Sound::from_wav(data).ok()
+    };
+    sound.ok_or_else(|| PackError::Corrupt(entry.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::scene::{Component, Sprite};
+    use std::io::Cursor;
+
+    fn png(size: u32) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(size, size, image::Rgba([255, 0, 0, 255]));
+        let mut png = Cursor::new(vec![]);
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        png.into_inner()
+    }
+
+    fn wav() -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = Cursor::new(vec![]);
+        let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+        for i in 0..100 {
+            writer.write_sample(i as i16 * 100).unwrap();
+        }
+        writer.finalize().unwrap();
+        wav.into_inner()
+    }
+
+    /// A game whose scene shows `hero.png` and runs `main.rhai`, which plays `jump.wav`.
+    fn game(ctx: &Context) -> (Project, Vec<(String, Scene)>, Assets) {
+        let mut assets = Assets::default();
+        for (name, size) in [("hero.png", 2), ("unused.png", 1)] {
+            let data = png(size);
+            let image = ColorImage::new([size as usize; 2], egui::Color32::RED);
+            let handle = ctx.load_texture(name, image, TextureOptions::NEAREST);
+            let size = [size as usize; 2];
+            assets
+                .textures
+                .insert(AssetRef::new(name), Texture { handle, size });
+            assets.sources.insert(AssetRef::new(name), data.into());
+        }
+        let jump = AssetRef::new("jump.wav");
+        let data = wav();
+        let sound = Sound::from_wav(&data).unwrap();
+        assets.sounds.insert(jump.clone(), Arc::new(sound));
+        assets.sources.insert(jump, data.into());
+        let script = AssetRef::new("main.rhai");
+        let source = r#"fn start() { play("jump.wav"); }"#;
+        assets.scripts.insert(script.clone(), source.into());
+
+        let mut scene = Scene::default();
+        let id = scene.spawn("Hero", None);
+        let hero = scene.find_mut(id).unwrap();
+        hero.components.push(Component::Sprite(Sprite {
+            image: AssetRef::new("hero.png"),
+            ..Default::default()
+        }));
+        hero.components
+            .push(Component::Script(crate::engine::script::Script { script }));
+        let scenes = vec![("main.scene.json".to_owned(), scene)];
+        (Project::default(), scenes, assets)
+    }
+
+    #[test]
+    fn cooked_games_read_back() {
+        let ctx = Context::default();
+        let (project, scenes, assets) = game(&ctx);
+        let (bytes, report) = cook(&project, &scenes, &assets).unwrap();
+        assert_eq!(report.unused, [AssetRef::new("unused.png")]);
+        assert!(report.missing.is_empty());
+        assert_eq!(report.entries, 5);
+        assert_eq!(report.bytes, bytes.len());
+
+        let pack = Pack::read(bytes).unwrap();
+        assert_eq!(pack.project().unwrap(), project);
+        assert_eq!(pack.scenes().unwrap(), scenes);
+        let loaded = pack.assets(&ctx).unwrap();
+        assert_eq!(loaded.textures[&AssetRef::new("hero.png")].size, [2, 2]);
+        assert!(!loaded.textures.contains_key(&AssetRef::new("unused.png")));
+        let jump = &loaded.sounds[&AssetRef::new("jump.wav")];
+        assert_eq!(
+            jump.samples,
+            assets.sounds[&AssetRef::new("jump.wav")].samples
+        );
+        assert_eq!(loaded.scripts, assets.scripts);
+    }
+
+    #[test]
+    fn missing_assets_are_reported() {
+        let ctx = Context::default();
+        let (project, scenes, mut assets) = game(&ctx);
+        assets.textures.clear();
+        let (_, report) = cook(&project, &scenes, &assets).unwrap();
+        let missing = (AssetRef::new("hero.png"), "main.scene.json".to_owned());
+        assert_eq!(report.missing, [missing]);
+    }
+
+    #[test]
+    fn corrupt_packs_are_errors() {
+        let ctx = Context::default();
+        let (project, scenes, assets) = game(&ctx);
+        let (bytes, _) = cook(&project, &scenes, &assets).unwrap();
+
+        assert!(matches!(
+            Pack::read(b"not a pack".to_vec()),
+            Err(PackError::NotAPack)
+        ));
+        let mut version = bytes.clone();
+        version[8] = 1;
+        assert!(matches!(Pack::read(version), Err(PackError::Version(1))));
+        // An index longer than the file.
+        let mut index = bytes.clone();
+        index[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(Pack::read(index), Err(PackError::Corrupt(_))));
+        // Entries pointing past the end of the data.
+        let mut pack = Pack::read(bytes).unwrap();
+        pack.data.truncate(10);
+        assert!(matches!(pack.project(), Err(PackError::Corrupt(_))));
+    }
+
+    #[test]
+    fn texture_sizes_must_match_their_pixels() {
+        let ctx = Context::default();
+        let mut writer = PackWriter::default();
+        let mut huge = vec![];
+        huge.extend(u32::MAX.to_le_bytes());
+        huge.extend(u32::MAX.to_le_bytes());
+        huge.extend([0; 16]);
+        writer.add("huge.png", EntryKind::Texture, &huge);
+        let pack = Pack::read(writer.finish()).unwrap();
+        assert!(matches!(pack.assets(&ctx), Err(PackError::Corrupt(name)) if name == "huge.png"));
+    }
+}
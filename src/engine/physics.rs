@@ -0,0 +1,771 @@
+//! 2D physics: [`RigidBody`] and [`Collider`] components simulated with a fixed time step.
+//!
+//! All shapes are convex polygons or circles and overlaps are found with the separating axis
+//! theorem. Bodies don't rotate, which suits most 2D games. An entity with colliders but no
+//! rigid body is static, like a wall or a tilemap.
+
+use egui::{Rect, Vec2};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::assets::Assets;
+use super::runtime::GameEvent;
+use super::scene::{Component, EntityId, Scene, Transform};
+use super::tilemap::{Tilemap, CHUNK_SIZE};
+
+/// Seconds per physics step, independent of the frame rate.
+pub const FIXED_STEP: f32 = 1.0 / 60.0;
+/// Steps per update at most, so a slow frame doesn't make the next one even slower.
+const MAX_STEPS: u32 = 8;
+const CIRCLE_SEGMENTS: usize = 24;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyKind {
+    /// Moved by gravity, impulses and collisions.
+    #[default]
+    Dynamic,
+    /// Moved only by its velocity, pushes dynamic bodies.
+    Kinematic,
+    /// Never moves.
+    Static,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RigidBody {
+    pub kind: BodyKind,
+    pub mass: f32,
+    /// World units per second.
+    pub velocity: Vec2,
+    pub gravity_scale: f32,
+    /// Fraction of the velocity lost per second, like air resistance.
+    pub damping: f32,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            kind: BodyKind::Dynamic,
+            mass: 1.0,
+            velocity: Vec2::ZERO,
+            gravity_scale: 1.0,
+            damping: 0.0,
+        }
+    }
+}
+
+impl RigidBody {
+    fn inverse_mass(&self) -> f32 {
+        match self.kind {
+            BodyKind::Dynamic if self.mass > 0.0 => 1.0 / self.mass,
+            _ => 0.0,
+        }
+    }
+
+    /// Changes the velocity of a dynamic body at once, like a jump or a hit.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.velocity += impulse * self.inverse_mass();
+    }
+}
+
+/// Shape of a collider in the local space of its entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ColliderShape {
+    Box {
+        size: Vec2,
+    },
+    Circle {
+        radius: f32,
+    },
+    /// Upright capsule, `height` includes the rounded ends.
+    Capsule {
+        radius: f32,
+        height: f32,
+    },
+    /// Convex polygon.
+    Polygon {
+        points: Vec<Vec2>,
+    },
+    /// The solid tiles of the [`Tilemap`] on the same entity.
+    Tilemap,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub offset: Vec2,
+    pub friction: f32,
+    /// Bounciness, 0 stops dead and 1 bounces back at full speed.
+    pub restitution: f32,
+    /// Triggers only report overlaps, they don't push anything.
+    pub trigger: bool,
+    /// Bits of the layers this collider is on.
+    pub layer: u32,
+    /// Bits of the layers this collider collides with.
+    pub mask: u32,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self {
+            shape: ColliderShape::Box {
+                size: Vec2::splat(16.0),
+            },
+            offset: Vec2::ZERO,
+            friction: 0.5,
+            restitution: 0.0,
+            trigger: false,
+            layer: 1,
+            mask: u32::MAX,
+        }
+    }
+}
+
+impl Collider {
+    fn collides_with(&self, other: &Collider) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+}
+
+/// A collider shape placed in the world.
+#[derive(Clone, Debug)]
+pub enum WorldShape {
+    Circle { center: Vec2, radius: f32 },
+    Polygon(Vec<Vec2>),
+}
+
+impl WorldShape {
+    fn center(&self) -> Vec2 {
+        match self {
+            WorldShape::Circle { center, .. } => *center,
+            WorldShape::Polygon(points) => {
+                points.iter().fold(Vec2::ZERO, |sum, p| sum + *p) / points.len().max(1) as f32
+            }
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        match self {
+            WorldShape::Circle { center, radius } => {
+                Rect::from_center_size(center.to_pos2(), Vec2::splat(2.0 * radius))
+            }
+            WorldShape::Polygon(points) => {
+                Rect::from_points(&points.iter().map(|p| p.to_pos2()).collect::<Vec<_>>())
+            }
+        }
+    }
+
+    fn translate(&mut self, d: Vec2) {
+        match self {
+            WorldShape::Circle { center, .. } => *center += d,
+            WorldShape::Polygon(points) => points.iter_mut().for_each(|p| *p += d),
+        }
+    }
+
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        match self {
+            WorldShape::Circle { center, radius } => {
+                let c = center.dot(axis);
+                (c - radius, c + radius)
+            }
+            WorldShape::Polygon(points) => points
+                .iter()
+                .map(|p| p.dot(axis))
+                .fold((f32::MAX, f32::MIN), |(lo, hi), d| (lo.min(d), hi.max(d))),
+        }
+    }
+
+    /// Axes to test against `other`: the edge normals of polygons and, for circles, the
+    /// direction to the closest corner of the other shape.
+    fn axes(&self, other: &WorldShape, axes: &mut Vec<Vec2>) {
+        match (self, other) {
+            (WorldShape::Polygon(points), _) => {
+                for (i, p) in points.iter().enumerate() {
+                    let edge = points[(i + 1) % points.len()] - *p;
+                    axes.push(Vec2::new(-edge.y, edge.x).normalized());
+                }
+            }
+            (WorldShape::Circle { center, .. }, WorldShape::Polygon(points)) => {
+                let closest = points.iter().min_by(|a, b| {
+                    (**a - *center)
+                        .length_sq()
+                        .total_cmp(&(**b - *center).length_sq())
+                });
+                if let Some(closest) = closest {
+                    axes.push((*closest - *center).normalized());
+                }
+            }
+            (WorldShape::Circle { .. }, WorldShape::Circle { .. }) => {}
+        }
+    }
+
+    /// Points around the shape, for debug drawing.
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self {
+            WorldShape::Circle { center, radius } => circle_points(*center, *radius, 0.0..=1.0),
+            WorldShape::Polygon(points) => points.clone(),
+        }
+    }
+}
+
+/// Points on an arc, `turns` is the part of the full circle to cover.
+fn circle_points(center: Vec2, radius: f32, turns: std::ops::RangeInclusive<f32>) -> Vec<Vec2> {
+    let n = ((turns.end() - turns.start()) * CIRCLE_SEGMENTS as f32).ceil() as usize;
+    (0..n)
+        .map(|i| {
+            let t = turns.start() + (turns.end() - turns.start()) * i as f32 / n as f32;
+            center + Vec2::angled(t * std::f32::consts::TAU) * radius
+        })
+        .collect()
+}
+
+/// A piece of a collider, a collider has several for tilemaps.
+struct Part {
+    shape: WorldShape,
+    /// Outward normals of faces touching a neighbouring solid tile. Bodies sliding over a
+    /// row of tiles would otherwise catch on the seams between them.
+    internal: Vec<Vec2>,
+}
+
+impl Part {
+    fn new(shape: WorldShape) -> Self {
+        Self {
+            shape,
+            internal: vec![],
+        }
+    }
+}
+
+/// The normal (pointing from `a` to `b`) and depth of the overlap of two parts.
+fn collide(a: &Part, b: &Part) -> Option<(Vec2, f32)> {
+    if let (
+        WorldShape::Circle {
+            center: ca,
+            radius: ra,
+        },
+        WorldShape::Circle {
+            center: cb,
+            radius: rb,
+        },
+    ) = (&a.shape, &b.shape)
+    {
+        let d = *cb - *ca;
+        let distance = d.length();
+        if distance >= ra + rb {
+            return None;
+        }
+        let normal = if distance > f32::EPSILON {
+            d / distance
+        } else {
+            Vec2::Y
+        };
+        return Some((normal, ra + rb - distance));
+    }
+
+    let mut axes = vec![];
+    a.shape.axes(&b.shape, &mut axes);
+    b.shape.axes(&a.shape, &mut axes);
+    let d = b.shape.center() - a.shape.center();
+    let mut best: Option<(Vec2, f32)> = None;
+    let mut any: Option<(Vec2, f32)> = None;
+    for axis in axes {
+        if !axis.is_finite() || axis == Vec2::ZERO {
+            continue;
+        }
+        let (a0, a1) = a.shape.project(axis);
+        let (b0, b1) = b.shape.project(axis);
+        if a1 <= b0 || b1 <= a0 {
+            // A separating axis.
+            return None;
+        }
+        let (normal, depth) = if axis.dot(d) >= 0.0 {
+            (axis, a1 - b0)
+        } else {
+            (-axis, b1 - a0)
+        };
+        let internal = a.internal.iter().any(|m| m.dot(normal) > 0.99)
+            || b.internal.iter().any(|m| m.dot(-normal) > 0.99);
+        if !internal && best.is_none_or(|(_, best)| depth < best) {
+            best = Some((normal, depth));
+        }
+        if any.is_none_or(|(_, any)| depth < any) {
+            any = Some((normal, depth));
+        }
+    }
+    // A shape fully inside a block of tiles has only internal faces to be pushed out of.
+    best.or(any)
+}
+
+/// A rigid body, or a static entity with colliders, during a physics step.
+struct Body<'a> {
+    entity: EntityId,
+    kind: BodyKind,
+    inverse_mass: f32,
+    velocity: Vec2,
+    gravity_scale: f32,
+    damping: f32,
+    world: Transform,
+    /// Distance moved in this step, in world space.
+    moved: Vec2,
+    colliders: Vec<&'a Collider>,
+    tilemap: Option<&'a Tilemap>,
+}
+
+impl Body<'_> {
+    fn translate(&mut self, d: Vec2) {
+        self.world.position += d;
+        self.moved += d;
+    }
+
+    /// The collider in world space. Tilemaps only give the tiles overlapping `query`.
+    fn parts(&self, collider: &Collider, assets: &Assets, query: Rect) -> Vec<Part> {
+        let world = &self.world;
+        let polygon = |points: Vec<Vec2>| {
+            let points = points
+                .into_iter()
+                .map(|p| world.apply(p + collider.offset))
+                .collect();
+            vec![Part::new(WorldShape::Polygon(points))]
+        };
+        match &collider.shape {
+            ColliderShape::Box { size } => {
+                let h = *size / 2.0;
+                polygon(vec![
+                    Vec2::new(-h.x, -h.y),
+                    Vec2::new(h.x, -h.y),
+                    Vec2::new(h.x, h.y),
+                    Vec2::new(-h.x, h.y),
+                ])
+            }
+            ColliderShape::Circle { radius } => {
+                let scale = world.scale.x.abs().max(world.scale.y.abs());
+                vec![Part::new(WorldShape::Circle {
+                    center: world.apply(collider.offset),
+                    radius: radius * scale,
+                })]
+            }
+            ColliderShape::Capsule { radius, height } => {
+                // Approximated by a polygon, the ends are half circles.
+                let half = (height / 2.0 - radius).max(0.0);
+                let mut points = circle_points(Vec2::new(0.0, half), *radius, 0.0..=0.5);
+                points.extend(circle_points(Vec2::new(0.0, -half), *radius, 0.5..=1.0));
+                polygon(points)
+            }
+            ColliderShape::Polygon { points } => polygon(points.clone()),
+            ColliderShape::Tilemap => self.tile_parts(collider, assets, query),
+        }
+    }
+
+    fn tile_parts(&self, collider: &Collider, assets: &Assets, query: Rect) -> Vec<Part> {
+        let Some(tilemap) = self.tilemap else {
+            return vec![];
+        };
+        let Some(tileset) = assets.tilesets.get(&tilemap.tileset) else {
+            return vec![];
+        };
+        let size = tileset.tile_size();
+        let world = &self.world;
+        let corners = [
+            query.left_top(),
+            query.right_top(),
+            query.right_bottom(),
+            query.left_bottom(),
+        ]
+        .map(|c| (world.inverse_apply(c.to_vec2()) - collider.offset).to_pos2());
+        let local = Rect::from_points(&corners);
+        let min = (local.min.to_vec2() / size).floor();
+        let max = (local.max.to_vec2() / size).floor();
+        let (min_x, min_y) = (min.x as i32, min.y as i32);
+        let (max_x, max_y) = (max.x as i32, max.y as i32);
+        let solid = |x: i32, y: i32| {
+            tilemap
+                .layers
+                .iter()
+                .any(|l| l.get(x, y).is_some_and(|t| tileset.solid.contains(&t)))
+        };
+        // Directions in world space, for the internal faces.
+        let direction = |d: Vec2| (world.apply(d) - world.apply(Vec2::ZERO)).normalized();
+        // Only the chunks holding tiles are looked at, the query may cover a huge empty area.
+        let mut cells = BTreeSet::new();
+        for layer in &tilemap.layers {
+            for (&(cx, cy), chunk) in &layer.chunks {
+                let (x0, y0) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+                let (x1, y1) = (x0 + CHUNK_SIZE - 1, y0 + CHUNK_SIZE - 1);
+                if x1 < min_x || x0 > max_x || y1 < min_y || y0 > max_y {
+                    continue;
+                }
+                for (x, y, tile) in chunk.tiles() {
+                    let (x, y) = (x0 + x, y0 + y);
+                    let inside = (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+                    if inside && tileset.solid.contains(&tile) {
+                        cells.insert((y, x));
+                    }
+                }
+            }
+        }
+        let mut parts = vec![];
+        for (y, x) in cells {
+            let min = Vec2::new(x as f32, y as f32) * size + collider.offset;
+            let points = [
+                min,
+                min + Vec2::new(size.x, 0.0),
+                min + size,
+                min + Vec2::new(0.0, size.y),
+            ]
+            .map(|p| world.apply(p));
+            let internal = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .into_iter()
+                .filter(|(dx, dy)| solid(x + dx, y + dy))
+                .map(|(dx, dy)| direction(Vec2::new(dx as f32, dy as f32)))
+                .collect();
+            parts.push(Part {
+                shape: WorldShape::Polygon(points.to_vec()),
+                internal,
+            });
+        }
+        parts
+    }
+}
+
+fn bodies<'a>(scene: &'a Scene) -> Vec<Body<'a>> {
+    let mut bodies = vec![];
+    scene.walk_world(&mut |e, world| {
+        let mut body = Body {
+            entity: e.id,
+            kind: BodyKind::Static,
+            inverse_mass: 0.0,
+            velocity: Vec2::ZERO,
+            gravity_scale: 0.0,
+            damping: 0.0,
+            world: world.clone(),
+            moved: Vec2::ZERO,
+            colliders: vec![],
+            tilemap: None,
+        };
+        let mut is_body = false;
+        for c in &e.components {
+            match c {
+                Component::RigidBody(rb) => {
+                    body.kind = rb.kind;
+                    body.inverse_mass = rb.inverse_mass();
+                    body.velocity = rb.velocity;
+                    body.gravity_scale = rb.gravity_scale;
+                    body.damping = rb.damping;
+                    is_body = true;
+                }
+                Component::Collider(c) => {
+                    body.colliders.push(c);
+                    is_body = true;
+                }
+                Component::Tilemap(t) => body.tilemap = Some(t),
+                _ => {}
+            }
+        }
+        if is_body {
+            bodies.push(body);
+        }
+    });
+    bodies
+}
+
+/// Pushes two overlapping bodies apart and changes their velocities for bounce and friction.
+/// Returns how far each was moved.
+fn resolve(
+    a: &mut Body<'_>,
+    b: &mut Body<'_>,
+    normal: Vec2,
+    depth: f32,
+    friction: f32,
+    restitution: f32,
+) -> (Vec2, Vec2) {
+    let total = a.inverse_mass + b.inverse_mass;
+    if total <= 0.0 {
+        return (Vec2::ZERO, Vec2::ZERO);
+    }
+    let da = -normal * depth * a.inverse_mass / total;
+    let db = normal * depth * b.inverse_mass / total;
+    a.translate(da);
+    b.translate(db);
+
+    let relative = b.velocity - a.velocity;
+    let approaching = relative.dot(normal);
+    if approaching < 0.0 {
+        let j = -(1.0 + restitution) * approaching / total;
+        a.velocity -= normal * j * a.inverse_mass;
+        b.velocity += normal * j * b.inverse_mass;
+
+        // Coulomb friction: the tangential impulse is limited by the normal impulse.
+        let relative = b.velocity - a.velocity;
+        let tangent = relative - normal * relative.dot(normal);
+        if tangent.length() > f32::EPSILON {
+            let tangent = tangent.normalized();
+            let jt = (-relative.dot(tangent) / total).clamp(-friction * j, friction * j);
+            a.velocity -= tangent * jt * a.inverse_mass;
+            b.velocity += tangent * jt * b.inverse_mass;
+        }
+    }
+    (da, db)
+}
+
+/// The physics state of a running game.
+pub struct Physics {
+    /// World units per second squared, y points down.
+    pub gravity: Vec2,
+    accumulator: f32,
+    /// Overlapping pairs (lower id first) and whether a trigger is involved.
+    contacts: BTreeMap<(EntityId, EntityId), bool>,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, 980.0),
+            accumulator: 0.0,
+            contacts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Physics {
+    /// Runs as many fixed steps as fit into `dt` seconds.
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        assets: &Assets,
+        dt: f32,
+        events: &mut Vec<GameEvent>,
+    ) {
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= FIXED_STEP {
+            if steps == MAX_STEPS {
+                // Give up catching up rather than slowing down every following frame.
+                self.accumulator = 0.0;
+                break;
+            }
+            self.step(scene, assets, events);
+            self.accumulator -= FIXED_STEP;
+            steps += 1;
+        }
+    }
+
+    fn step(&mut self, scene: &mut Scene, assets: &Assets, events: &mut Vec<GameEvent>) {
+        let h = FIXED_STEP;
+        let mut bodies = bodies(scene);
+        for b in &mut bodies {
+            match b.kind {
+                BodyKind::Dynamic => {
+                    b.velocity += self.gravity * b.gravity_scale * h;
+                    b.velocity /= 1.0 + b.damping * h;
+                    b.translate(b.velocity * h);
+                }
+                BodyKind::Kinematic => b.translate(b.velocity * h),
+                BodyKind::Static => {}
+            }
+        }
+
+        let mut contacts = BTreeMap::new();
+        for i in 0..bodies.len() {
+            let (head, tail) = bodies.split_at_mut(i + 1);
+            let a = &mut head[i];
+            for b in tail {
+                let triggers = a.colliders.iter().chain(&b.colliders).any(|c| c.trigger);
+                if a.kind != BodyKind::Dynamic && b.kind != BodyKind::Dynamic && !triggers {
+                    continue;
+                }
+                for ca in a.colliders.clone() {
+                    for cb in b.colliders.clone() {
+                        if !ca.collides_with(cb) {
+                            continue;
+                        }
+                        let trigger = ca.trigger || cb.trigger;
+                        if Self::collide_pair(a, ca, b, cb, assets, trigger) {
+                            let key = (a.entity.min(b.entity), a.entity.max(b.entity));
+                            *contacts.entry(key).or_insert(false) |= trigger;
+                        }
+                    }
+                }
+            }
+        }
+
+        for (&(a, b), &trigger) in &contacts {
+            if !self.contacts.contains_key(&(a, b)) {
+                events.push(GameEvent::CollisionStart { a, b, trigger });
+            }
+        }
+        for (&(a, b), &trigger) in &self.contacts {
+            if !contacts.contains_key(&(a, b)) {
+                events.push(GameEvent::CollisionStop { a, b, trigger });
+            }
+        }
+        self.contacts = contacts;
+
+        let moved: Vec<_> = bodies
+            .iter()
+            .filter(|b| b.kind != BodyKind::Static)
+            .map(|b| (b.entity, b.world.clone(), b.moved, b.velocity))
+            .collect();
+        for (id, world, moved, velocity) in moved {
+            let Some(e) = scene.find_mut(id) else {
+                continue;
+            };
+            for c in &mut e.components {
+                match c {
+                    Component::Transform(local) => {
+                        // The movement is in world space, turn it into the space of the parent.
+                        let parent = Transform {
+                            position: Vec2::ZERO,
+                            rotation: world.rotation - local.rotation,
+                            scale: world.scale / local.scale,
+                        };
+                        let d = parent.inverse_apply(moved);
+                        if d.is_finite() {
+                            local.position += d;
+                        }
+                    }
+                    Component::RigidBody(rb) => rb.velocity = velocity,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Tests two colliders and resolves the overlap, returns true if they touch.
+    fn collide_pair(
+        a: &mut Body<'_>,
+        ca: &Collider,
+        b: &mut Body<'_>,
+        cb: &Collider,
+        assets: &Assets,
+        trigger: bool,
+    ) -> bool {
+        let is_tilemap = |c: &Collider| c.shape == ColliderShape::Tilemap;
+        if is_tilemap(ca) && is_tilemap(cb) {
+            return false;
+        }
+        // A tilemap only gives the tiles around the other collider.
+        let (mut pa, mut pb) = if is_tilemap(ca) {
+            let pb = b.parts(cb, assets, Rect::EVERYTHING);
+            (a.parts(ca, assets, bounds(&pb)), pb)
+        } else {
+            let pa = a.parts(ca, assets, Rect::EVERYTHING);
+            let query = bounds(&pa);
+            (pa, b.parts(cb, assets, query))
+        };
+        let friction = (ca.friction * cb.friction).max(0.0).sqrt();
+        let restitution = ca.restitution.max(cb.restitution);
+        let mut touching = false;
+        for i in 0..pa.len() {
+            for j in 0..pb.len() {
+                let Some((normal, depth)) = collide(&pa[i], &pb[j]) else {
+                    continue;
+                };
+                touching = true;
+                if trigger {
+                    continue;
+                }
+                let (da, db) = resolve(a, b, normal, depth, friction, restitution);
+                pa.iter_mut().for_each(|p| p.shape.translate(da));
+                pb.iter_mut().for_each(|p| p.shape.translate(db));
+            }
+        }
+        touching
+    }
+}
+
+fn bounds(parts: &[Part]) -> Rect {
+    parts
+        .iter()
+        .fold(Rect::NOTHING, |r, p| r.union(p.shape.bounds()))
+        .expand(1.0)
+}
+
+/// Outlines of all colliders overlapping `visible`, and whether they are triggers, for debug
+/// drawing.
+pub fn debug_shapes(scene: &Scene, assets: &Assets, visible: Rect) -> Vec<(Vec<Vec2>, bool)> {
+    let mut shapes = vec![];
+    for body in bodies(scene) {
+        for c in &body.colliders {
+            for part in body.parts(c, assets, visible) {
+                if visible.intersects(part.shape.bounds()) {
+                    shapes.push((part.shape.outline(), c.trigger));
+                }
+            }
+        }
+    }
+    shapes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::assets::AssetRef;
+    use crate::engine::tilemap::{TileLayer, Tileset};
+
+    /// A level of 8px tiles with a solid floor from x = -1000 to 1000 at y = 0.
+    fn level() -> (Scene, Assets) {
+        let mut assets = Assets::default();
+        let tileset = AssetRef::new("level.tileset.json");
+        assets.tilesets.insert(
+            tileset.clone(),
+            Tileset {
+                tile_size: [8, 8],
+                solid: [0].into(),
+                ..Default::default()
+            },
+        );
+        let mut layer = TileLayer::new("Ground");
+        for x in -125..125 {
+            layer.set(x, 0, Some(0));
+        }
+        let mut scene = Scene::default();
+        let id = scene.spawn("Level", None);
+        let level = scene.find_mut(id).unwrap();
+        level.components.push(Component::Tilemap(Tilemap {
+            tileset,
+            layers: vec![layer],
+            ..Default::default()
+        }));
+        level.components.push(Component::Collider(Collider {
+            shape: ColliderShape::Tilemap,
+            ..Default::default()
+        }));
+        (scene, assets)
+    }
+
+    #[test]
+    fn big_bodies_land_on_small_tiles() {
+        let (mut scene, assets) = level();
+        let id = scene.spawn("Giant", None);
+        let giant = scene.find_mut(id).unwrap();
+        giant
+            .components
+            .push(Component::RigidBody(RigidBody::default()));
+        giant.components.push(Component::Collider(Collider {
+            shape: ColliderShape::Box {
+                size: Vec2::splat(600.0),
+            },
+            ..Default::default()
+        }));
+        let mut physics = Physics::default();
+        let mut events = vec![];
+        for _ in 0..60 {
+            physics.update(&mut scene, &assets, FIXED_STEP, &mut events);
+        }
+        let y = scene.find(id).unwrap().transform().unwrap().position.y;
+        // The bottom of the giant rests on the top of the floor.
+        assert!((y + 300.0).abs() < 2.0, "fell to {y}");
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, GameEvent::CollisionStart { .. })));
+    }
+
+    #[test]
+    fn zoomed_out_debug_shapes_include_the_tiles() {
+        let (scene, assets) = level();
+        let visible = Rect::from_min_max((-5000.0, -5000.0).into(), (5000.0, 5000.0).into());
+        assert_eq!(debug_shapes(&scene, &assets, visible).len(), 250);
+    }
+}
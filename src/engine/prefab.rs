@@ -0,0 +1,472 @@
+//! Prefabs: entity subtrees saved as `*.prefab.json` and instantiated into scenes.
+//!
+//! Every entity of an instance keeps a [`PrefabLink`] to the prefab entity it was created from,
+//! and the list of fields the instance overrides. [`sync_instances`] rebuilds the linked
+//! entities from the prefab while keeping those fields, so prefab changes reach all instances.
+//!
+//! Override paths are `name`, `<Component>.<field>` for a single field, or `<Component>` when
+//! the instance added or removed the whole component. The transform of the instance root is
+//! always owned by the instance, it is never propagated nor applied back to the prefab.
+//! Nested prefabs are not supported, links inside a prefab are dropped on creation.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::assets::AssetRef;
+use super::format::{self, FormatError, Migration};
+use super::scene::{Component, Entity, EntityId, Scene};
+
+pub const PREFAB_VERSION: u32 = 1;
+pub const PREFAB_MIGRATIONS: &[Migration] = &[];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrefabLink {
+    pub asset: AssetRef,
+    /// Id of the source entity inside the prefab.
+    pub source: EntityId,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    pub version: u32,
+    pub root: Entity,
+}
+
+impl Prefab {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, PREFAB_VERSION, PREFAB_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+
+    /// Creates a prefab from a copy of `entity` and turns `entity` into its instance.
+    pub fn create(asset: AssetRef, entity: &mut Entity) -> Self {
+        Prefab {
+            version: PREFAB_VERSION,
+            root: link_subtree(&asset, entity, &mut 1),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        let mut max = 0;
+        self.root.walk(&mut |e| max = max.max(e.id.0));
+        max + 1
+    }
+}
+
+/// Copies `entity` into a prefab subtree with ids starting at `next`, and links `entity` to it.
+fn link_subtree(asset: &AssetRef, entity: &mut Entity, next: &mut u64) -> Entity {
+    let mut copy = entity.clone();
+    let mut sources = vec![];
+    copy.walk_mut(&mut |e| {
+        e.id = EntityId(*next);
+        e.prefab = None;
+        sources.push(e.id);
+        *next += 1;
+    });
+    let mut sources = sources.into_iter();
+    entity.walk_mut(&mut |e| {
+        e.prefab = Some(PrefabLink {
+            asset: asset.clone(),
+            source: sources.next().expect("same tree shape"),
+            overrides: vec![],
+        });
+    });
+    copy
+}
+
+/// Adds a new instance of the prefab to the scene, returns the id of its root.
+pub fn instantiate(
+    scene: &mut Scene,
+    asset: &AssetRef,
+    prefab: &Prefab,
+    parent: Option<EntityId>,
+) -> EntityId {
+    let mut next = scene.next_id().0;
+    let entity = instantiate_entity(asset, &prefab.root, &mut next);
+    let id = entity.id;
+    scene.insert(entity, parent);
+    id
+}
+
+fn instantiate_entity(asset: &AssetRef, source: &Entity, next: &mut u64) -> Entity {
+    let id = EntityId(*next);
+    *next += 1;
+    Entity {
+        id,
+        name: source.name.clone(),
+        components: source.components.clone(),
+        children: source
+            .children
+            .iter()
+            .map(|c| instantiate_entity(asset, c, next))
+            .collect(),
+        prefab: Some(PrefabLink {
+            asset: asset.clone(),
+            source: source.id,
+            overrides: vec![],
+        }),
+    }
+}
+
+/// Returns the root of the prefab instance containing `id`.
+pub fn instance_root(scene: &Scene, id: EntityId) -> Option<EntityId> {
+    let asset = scene.find(id)?.prefab.as_ref()?.asset.clone();
+    let mut root = id;
+    while let Some(parent) = scene.parent_of(root) {
+        match scene.find(parent).and_then(|p| p.prefab.as_ref()) {
+            Some(link) if link.asset == asset => root = parent,
+            _ => break,
+        }
+    }
+    Some(root)
+}
+
+fn fields(component: &Component) -> Map<String, Value> {
+    match serde_json::to_value(component) {
+        Ok(Value::Object(mut map)) => {
+            map.remove("type");
+            map
+        }
+        _ => Map::new(),
+    }
+}
+
+fn with_fields(type_name: &str, mut fields: Map<String, Value>) -> Option<Component> {
+    fields.insert("type".to_owned(), type_name.into());
+    serde_json::from_value(Value::Object(fields)).ok()
+}
+
+fn find_component<'a>(entity: &'a Entity, type_name: &str) -> Option<&'a Component> {
+    entity
+        .components
+        .iter()
+        .find(|c| c.type_name() == type_name)
+}
+
+/// Whether the component is owned by the instance and not synchronized at all.
+fn instance_owned(overrides: &[String], is_root: bool, type_name: &str) -> bool {
+    (is_root && type_name == "Transform") || overrides.iter().any(|o| o == type_name)
+}
+
+/// Compares a linked entity with its prefab source and lists what the entity overrides.
+pub fn compute_overrides(entity: &Entity, source: &Entity, is_root: bool) -> Vec<String> {
+    let mut overrides = vec![];
+    if entity.name != source.name {
+        overrides.push("name".to_owned());
+    }
+    for c in &entity.components {
+        let type_name = c.type_name();
+        if is_root && type_name == "Transform" {
+            continue;
+        }
+        let Some(sc) = find_component(source, type_name) else {
+            overrides.push(type_name.to_owned());
+            continue;
+        };
+        let source_fields = fields(sc);
+        for (k, v) in fields(c) {
+            if source_fields.get(&k) != Some(&v) {
+                overrides.push(format!("{type_name}.{k}"));
+            }
+        }
+    }
+    for sc in &source.components {
+        if find_component(entity, sc.type_name()).is_none() {
+            overrides.push(sc.type_name().to_owned());
+        }
+    }
+    overrides
+}
+
+/// Recomputes the overrides of every linked entity in the subtree of `id`.
+pub fn update_overrides(scene: &mut Scene, id: EntityId, prefab: &Prefab) {
+    let Some(root) = instance_root(scene, id) else {
+        return;
+    };
+    if let Some(entity) = scene.find_mut(root) {
+        let asset = entity.prefab.as_ref().map(|l| l.asset.clone());
+        let mut is_root = true;
+        entity.walk_mut(&mut |e| {
+            if let Some(source) = e
+                .prefab
+                .as_ref()
+                .filter(|link| Some(&link.asset) == asset.as_ref())
+                .and_then(|link| prefab.root.find(link.source))
+            {
+                let overrides = compute_overrides(e, source, is_root);
+                if let Some(link) = &mut e.prefab {
+                    link.overrides = overrides;
+                }
+            }
+            is_root = false;
+        });
+    }
+}
+
+/// Rebuilds a linked entity from its source, keeping the overridden fields.
+fn sync_entity(entity: &mut Entity, source: &Entity, is_root: bool, next: &mut u64) {
+    let Some(link) = entity.prefab.clone() else {
+        return;
+    };
+    let overrides = &link.overrides;
+    if !overrides.iter().any(|o| o == "name") {
+        entity.name = source.name.clone();
+    }
+
+    let mut components = vec![];
+    for sc in &source.components {
+        let type_name = sc.type_name();
+        if instance_owned(overrides, is_root, type_name) {
+            continue;
+        }
+        let mut merged = fields(sc);
+        if let Some(c) = find_component(entity, type_name) {
+            let own = fields(c);
+            let prefix = format!("{type_name}.");
+            for field in overrides.iter().filter_map(|o| o.strip_prefix(&prefix)) {
+                if let Some(v) = own.get(field) {
+                    merged.insert(field.to_owned(), v.clone());
+                }
+            }
+        }
+        components.extend(with_fields(type_name, merged));
+    }
+    for c in &entity.components {
+        if instance_owned(overrides, is_root, c.type_name()) {
+            components.push(c.clone());
+        }
+    }
+    entity.components = components;
+
+    // Children: sync linked ones, add new ones from the prefab, drop those removed from it.
+    // Children added to the instance by hand are kept.
+    let mut children = vec![];
+    for sc in &source.children {
+        let existing = entity.children.iter().position(|c| {
+            c.prefab
+                .as_ref()
+                .is_some_and(|l| l.asset == link.asset && l.source == sc.id)
+        });
+        match existing {
+            Some(i) => {
+                let mut child = entity.children.remove(i);
+                sync_entity(&mut child, sc, false, next);
+                children.push(child);
+            }
+            None => children.push(instantiate_entity(&link.asset, sc, next)),
+        }
+    }
+    children.extend(
+        entity
+            .children
+            .drain(..)
+            .filter(|c| c.prefab.as_ref().is_none_or(|l| l.asset != link.asset)),
+    );
+    entity.children = children;
+}
+
+/// Rebuilds all instances of the prefab in the scene, e.g. after the prefab was reloaded.
+pub fn sync_instances(scene: &mut Scene, asset: &AssetRef, prefab: &Prefab) {
+    let mut roots = vec![];
+    scene.walk(&mut |e| {
+        if e.prefab.as_ref().is_some_and(|l| &l.asset == asset) {
+            roots.push(e.id);
+        }
+    });
+    roots.retain(|id| instance_root(scene, *id) == Some(*id));
+    let mut next = scene.next_id().0;
+    for id in roots {
+        if let Some(entity) = scene.find_mut(id) {
+            sync_entity(entity, &prefab.root, true, &mut next);
+        }
+    }
+}
+
+/// Writes the overrides of the instance containing `id` into the prefab, then clears them.
+///
+/// Children added to the instance by hand become part of the prefab. Afterwards the caller
+/// should [`sync_instances`] to propagate the change to the other instances.
+pub fn apply_overrides(scene: &mut Scene, id: EntityId, prefab: &mut Prefab) {
+    let Some(root) = instance_root(scene, id) else {
+        return;
+    };
+    let Some(instance) = scene.find_mut(root) else {
+        return;
+    };
+    let Some(asset) = instance.prefab.as_ref().map(|l| l.asset.clone()) else {
+        return;
+    };
+    let mut next = prefab.next_id();
+    apply_entity(instance, &asset, prefab, true, &mut next);
+}
+
+fn apply_entity(
+    entity: &mut Entity,
+    asset: &AssetRef,
+    prefab: &mut Prefab,
+    is_root: bool,
+    next: &mut u64,
+) {
+    let Some(link) = entity.prefab.as_mut() else {
+        return;
+    };
+    let overrides = std::mem::take(&mut link.overrides);
+    let source_id = link.source;
+    let Some(source) = prefab.root.find_mut(source_id) else {
+        return;
+    };
+    for o in &overrides {
+        if o == "name" {
+            source.name = entity.name.clone();
+        } else if let Some((type_name, field)) = o.split_once('.') {
+            let (Some(c), Some(i)) = (
+                find_component(entity, type_name),
+                source
+                    .components
+                    .iter()
+                    .position(|c| c.type_name() == type_name),
+            ) else {
+                continue;
+            };
+            let mut merged = fields(&source.components[i]);
+            if let Some(v) = fields(c).remove(field) {
+                merged.insert(field.to_owned(), v);
+            }
+            if let Some(new) = with_fields(type_name, merged) {
+                source.components[i] = new;
+            }
+        } else if !(is_root && o == "Transform") {
+            source.components.retain(|c| c.type_name() != o);
+            if let Some(c) = find_component(entity, o) {
+                source.components.push(c.clone());
+            }
+        }
+    }
+
+    for child in &mut entity.children {
+        if child.prefab.as_ref().is_some_and(|l| &l.asset == asset) {
+            apply_entity(child, asset, prefab, false, next);
+            continue;
+        }
+        // A child added to the instance becomes a new child of the source.
+        let added = link_subtree(asset, child, next);
+        if let Some(source) = prefab.root.find_mut(source_id) {
+            source.children.push(added);
+        }
+    }
+}
+
+/// Drops all overrides of the instance containing `id` and restores the prefab values.
+pub fn revert_overrides(scene: &mut Scene, id: EntityId, prefab: &Prefab) {
+    let Some(root) = instance_root(scene, id) else {
+        return;
+    };
+    let mut next = scene.next_id().0;
+    if let Some(instance) = scene.find_mut(root) {
+        instance.walk_mut(&mut |e| {
+            if let Some(link) = &mut e.prefab {
+                link.overrides.clear();
+            }
+        });
+        sync_entity(instance, &prefab.root, true, &mut next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::physics::Collider;
+    use crate::engine::scene::Sprite;
+    use egui::{Color32, Vec2};
+
+    fn sprite(entity: &mut Entity) -> &mut Sprite {
+        entity
+            .components
+            .iter_mut()
+            .find_map(|c| match c {
+                Component::Sprite(s) => Some(s),
+                _ => None,
+            })
+            .expect("a sprite")
+    }
+
+    /// A scene with a player and a prefab made from it, the player is an instance of it.
+    fn scene_with_instance() -> (Scene, EntityId, AssetRef, Prefab) {
+        let mut scene = Scene::default();
+        let id = scene.spawn("Player", None);
+        scene.spawn("Gun", Some(id));
+        let player = scene.find_mut(id).unwrap();
+        player.components.push(Component::Sprite(Sprite::default()));
+        let asset = AssetRef::new("player.prefab.json");
+        let prefab = Prefab::create(asset.clone(), player);
+        (scene, id, asset, prefab)
+    }
+
+    #[test]
+    fn overrides_list_changed_fields_and_components() {
+        let (mut scene, id, _, prefab) = scene_with_instance();
+        let player = scene.find_mut(id).unwrap();
+        player.name = "Hero".to_owned();
+        sprite(player).layer = 3;
+        player
+            .components
+            .push(Component::Collider(Collider::default()));
+        // The root transform belongs to the instance.
+        if let Component::Transform(t) = &mut player.components[0] {
+            t.position = Vec2::new(10.0, 0.0);
+        }
+        let overrides = compute_overrides(player, &prefab.root, true);
+        assert_eq!(overrides, ["name", "Sprite.layer", "Collider"]);
+    }
+
+    #[test]
+    fn sync_keeps_overrides_and_takes_prefab_changes() {
+        let (mut scene, id, asset, mut prefab) = scene_with_instance();
+        sprite(scene.find_mut(id).unwrap()).layer = 3;
+        update_overrides(&mut scene, id, &prefab);
+        sprite(&mut prefab.root).tint = Color32::RED;
+        prefab.root.children[0].name = "Sword".to_owned();
+        sync_instances(&mut scene, &asset, &prefab);
+
+        let player = scene.find_mut(id).unwrap();
+        assert_eq!(sprite(player).layer, 3);
+        assert_eq!(sprite(player).tint, Color32::RED);
+        assert_eq!(player.children[0].name, "Sword");
+    }
+
+    #[test]
+    fn apply_writes_overrides_into_the_prefab() {
+        let (mut scene, id, _, mut prefab) = scene_with_instance();
+        sprite(scene.find_mut(id).unwrap()).layer = 3;
+        scene.spawn("Shield", Some(id));
+        update_overrides(&mut scene, id, &prefab);
+        apply_overrides(&mut scene, id, &mut prefab);
+
+        assert_eq!(sprite(&mut prefab.root).layer, 3);
+        let children: Vec<_> = prefab.root.children.iter().map(|c| &c.name).collect();
+        assert_eq!(children, ["Gun", "Shield"]);
+        let player = scene.find(id).unwrap();
+        assert!(player.prefab.as_ref().unwrap().overrides.is_empty());
+        assert!(player.children[1].prefab.is_some());
+    }
+
+    #[test]
+    fn revert_restores_the_prefab_values() {
+        let (mut scene, id, _, prefab) = scene_with_instance();
+        let player = scene.find_mut(id).unwrap();
+        player.name = "Hero".to_owned();
+        sprite(player).layer = 3;
+        update_overrides(&mut scene, id, &prefab);
+        revert_overrides(&mut scene, id, &prefab);
+
+        let player = scene.find_mut(id).unwrap();
+        assert_eq!(player.name, "Player");
+        assert_eq!(sprite(player).layer, 0);
+        assert!(player.prefab.as_ref().unwrap().overrides.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Frame profiler: the time spent in scopes marked with [`profile_scope!`], kept for the last
+//! frames.
+//!
+//! The host calls [`begin_frame`] and [`end_frame`] around each frame. Scopes nest, so a frame is
+//! a tree of timings, which can be exported as a Chrome trace (`chrome://tracing`, Perfetto).
+//!
+//! [`profile_scope!`]: crate::profile_scope
+
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use web_time::Instant;
+
+/// Number of frames kept in the history.
+pub const HISTORY: usize = 600;
+/// Number of spikes kept, older ones are dropped.
+pub const MAX_SPIKES: usize = 20;
+
+/// Times the rest of the enclosing block under the given name.
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::engine::profiler::Scope::new($name);
+    };
+}
+
+/// A timed scope of a frame. Times are in seconds since the start of the frame.
+#[derive(Clone, Debug)]
+pub struct ScopeRecord {
+    pub name: &'static str,
+    /// Number of scopes this one is nested in.
+    pub depth: usize,
+    pub start: f64,
+    pub duration: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameRecord {
+    /// Counts up from 0 for every recorded frame.
+    pub index: u64,
+    /// Seconds since the profiler was created.
+    pub start: f64,
+    pub duration: f64,
+    /// In the order they started, so a scope comes before the scopes nested in it.
+    pub scopes: Vec<ScopeRecord>,
+}
+
+impl FrameRecord {
+    /// Total time of each scope name, longest first. Nested scopes with the same name as the
+    /// scope around them are not counted twice.
+    pub fn breakdown(&self) -> Vec<(&'static str, f64)> {
+        let mut totals = BTreeMap::new();
+        let mut open: Vec<&ScopeRecord> = vec![];
+        for scope in &self.scopes {
+            open.truncate(scope.depth);
+            if !open.iter().any(|s| s.name == scope.name) {
+                *totals.entry(scope.name).or_insert(0.0) += scope.duration;
+            }
+            open.push(scope);
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+        totals
+    }
+}
+
+/// Frame time statistics, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Stats {
+    pub fn new<'a>(frames: impl IntoIterator<Item = &'a FrameRecord>) -> Option<Self> {
+        let mut times: Vec<f64> = frames.into_iter().map(|f| f.duration).collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_by(f64::total_cmp);
+        let percentile = |p: f64| times[((times.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            min: times[0],
+            avg: times.iter().sum::<f64>() / times.len() as f64,
+            max: times[times.len() - 1],
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        })
+    }
+}
+
+/// The frame being recorded.
+struct Current {
+    start: Instant,
+    scopes: Vec<ScopeRecord>,
+    depth: usize,
+}
+
+pub struct Profiler {
+    epoch: Instant,
+    /// While paused no frames are recorded, to look at the history.
+    pub paused: bool,
+    /// Frames taking longer than this many seconds are kept as spikes.
+    pub spike_threshold: f64,
+    frames: VecDeque<FrameRecord>,
+    spikes: VecDeque<FrameRecord>,
+    current: Option<Current>,
+    next_index: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+            paused: false,
+            spike_threshold: 1.0 / 30.0,
+            frames: VecDeque::new(),
+            spikes: VecDeque::new(),
+            current: None,
+            next_index: 0,
+        }
+    }
+}
+
+impl Profiler {
+    /// The recorded frames, oldest first.
+    pub fn frames(&self) -> &VecDeque<FrameRecord> {
+        &self.frames
+    }
+
+    /// Frames slower than the spike threshold, oldest first. They are kept after they left the
+    /// history.
+    pub fn spikes(&self) -> &VecDeque<FrameRecord> {
+        &self.spikes
+    }
+
+    pub fn frame(&self, index: u64) -> Option<&FrameRecord> {
+        self.frames
+            .iter()
+            .chain(&self.spikes)
+            .find(|f| f.index == index)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.spikes.clear();
+    }
+
+    fn begin_frame(&mut self) {
+        self.current = (!self.paused).then(|| Current {
+            start: Instant::now(),
+            scopes: vec![],
+            depth: 0,
+        });
+    }
+
+    fn end_frame(&mut self) {
+        let Some(current) = self.current.take() else {
+            return;
+        };
+        let frame = FrameRecord {
+            index: self.next_index,
+            start: (current.start - self.epoch).as_secs_f64(),
+            duration: current.start.elapsed().as_secs_f64(),
+            scopes: current.scopes,
+        };
+        self.next_index += 1;
+        if frame.duration > self.spike_threshold {
+            if self.spikes.len() == MAX_SPIKES {
+                self.spikes.pop_front();
+            }
+            self.spikes.push_back(frame.clone());
+        }
+        if self.frames.len() == HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+/// Runs `f` with the profiler of this thread.
+pub fn with<R>(f: impl FnOnce(&mut Profiler) -> R) -> R {
+    PROFILER.with(|p| f(&mut p.borrow_mut()))
+}
+
+/// Starts recording a frame, unless the profiler is paused.
+pub fn begin_frame() {
+    with(Profiler::begin_frame);
+}
+
+/// Finishes the frame started with [`begin_frame`] and adds it to the history.
+pub fn end_frame() {
+    with(Profiler::end_frame);
+}
+
+/// Records the time until it is dropped, made by [`profile_scope!`].
+///
+/// [`profile_scope!`]: crate::profile_scope
+pub struct Scope {
+    /// Index of the record in the current frame.
+    record: Option<usize>,
+}
+
+impl Scope {
+    pub fn new(name: &'static str) -> Self {
+        let record = PROFILER.with(|p| {
+            // Scopes opened while the profiler itself is in use are not recorded.
+            let mut p = p.try_borrow_mut().ok()?;
+            let current = p.current.as_mut()?;
+            current.scopes.push(ScopeRecord {
+                name,
+                depth: current.depth,
+                start: current.start.elapsed().as_secs_f64(),
+                duration: 0.0,
+            });
+            current.depth += 1;
+            Some(current.scopes.len() - 1)
+        });
+        Self { record }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let Some(i) = self.record else {
+            return;
+        };
+        PROFILER.with(|p| {
+            let Ok(mut p) = p.try_borrow_mut() else {
+                return;
+            };
+            // The frame may have ended before the scope, e.g. in a panic.
+            let Some(current) = p.current.as_mut().filter(|c| i < c.scopes.len()) else {
+                return;
+            };
+            let end = current.start.elapsed().as_secs_f64();
+            let scope = &mut current.scopes[i];
+            scope.duration = end - scope.start;
+            current.depth -= 1;
+        });
+    }
+}
+
+/// The frames in the Chrome trace event format, as read by `chrome://tracing` and Perfetto.
+pub fn chrome_trace<'a>(frames: impl IntoIterator<Item = &'a FrameRecord>) -> String {
+    let micros = |seconds: f64| seconds * 1e6;
+    let mut events = vec![];
+    for frame in frames {
+        events.push(json!({
+            "name": format!("Frame {}", frame.index),
+            "ph": "X",
+            "ts": micros(frame.start),
+            "dur": micros(frame.duration),
+            "pid": 0,
+            "tid": 0,
+        }));
+        for scope in &frame.scopes {
+            events.push(json!({
+                "name": scope.name,
+                "ph": "X",
+                "ts": micros(frame.start + scope.start),
+                "dur": micros(scope.duration),
+                "pid": 0,
+                "tid": 0,
+            }));
+        }
+    }
+    let trace = json!({ "traceEvents": events, "displayTimeUnit": "ms" });
+    serde_json::to_string(&trace).expect("traces are plain data")
+}
@@ -0,0 +1,137 @@
+//! Settings of the whole game, as opposed to a single scene, saved as `project.json`.
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io};
+
+use super::format::{self, FormatError, Migration};
+#[cfg(not(target_arch = "wasm32"))]
+use super::import;
+use super::input::InputMap;
+#[cfg(not(target_arch = "wasm32"))]
+use super::scene::Scene;
+use super::text::FontSettings;
+
+pub const PROJECT_FILE: &str = "project.json";
+pub const PROJECT_VERSION: u32 = 1;
+pub const PROJECT_MIGRATIONS: &[Migration] = &[];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub name: String,
+    /// Actions and axes the game reads its input from.
+    #[serde(default)]
+    pub input: InputMap,
+    /// Families made of the project fonts.
+    #[serde(default)]
+    pub fonts: FontSettings,
+    /// Arrangement of the editor panels. The engine doesn't look inside.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<serde_json::Value>,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            version: PROJECT_VERSION,
+            name: "Untitled".to_owned(),
+            input: InputMap::default(),
+            fonts: FontSettings::default(),
+            layout: None,
+        }
+    }
+}
+
+impl Project {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, PROJECT_VERSION, PROJECT_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+}
+
+/// The files of a project folder: the project file, the scenes and the importable assets.
+///
+/// Assets are referenced by their path relative to the folder, with `/` between the parts.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProjectDir {
+    pub root: PathBuf,
+    /// The project file, if the folder has one.
+    pub project: Option<PathBuf>,
+    pub scenes: Vec<PathBuf>,
+    pub assets: Vec<PathBuf>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ProjectDir {
+    /// Lists the files in the folder and its subfolders.
+    pub fn read(root: &Path) -> io::Result<Self> {
+        let mut dir = Self {
+            root: root.to_owned(),
+            ..Default::default()
+        };
+        let mut folders = vec![root.to_owned()];
+        while let Some(folder) = folders.pop() {
+            for entry in fs::read_dir(&folder)? {
+                let entry = entry?;
+                // Version control and editor folders.
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let path = entry.path();
+                let name = path.to_string_lossy();
+                if path.is_dir() {
+                    folders.push(path);
+                } else if folder == root && entry.file_name() == PROJECT_FILE {
+                    dir.project = Some(path);
+                } else if name.ends_with(".scene.json") {
+                    dir.scenes.push(path);
+                } else if import::is_importable(&name) {
+                    dir.assets.push(path);
+                }
+            }
+        }
+        dir.scenes.sort();
+        dir.assets.sort();
+        Ok(dir)
+    }
+
+    /// The name the asset at `path` is referenced by.
+    pub fn reference(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let parts: Vec<_> = relative.iter().map(|p| p.to_string_lossy()).collect();
+        parts.join("/")
+    }
+
+    /// Reads the project file, or the default project if there is none.
+    pub fn load_project(&self) -> Result<Project, String> {
+        let Some(path) = &self.project else {
+            return Ok(Project::default());
+        };
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Project::load(&text).map_err(|e| e.to_string())
+    }
+
+    /// Finds a scene by its file, relative to the folder, or by its name.
+    pub fn find_scene(&self, name: &str) -> Option<&Path> {
+        let by_file = self
+            .scenes
+            .iter()
+            .find(|p| self.reference(p) == name || p.file_name().is_some_and(|f| f == name));
+        let by_name = || {
+            self.scenes.iter().find(|p| {
+                let scene = fs::read_to_string(p).ok();
+                scene
+                    .and_then(|s| Scene::load(&s).ok())
+                    .is_some_and(|s| s.name == name)
+            })
+        };
+        by_file.or_else(by_name).map(PathBuf::as_path)
+    }
+}
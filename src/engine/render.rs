@@ -0,0 +1,327 @@
+//! Draws scenes into an egui painter.
+//!
+//! World space uses pixels as units with y pointing down, so a sprite at scale 1 and camera
+//! zoom 1 covers exactly its image pixels on screen.
+
+use egui::epaint::TextShape;
+use egui::{Color32, Context, FontId, Mesh, Painter, Pos2, Rect, Shape, TextureId, Vec2};
+
+use super::assets::Assets;
+use super::scene::{Component, EntityId, Scene, Sprite, Transform};
+use super::text;
+use super::tilemap::{Tilemap, CHUNK_SIZE};
+
+#[derive(Clone, Debug)]
+pub struct Camera {
+    /// World position shown in the middle of the viewport.
+    pub center: Vec2,
+    /// Screen points per world unit.
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            center: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn world_to_screen(&self, viewport: Rect, p: Vec2) -> Pos2 {
+        viewport.center() + (p - self.center) * self.zoom
+    }
+
+    pub fn screen_to_world(&self, viewport: Rect, p: Pos2) -> Vec2 {
+        (p - viewport.center()) / self.zoom + self.center
+    }
+
+    /// The part of the world shown in `viewport`.
+    pub fn visible(&self, viewport: Rect) -> Rect {
+        Rect::from_center_size(self.center.to_pos2(), viewport.size() / self.zoom)
+    }
+}
+
+/// A sprite ready to draw: its corners in world space and where to sample the texture.
+pub struct SpriteQuad {
+    pub entity: EntityId,
+    pub layer: i32,
+    pub texture: TextureId,
+    /// Top left, top right, bottom right, bottom left.
+    pub corners: [Vec2; 4],
+    pub uv: Rect,
+    pub tint: Color32,
+}
+
+impl SpriteQuad {
+    pub fn contains(&self, p: Vec2) -> bool {
+        // The quad is a (possibly rotated or mirrored) rectangle: p must be on the same side
+        // of all four edges.
+        let sides = (0..4).map(|i| {
+            let a = self.corners[i];
+            let b = self.corners[(i + 1) % 4];
+            (b - a).x * (p - a).y - (b - a).y * (p - a).x
+        });
+        let (min, max) = sides.fold((f32::MAX, f32::MIN), |(lo, hi), s| (lo.min(s), hi.max(s)));
+        min >= 0.0 || max <= 0.0
+    }
+}
+
+fn sprite_quad(
+    entity: EntityId,
+    sprite: &Sprite,
+    world: &Transform,
+    assets: &Assets,
+) -> Option<SpriteQuad> {
+    let (texture, region) = assets.image_region(&sprite.image, &sprite.region)?;
+    let size = region.size();
+    let mut world = world.clone();
+    if sprite.pixel_snap {
+        world.position = world.position.round();
+    }
+    let min = -sprite.pivot * size;
+    let corners = [
+        min,
+        min + Vec2::new(size.x, 0.0),
+        min + size,
+        min + Vec2::new(0.0, size.y),
+    ]
+    .map(|c| world.apply(c));
+
+    let mut uv = texture.uv(region);
+    if sprite.flip_x {
+        std::mem::swap(&mut uv.min.x, &mut uv.max.x);
+    }
+    if sprite.flip_y {
+        std::mem::swap(&mut uv.min.y, &mut uv.max.y);
+    }
+    Some(SpriteQuad {
+        entity,
+        layer: sprite.layer,
+        texture: texture.handle.id(),
+        corners,
+        uv,
+        tint: sprite.tint,
+    })
+}
+
+/// Quads of the tiles of a tilemap which may be visible in `visible` (in world space).
+///
+/// Only the chunks overlapping the visible area are looked at, so big maps stay cheap.
+fn tile_quads(
+    entity: EntityId,
+    tilemap: &Tilemap,
+    world: &Transform,
+    assets: &Assets,
+    visible: Rect,
+    quads: &mut Vec<SpriteQuad>,
+) {
+    let Some((tileset, texture)) = assets.tileset(&tilemap.tileset) else {
+        return;
+    };
+    let size = tileset.tile_size();
+    // The visible area in the local space of the map, in chunks.
+    let local = [
+        visible.left_top(),
+        visible.right_top(),
+        visible.right_bottom(),
+        visible.left_bottom(),
+    ]
+    .map(|c| world.inverse_apply(c.to_vec2()).to_pos2());
+    let local = Rect::from_points(&local);
+    let chunk = size * CHUNK_SIZE as f32;
+    let min = (local.min.to_vec2() / chunk).floor();
+    let max = (local.max.to_vec2() / chunk).floor();
+    for layer in tilemap.layers.iter().filter(|l| l.visible) {
+        for (&(cx, cy), c) in layer.chunks.range((min.x as i32, i32::MIN)..) {
+            if cx > max.x as i32 {
+                break;
+            }
+            if (cy as f32) < min.y || (cy as f32) > max.y {
+                continue;
+            }
+            for (x, y, tile) in c.tiles() {
+                let cell = Vec2::new((cx * CHUNK_SIZE + x) as f32, (cy * CHUNK_SIZE + y) as f32);
+                let min = cell * size;
+                let corners = [
+                    min,
+                    min + Vec2::new(size.x, 0.0),
+                    min + size,
+                    min + Vec2::new(0.0, size.y),
+                ]
+                .map(|c| world.apply(c));
+                quads.push(SpriteQuad {
+                    entity,
+                    layer: tilemap.layer,
+                    texture: texture.handle.id(),
+                    corners,
+                    uv: texture.uv(tileset.tile_rect(tile, texture.size)),
+                    tint: Color32::WHITE,
+                });
+            }
+        }
+    }
+}
+
+/// All sprites and tiles of the scene overlapping `visible` (in world space), in draw order:
+/// by layer, then by order in the hierarchy.
+pub fn sprite_quads(scene: &Scene, assets: &Assets, visible: Rect) -> Vec<SpriteQuad> {
+    crate::profile_scope!("Collect sprites");
+    let mut quads = vec![];
+    scene.walk_world(&mut |e, world| {
+        for c in &e.components {
+            match c {
+                Component::Sprite(sprite) => {
+                    quads.extend(sprite_quad(e.id, sprite, world, assets).filter(|q| {
+                        visible.intersects(Rect::from_points(&q.corners.map(|c| c.to_pos2())))
+                    }))
+                }
+                Component::Tilemap(tilemap) => {
+                    tile_quads(e.id, tilemap, world, assets, visible, &mut quads)
+                }
+                _ => {}
+            }
+        }
+    });
+    quads.sort_by_key(|q| q.layer);
+    quads
+}
+
+/// Shapes of the sprites, batching consecutive sprites with the same texture into one mesh.
+pub fn sprite_shapes(camera: &Camera, viewport: Rect, quads: &[SpriteQuad]) -> Vec<Shape> {
+    crate::profile_scope!("Draw sprites");
+    let mut shapes = vec![];
+    let mut mesh: Option<Mesh> = None;
+    for q in quads {
+        if mesh.as_ref().is_some_and(|m| m.texture_id != q.texture) {
+            shapes.push(Shape::mesh(mesh.take().unwrap()));
+        }
+        let m = mesh.get_or_insert_with(|| Mesh::with_texture(q.texture));
+        let base = m.vertices.len() as u32;
+        let uvs = [
+            q.uv.left_top(),
+            q.uv.right_top(),
+            q.uv.right_bottom(),
+            q.uv.left_bottom(),
+        ];
+        for (corner, uv) in q.corners.iter().zip(uvs) {
+            m.vertices.push(egui::epaint::Vertex {
+                pos: camera.world_to_screen(viewport, *corner),
+                uv,
+                color: q.tint,
+            });
+        }
+        m.add_triangle(base, base + 1, base + 2);
+        m.add_triangle(base, base + 2, base + 3);
+    }
+    shapes.extend(mesh.map(Shape::mesh));
+    shapes
+}
+
+/// Draws the sprites, see [`sprite_shapes`].
+pub fn draw_sprites(painter: &Painter, camera: &Camera, viewport: Rect, quads: &[SpriteQuad]) {
+    painter.extend(sprite_shapes(camera, viewport, quads));
+}
+
+/// Shapes of the texts of the scene, ordered by layer. Unknown fonts fall back to the default
+/// one.
+pub fn text_shapes(ctx: &Context, camera: &Camera, viewport: Rect, scene: &Scene) -> Vec<Shape> {
+    crate::profile_scope!("Draw texts");
+    let families = ctx.fonts(|f| f.families());
+    let mut shapes = vec![];
+    scene.walk_world(&mut |e, world| {
+        for c in &e.components {
+            let Component::Text(text) = c else {
+                continue;
+            };
+            let size = text.size * world.scale.y.abs() * camera.zoom;
+            // Tiny text is unreadable anyway, huge text would fill the font atlas.
+            if size < 1.0 || text.text.is_empty() {
+                continue;
+            }
+            let family = Some(text::family(&text.font))
+                .filter(|f| families.contains(f))
+                .unwrap_or_default();
+            let font = FontId::new(size.min(512.0), family);
+            let galley = ctx.fonts(|f| f.layout_no_wrap(text.text.clone(), font, text.color));
+            let offset = -text.pivot * galley.size();
+            let (sin, cos) = world.rotation.sin_cos();
+            let offset = Vec2::new(
+                offset.x * cos - offset.y * sin,
+                offset.x * sin + offset.y * cos,
+            );
+            let pos = camera.world_to_screen(viewport, world.position) + offset;
+            let shape = TextShape {
+                angle: world.rotation,
+                ..TextShape::new(pos, galley)
+            };
+            shapes.push((text.layer, Shape::Text(shape)));
+        }
+    });
+    shapes.sort_by_key(|(layer, _)| *layer);
+    shapes.into_iter().map(|(_, shape)| shape).collect()
+}
+
+/// Draws the texts, see [`text_shapes`].
+pub fn draw_texts(painter: &Painter, camera: &Camera, viewport: Rect, scene: &Scene) {
+    painter.extend(text_shapes(painter.ctx(), camera, viewport, scene));
+}
+
+/// What drawing a scene costs, from the meshes egui tessellates its shapes into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    pub shapes: usize,
+    /// Meshes sent to the GPU, each one is a draw call.
+    pub draw_calls: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+impl DrawStats {
+    /// Counts the meshes of sprite and text shapes as they are, without tessellating them.
+    /// egui merges neighbouring meshes with the same texture into one draw call.
+    pub fn measure(shapes: &[Shape]) -> Self {
+        let mut stats = Self {
+            shapes: shapes.len(),
+            ..Default::default()
+        };
+        let mut texture = None;
+        let mut add = |mesh: &Mesh| {
+            if mesh.is_empty() {
+                return;
+            }
+            if texture != Some(mesh.texture_id) {
+                texture = Some(mesh.texture_id);
+                stats.draw_calls += 1;
+            }
+            stats.vertices += mesh.vertices.len();
+            stats.triangles += mesh.indices.len() / 3;
+        };
+        for shape in shapes {
+            match shape {
+                Shape::Mesh(mesh) => add(mesh),
+                Shape::Text(text) => {
+                    for row in &text.galley.rows {
+                        add(&row.visuals.mesh);
+                    }
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+/// Draws the whole scene into `viewport`.
+pub fn draw_scene(
+    painter: &Painter,
+    camera: &Camera,
+    viewport: Rect,
+    scene: &Scene,
+    assets: &Assets,
+) {
+    let quads = sprite_quads(scene, assets, camera.visible(viewport));
+    draw_sprites(painter, camera, viewport, &quads);
+    draw_texts(painter, camera, viewport, scene);
+}
@@ -0,0 +1,99 @@
+//! The game loop: a scene being played and the systems updating it.
+
+use super::animation;
+use super::assets::Assets;
+use super::audio::{self, MixerHandle};
+use super::input::Input;
+use super::physics::Physics;
+use super::project::Project;
+use super::scene::{EntityId, Scene};
+use super::script::Scripting;
+
+/// Something that happened during the last update, for game systems to react to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// An animation entered a frame with an event.
+    Animation { entity: EntityId, name: String },
+    /// Two entities started touching, `a` has the lower id. A trigger only reports overlaps.
+    CollisionStart {
+        a: EntityId,
+        b: EntityId,
+        trigger: bool,
+    },
+    /// Two entities stopped touching.
+    CollisionStop {
+        a: EntityId,
+        b: EntityId,
+        trigger: bool,
+    },
+}
+
+/// A running game. The editor creates one from a copy of the edited scene in play mode.
+pub struct Runtime {
+    pub scene: Scene,
+    /// Seconds since the game started.
+    pub time: f64,
+    /// Events of the last update.
+    pub events: Vec<GameEvent>,
+    pub physics: Physics,
+    /// Actions and axes, fed by the host with [`Input::update`] before each update.
+    pub input: Input,
+    pub scripts: Scripting,
+    /// The mixer the game plays its sounds on.
+    pub mixer: MixerHandle,
+}
+
+impl Runtime {
+    pub fn new(scene: Scene, project: &Project, mixer: MixerHandle) -> Self {
+        Self {
+            scene,
+            time: 0.0,
+            events: vec![],
+            physics: Physics::default(),
+            input: Input::new(project.input.clone()),
+            scripts: Scripting::default(),
+            mixer,
+        }
+    }
+
+    /// Advances the game by `dt` seconds.
+    ///
+    /// Scripts may spawn prefabs, which borrows them from `assets` during the update.
+    pub fn update(&mut self, assets: &mut Assets, dt: f32) {
+        let first = self.time == 0.0;
+        self.time += dt as f64;
+        self.events.clear();
+        {
+            crate::profile_scope!("Animation");
+            animation::update(&mut self.scene, assets, dt, &mut self.events);
+        }
+        {
+            crate::profile_scope!("Physics");
+            self.physics
+                .update(&mut self.scene, assets, dt, &mut self.events);
+        }
+        let play = {
+            crate::profile_scope!("Scripts");
+            self.scripts.update(
+                &mut self.scene,
+                &mut self.input,
+                assets,
+                self.time,
+                dt,
+                &self.events,
+            )
+        };
+        crate::profile_scope!("Audio");
+        let mut mixer = self.mixer.lock().unwrap();
+        audio::play(&mut self.scene, assets, &mut mixer, &play);
+        audio::update(&mut self.scene, assets, &mut mixer, first);
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        if let Ok(mut mixer) = self.mixer.lock() {
+            audio::stop(&mut self.scene, &mut mixer);
+        }
+    }
+}
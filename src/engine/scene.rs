@@ -0,0 +1,342 @@
+use egui::{Color32, Vec2};
+use serde::{Deserialize, Serialize};
+
+use super::animation::Animator;
+use super::assets::AssetRef;
+use super::audio::AudioSource;
+use super::format::{self, FormatError, Migration};
+use super::physics::{Collider, RigidBody};
+use super::prefab::PrefabLink;
+use super::script::Script;
+use super::text::Text;
+use super::tilemap::Tilemap;
+
+/// Current version of the scene file format, bump it together with adding a [`Migration`].
+pub const SCENE_VERSION: u32 = 1;
+
+/// Upgrades older scene files, see [`format::rename_field`] for the usual case of a renamed field.
+pub const SCENE_MIGRATIONS: &[Migration] = &[];
+
+/// Unique (within a scene) and stable id of an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EntityId(pub u64);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub position: Vec2,
+    /// Rotation in radians.
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::splat(1.0),
+        }
+    }
+}
+
+impl Transform {
+    /// Maps a point from the local space of this transform to the space of its parent.
+    pub fn apply(&self, point: Vec2) -> Vec2 {
+        let p = point * self.scale;
+        let (sin, cos) = self.rotation.sin_cos();
+        Vec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos) + self.position
+    }
+
+    /// Maps a point from the space of the parent to the local space, the inverse of [`Self::apply`].
+    pub fn inverse_apply(&self, point: Vec2) -> Vec2 {
+        let p = point - self.position;
+        let (sin, cos) = self.rotation.sin_cos();
+        Vec2::new(p.x * cos + p.y * sin, -p.x * sin + p.y * cos) / self.scale
+    }
+
+    /// Combines this (parent) transform with a child transform.
+    ///
+    /// Non-uniform scale of a rotated parent is not sheared, which is fine for 2D games.
+    pub fn then(&self, child: &Transform) -> Transform {
+        Transform {
+            position: self.apply(child.position),
+            rotation: self.rotation + child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// An image, or a frame of an atlas, drawn at the entity position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sprite {
+    /// Either an image, or an atlas in which case `region` names the frame.
+    pub image: AssetRef,
+    pub region: String,
+    pub tint: Color32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Point of the sprite placed at the entity position, `(0, 0)` is the top left corner.
+    pub pivot: Vec2,
+    /// Sprites with a higher layer are drawn on top.
+    pub layer: i32,
+    /// Round the position to whole world units, so pixel art stays crisp.
+    pub pixel_snap: bool,
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self {
+            image: AssetRef::default(),
+            region: String::new(),
+            tint: Color32::WHITE,
+            flip_x: false,
+            flip_y: false,
+            pivot: Vec2::splat(0.5),
+            layer: 0,
+            pixel_snap: true,
+        }
+    }
+}
+
+/// All component types. Serialized with their type name in a `type` field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Component {
+    Transform(Transform),
+    Sprite(Sprite),
+    Animator(Animator),
+    Tilemap(Tilemap),
+    RigidBody(RigidBody),
+    Collider(Collider),
+    AudioSource(AudioSource),
+    Script(Script),
+    Text(Text),
+}
+
+impl Component {
+    /// One default instance of every component type, used by the "Add component" menu.
+    pub fn all_defaults() -> Vec<Component> {
+        vec![
+            Component::Transform(Transform::default()),
+            Component::Sprite(Sprite::default()),
+            Component::Animator(Animator::default()),
+            Component::Tilemap(Tilemap::default()),
+            Component::RigidBody(RigidBody::default()),
+            Component::Collider(Collider::default()),
+            Component::AudioSource(AudioSource::default()),
+            Component::Script(Script::default()),
+            Component::Text(Text::default()),
+        ]
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Component::Transform(_) => "Transform",
+            Component::Sprite(_) => "Sprite",
+            Component::Animator(_) => "Animator",
+            Component::Tilemap(_) => "Tilemap",
+            Component::RigidBody(_) => "RigidBody",
+            Component::Collider(_) => "Collider",
+            Component::AudioSource(_) => "AudioSource",
+            Component::Script(_) => "Script",
+            Component::Text(_) => "Text",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    pub id: EntityId,
+    pub name: String,
+    #[serde(default)]
+    pub components: Vec<Component>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Entity>,
+    /// Set on entities created from a prefab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefab: Option<PrefabLink>,
+}
+
+impl Entity {
+    pub fn new(id: EntityId, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            components: vec![Component::Transform(Transform::default())],
+            children: vec![],
+            prefab: None,
+        }
+    }
+
+    /// Finds the entity with the given id in this subtree.
+    pub fn find(&self, id: EntityId) -> Option<&Entity> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.find_mut(id))
+    }
+
+    pub fn transform(&self) -> Option<&Transform> {
+        self.components.iter().find_map(|c| match c {
+            Component::Transform(t) => Some(t),
+            _ => None,
+        })
+    }
+
+    /// Calls `f` on this entity and all its descendants, parents before children.
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Entity)) {
+        f(self);
+        for c in &self.children {
+            c.walk(f);
+        }
+    }
+
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Entity)) {
+        f(self);
+        for c in &mut self.children {
+            c.walk_mut(f);
+        }
+    }
+}
+
+/// A tree of entities, saved as one `*.scene.json` file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub entities: Vec<Entity>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            version: SCENE_VERSION,
+            name: "Untitled".to_owned(),
+            entities: vec![],
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, SCENE_VERSION, SCENE_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Entity)) {
+        for e in &self.entities {
+            e.walk(f);
+        }
+    }
+
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Entity)) {
+        for e in &mut self.entities {
+            e.walk_mut(f);
+        }
+    }
+
+    /// Like [`Self::walk`], also passing the world transform of each entity.
+    pub fn walk_world<'a>(&'a self, f: &mut impl FnMut(&'a Entity, &Transform)) {
+        fn walk_in<'a>(
+            e: &'a Entity,
+            parent: &Transform,
+            f: &mut impl FnMut(&'a Entity, &Transform),
+        ) {
+            let world = match e.transform() {
+                Some(t) => parent.then(t),
+                None => parent.clone(),
+            };
+            f(e, &world);
+            for c in &e.children {
+                walk_in(c, &world, f);
+            }
+        }
+        for e in &self.entities {
+            walk_in(e, &Transform::default(), f);
+        }
+    }
+
+    /// Transform of an entity in world space.
+    pub fn world_transform(&self, id: EntityId) -> Option<Transform> {
+        let mut found = None;
+        self.walk_world(&mut |e, world| {
+            if e.id == id {
+                found = Some(world.clone());
+            }
+        });
+        found
+    }
+
+    pub fn entity_count(&self) -> usize {
+        let mut n = 0;
+        self.walk(&mut |_| n += 1);
+        n
+    }
+
+    /// Returns an id not used by any entity of the scene.
+    pub fn next_id(&self) -> EntityId {
+        let mut max = 0;
+        self.walk(&mut |e| max = max.max(e.id.0));
+        EntityId(max + 1)
+    }
+
+    pub fn find(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.iter().find_map(|e| e.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.iter_mut().find_map(|e| e.find_mut(id))
+    }
+
+    /// Creates a new entity under `parent`, or at the top level if `parent` is `None`.
+    pub fn spawn(&mut self, name: impl Into<String>, parent: Option<EntityId>) -> EntityId {
+        let id = self.next_id();
+        self.insert(Entity::new(id, name), parent);
+        id
+    }
+
+    /// Inserts an existing entity subtree under `parent`, or at the top level if `parent` is `None`.
+    pub fn insert(&mut self, entity: Entity, parent: Option<EntityId>) {
+        match parent.and_then(|p| self.find_mut(p)) {
+            Some(p) => p.children.push(entity),
+            None => self.entities.push(entity),
+        }
+    }
+
+    /// Removes the entity and its whole subtree, returning it.
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        fn remove_from(list: &mut Vec<Entity>, id: EntityId) -> Option<Entity> {
+            if let Some(i) = list.iter().position(|e| e.id == id) {
+                return Some(list.remove(i));
+            }
+            list.iter_mut()
+                .find_map(|e| remove_from(&mut e.children, id))
+        }
+        remove_from(&mut self.entities, id)
+    }
+
+    /// Id of the parent entity, `None` for top level or unknown entities.
+    pub fn parent_of(&self, id: EntityId) -> Option<EntityId> {
+        let mut parent = None;
+        self.walk(&mut |e| {
+            if e.children.iter().any(|c| c.id == id) {
+                parent = Some(e.id);
+            }
+        });
+        parent
+    }
+}
@@ -0,0 +1,671 @@
+//! Game logic in [Rhai](https://rhai.rs) scripts, so it can be written without touching Rust.
+//!
+//! A [`Script`] component runs a `.rhai` asset for its entity. The script defines any of
+//! these callbacks, in which `this` is the entity:
+//!
+//! ```rhai
+//! fn on_start() { this.speed = 120.0; }
+//! fn on_update(dt) { this.x += axis("move_x") * this.speed * dt; }
+//! fn on_collision(other, started, trigger) { if started { print(`hit ${other.name}`); } }
+//! ```
+//!
+//! Entities have the properties `id`, `name`, `x`, `y`, `rotation`, `scale_x`, `scale_y`,
+//! `velocity_x` and `velocity_y`, and the methods `translate(dx, dy)`, `apply_impulse(x, y)`,
+//! `has(type)`, `get(type)`, `set(type, map)`, `add(type)`, `remove(type)`, `play()` and
+//! `despawn()`. Other properties are variables of the script instance, e.g. `this.speed`.
+//! The functions `find(name)`, `spawn_entity(name)`, `spawn_prefab(path)`,
+//! `pressed(action)`, `just_pressed(action)`, `just_released(action)`, `axis(name)` and
+//! `time()` reach the rest of the game.
+//!
+//! Errors are logged with the script path and line, and stop the instance until the script
+//! is fixed: replacing the asset reloads all instances running it, keeping their variables.
+
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, ParseError, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::assets::{AssetRef, Assets};
+use super::input::Input;
+use super::physics::RigidBody;
+use super::prefab::{self, Prefab};
+use super::runtime::GameEvent;
+use super::scene::{Component, Entity, EntityId, Scene, Transform};
+
+/// Runaway scripts are stopped after this many operations in one callback.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Runs a script asset for the entity.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Script {
+    pub script: AssetRef,
+}
+
+/// A compile or runtime error, with the position in the script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptError {
+    /// 1-based, 0 if unknown.
+    pub line: usize,
+    /// 1-based, 0 if unknown.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<ParseError> for ScriptError {
+    fn from(e: ParseError) -> Self {
+        Self {
+            line: e.position().line().unwrap_or_default(),
+            column: e.position().position().unwrap_or_default(),
+            message: e.err_type().to_string(),
+        }
+    }
+}
+
+impl From<Box<EvalAltResult>> for ScriptError {
+    fn from(mut e: Box<EvalAltResult>) -> Self {
+        // Errors inside functions point at the call, the cause knows the real position.
+        while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = *e {
+            e = inner;
+        }
+        let position = e.take_position();
+        Self {
+            line: position.line().unwrap_or_default(),
+            column: position.position().unwrap_or_default(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Compiles the source, for reporting errors while it is edited.
+pub fn check(source: &str) -> Result<(), ScriptError> {
+    Engine::new().compile(source)?;
+    Ok(())
+}
+
+/// An entity as seen by scripts, along with the variables of a script instance.
+#[derive(Clone)]
+pub struct EntityHandle {
+    id: EntityId,
+    vars: rhai::Map,
+}
+
+impl EntityHandle {
+    fn new(id: EntityId) -> Self {
+        Self {
+            id,
+            vars: rhai::Map::new(),
+        }
+    }
+}
+
+/// The game state scripts work on, moved in from the runtime for the duration of an update.
+#[derive(Default)]
+struct World {
+    scene: Scene,
+    input: Input,
+    prefabs: BTreeMap<AssetRef, Prefab>,
+    time: f64,
+    /// Entities whose audio source scripts started.
+    play: Vec<EntityId>,
+}
+
+type Shared = Rc<RefCell<World>>;
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+fn number(value: Dynamic) -> ScriptResult<f32> {
+    if let Ok(f) = value.as_float() {
+        return Ok(f as f32);
+    }
+    match value.as_int() {
+        Ok(i) => Ok(i as f32),
+        Err(t) => Err(format!("expected a number, got {t}").into()),
+    }
+}
+
+fn with_entity<T>(
+    world: &Shared,
+    id: EntityId,
+    f: impl FnOnce(&mut Entity) -> T,
+) -> ScriptResult<T> {
+    let mut world = world.borrow_mut();
+    match world.scene.find_mut(id) {
+        Some(entity) => Ok(f(entity)),
+        None => Err(format!("entity {} does not exist", id.0).into()),
+    }
+}
+
+fn transform(entity: &Entity) -> Transform {
+    entity.transform().cloned().unwrap_or_default()
+}
+
+/// The transform of the entity, added if it has none.
+fn transform_mut(entity: &mut Entity) -> &mut Transform {
+    let i = match entity
+        .components
+        .iter()
+        .position(|c| matches!(c, Component::Transform(_)))
+    {
+        Some(i) => i,
+        None => {
+            entity
+                .components
+                .insert(0, Component::Transform(Transform::default()));
+            0
+        }
+    };
+    match &mut entity.components[i] {
+        Component::Transform(t) => t,
+        _ => unreachable!(),
+    }
+}
+
+fn body(entity: &Entity) -> Option<&RigidBody> {
+    entity.components.iter().find_map(|c| match c {
+        Component::RigidBody(b) => Some(b),
+        _ => None,
+    })
+}
+
+fn body_mut(entity: &mut Entity) -> Option<&mut RigidBody> {
+    entity.components.iter_mut().find_map(|c| match c {
+        Component::RigidBody(b) => Some(b),
+        _ => None,
+    })
+}
+
+/// The fields of a component as a script object map.
+fn component_to_dynamic(component: &Component) -> ScriptResult<Dynamic> {
+    let mut value = serde_json::to_value(component).map_err(|e| e.to_string())?;
+    if let Some(map) = value.as_object_mut() {
+        map.remove("type");
+    }
+    rhai::serde::to_dynamic(value)
+}
+
+fn component_from_dynamic(type_name: &str, value: &Dynamic) -> ScriptResult<Component> {
+    let mut value: serde_json::Value = rhai::serde::from_dynamic(value)?;
+    let Some(map) = value.as_object_mut() else {
+        return Err(format!("{type_name} must be an object map").into());
+    };
+    map.insert("type".to_owned(), type_name.into());
+    serde_json::from_value(value).map_err(|e| format!("invalid {type_name}: {e}").into())
+}
+
+fn default_component(type_name: &str) -> ScriptResult<Component> {
+    Component::all_defaults()
+        .into_iter()
+        .find(|c| c.type_name() == type_name)
+        .ok_or_else(|| format!("unknown component type `{type_name}`").into())
+}
+
+/// Registers a number property of the entity.
+fn register_number(
+    engine: &mut Engine,
+    world: &Shared,
+    name: &str,
+    get: fn(&Entity) -> f32,
+    set: fn(&mut Entity, f32),
+) {
+    let w = world.clone();
+    let w2 = world.clone();
+    engine.register_get(name, move |h: &mut EntityHandle| -> ScriptResult<f64> {
+        with_entity(&w, h.id, |e| get(e) as f64)
+    });
+    // Takes integers too, so `this.x = 10` works.
+    engine.register_set(
+        name,
+        move |h: &mut EntityHandle, value: Dynamic| -> ScriptResult<()> {
+            let value = number(value)?;
+            with_entity(&w2, h.id, |e| set(e, value))
+        },
+    );
+}
+
+fn register_api(engine: &mut Engine, world: &Shared) {
+    engine.register_type_with_name::<EntityHandle>("Entity");
+    engine.register_get("id", |h: &mut EntityHandle| h.id.0 as i64);
+    engine.register_fn("==", |a: &mut EntityHandle, b: EntityHandle| a.id == b.id);
+    engine.register_fn("!=", |a: &mut EntityHandle, b: EntityHandle| a.id != b.id);
+    let w = world.clone();
+    engine.register_fn("to_string", move |h: &mut EntityHandle| {
+        let world = w.borrow();
+        let name = world.scene.find(h.id).map_or("", |e| e.name.as_str());
+        format!("Entity({} {name})", h.id.0)
+    });
+    // Unknown properties are the variables of the instance.
+    engine.register_indexer_get_set(
+        |h: &mut EntityHandle, key: &str| h.vars.get(key).cloned().unwrap_or(Dynamic::UNIT),
+        |h: &mut EntityHandle, key: &str, value: Dynamic| {
+            h.vars.insert(key.into(), value);
+        },
+    );
+
+    let w = world.clone();
+    let w2 = world.clone();
+    engine.register_get_set(
+        "name",
+        move |h: &mut EntityHandle| -> ScriptResult<String> {
+            with_entity(&w, h.id, |e| e.name.clone())
+        },
+        move |h: &mut EntityHandle, name: String| -> ScriptResult<()> {
+            with_entity(&w2, h.id, |e| e.name = name)
+        },
+    );
+    register_number(
+        engine,
+        world,
+        "x",
+        |e| transform(e).position.x,
+        |e, v| transform_mut(e).position.x = v,
+    );
+    register_number(
+        engine,
+        world,
+        "y",
+        |e| transform(e).position.y,
+        |e, v| transform_mut(e).position.y = v,
+    );
+    register_number(
+        engine,
+        world,
+        "rotation",
+        |e| transform(e).rotation,
+        |e, v| transform_mut(e).rotation = v,
+    );
+    register_number(
+        engine,
+        world,
+        "scale_x",
+        |e| transform(e).scale.x,
+        |e, v| transform_mut(e).scale.x = v,
+    );
+    register_number(
+        engine,
+        world,
+        "scale_y",
+        |e| transform(e).scale.y,
+        |e, v| transform_mut(e).scale.y = v,
+    );
+    register_number(
+        engine,
+        world,
+        "velocity_x",
+        |e| body(e).map_or(0.0, |b| b.velocity.x),
+        |e, v| {
+            if let Some(b) = body_mut(e) {
+                b.velocity.x = v;
+            }
+        },
+    );
+    register_number(
+        engine,
+        world,
+        "velocity_y",
+        |e| body(e).map_or(0.0, |b| b.velocity.y),
+        |e, v| {
+            if let Some(b) = body_mut(e) {
+                b.velocity.y = v;
+            }
+        },
+    );
+
+    let w = world.clone();
+    engine.register_fn("exists", move |h: &mut EntityHandle| {
+        w.borrow().scene.find(h.id).is_some()
+    });
+    let w = world.clone();
+    engine.register_fn(
+        "translate",
+        move |h: &mut EntityHandle, dx: Dynamic, dy: Dynamic| -> ScriptResult<()> {
+            let d = egui::vec2(number(dx)?, number(dy)?);
+            with_entity(&w, h.id, |e| transform_mut(e).position += d)
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "apply_impulse",
+        move |h: &mut EntityHandle, x: Dynamic, y: Dynamic| -> ScriptResult<()> {
+            let impulse = egui::vec2(number(x)?, number(y)?);
+            with_entity(&w, h.id, |e| {
+                if let Some(b) = body_mut(e) {
+                    b.apply_impulse(impulse);
+                }
+            })
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "has",
+        move |h: &mut EntityHandle, type_name: &str| -> ScriptResult<bool> {
+            with_entity(&w, h.id, |e| {
+                e.components.iter().any(|c| c.type_name() == type_name)
+            })
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "get",
+        move |h: &mut EntityHandle, type_name: &str| -> ScriptResult<Dynamic> {
+            let component = with_entity(&w, h.id, |e| {
+                e.components
+                    .iter()
+                    .find(|c| c.type_name() == type_name)
+                    .cloned()
+            })?;
+            match component {
+                Some(c) => component_to_dynamic(&c),
+                None => Ok(Dynamic::UNIT),
+            }
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "set",
+        move |h: &mut EntityHandle, type_name: &str, value: Dynamic| -> ScriptResult<()> {
+            let component = component_from_dynamic(type_name, &value)?;
+            with_entity(&w, h.id, |e| {
+                match e.components.iter_mut().find(|c| c.type_name() == type_name) {
+                    Some(c) => *c = component,
+                    None => e.components.push(component),
+                }
+            })
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "add",
+        move |h: &mut EntityHandle, type_name: &str| -> ScriptResult<()> {
+            let component = default_component(type_name)?;
+            with_entity(&w, h.id, |e| {
+                if !e.components.iter().any(|c| c.type_name() == type_name) {
+                    e.components.push(component);
+                }
+            })
+        },
+    );
+    let w = world.clone();
+    engine.register_fn(
+        "remove",
+        move |h: &mut EntityHandle, type_name: &str| -> ScriptResult<()> {
+            with_entity(&w, h.id, |e| {
+                e.components.retain(|c| c.type_name() != type_name)
+            })
+        },
+    );
+    let w = world.clone();
+    engine.register_fn("play", move |h: &mut EntityHandle| {
+        w.borrow_mut().play.push(h.id);
+    });
+    let w = world.clone();
+    engine.register_fn("despawn", move |h: &mut EntityHandle| {
+        w.borrow_mut().scene.remove(h.id);
+    });
+
+    let w = world.clone();
+    engine.register_fn("find", move |name: &str| {
+        let world = w.borrow();
+        let mut found = None;
+        world.scene.walk(&mut |e| {
+            if found.is_none() && e.name == name {
+                found = Some(e.id);
+            }
+        });
+        found.map_or(Dynamic::UNIT, |id| Dynamic::from(EntityHandle::new(id)))
+    });
+    let w = world.clone();
+    engine.register_fn("spawn_entity", move |name: &str| {
+        EntityHandle::new(w.borrow_mut().scene.spawn(name, None))
+    });
+    let w = world.clone();
+    engine.register_fn(
+        "spawn_prefab",
+        move |path: &str| -> ScriptResult<EntityHandle> {
+            let world = &mut *w.borrow_mut();
+            let asset = AssetRef::new(path);
+            let Some(prefab) = world.prefabs.get(&asset) else {
+                return Err(format!("prefab {path} is not loaded").into());
+            };
+            let id = prefab::instantiate(&mut world.scene, &asset, prefab, None);
+            Ok(EntityHandle::new(id))
+        },
+    );
+    let w = world.clone();
+    engine.register_fn("pressed", move |action: &str| {
+        w.borrow().input.pressed(action)
+    });
+    let w = world.clone();
+    engine.register_fn("just_pressed", move |action: &str| {
+        w.borrow().input.just_pressed(action)
+    });
+    let w = world.clone();
+    engine.register_fn("just_released", move |action: &str| {
+        w.borrow().input.just_released(action)
+    });
+    let w = world.clone();
+    engine.register_fn("axis", move |axis: &str| w.borrow().input.axis(axis) as f64);
+    let w = world.clone();
+    engine.register_fn("time", move || w.borrow().time);
+
+    engine.on_print(|text| log::info!("{text}"));
+    engine.on_debug(|text, source, position| {
+        let line = position.line().unwrap_or_default();
+        log::debug!("{}, line {line}: {text}", source.unwrap_or("script"))
+    });
+}
+
+/// A script running for one entity.
+struct Instance {
+    script: AssetRef,
+    /// The source the AST was compiled from, to notice when the asset is replaced.
+    source: Arc<str>,
+    ast: AST,
+    /// The [`EntityHandle`] bound to `this`, holding the variables.
+    this: Dynamic,
+    started: bool,
+    /// Stopped by an error, until the script changes.
+    failed: bool,
+}
+
+/// Runs the scripts of a scene.
+pub struct Scripting {
+    engine: Engine,
+    world: Shared,
+    /// Keyed by entity and the index of the script among its scripts.
+    instances: BTreeMap<(EntityId, usize), Instance>,
+    /// Scripts which failed to compile, to report each error once.
+    broken: BTreeMap<AssetRef, Arc<str>>,
+    /// Scripts reported as not loaded.
+    missing: BTreeSet<AssetRef>,
+}
+
+impl Default for Scripting {
+    fn default() -> Self {
+        let world = Shared::default();
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register_api(&mut engine, &world);
+        Self {
+            engine,
+            world,
+            instances: BTreeMap::new(),
+            broken: BTreeMap::new(),
+            missing: BTreeSet::new(),
+        }
+    }
+}
+
+impl Scripting {
+    /// Runs the script callbacks: `on_start` for new instances, `on_collision` for the
+    /// collisions in `events` and `on_update` for all.
+    ///
+    /// Returns the entities whose audio sources the scripts asked to play. The scripts run
+    /// without the mixer, so the audio thread is not kept waiting for them.
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        input: &mut Input,
+        assets: &mut Assets,
+        time: f64,
+        dt: f32,
+        events: &[GameEvent],
+    ) -> Vec<EntityId> {
+        self.sync(scene, assets);
+        if self.instances.is_empty() {
+            return vec![];
+        }
+        {
+            let mut world = self.world.borrow_mut();
+            world.scene = std::mem::take(scene);
+            world.input = std::mem::take(input);
+            world.prefabs = std::mem::take(&mut assets.prefabs);
+            world.time = time;
+        }
+
+        let keys: Vec<_> = self.instances.keys().copied().collect();
+        for key in &keys {
+            if !self.instances[key].started {
+                self.call(*key, "on_start", ());
+                if let Some(instance) = self.instances.get_mut(key) {
+                    instance.started = true;
+                }
+            }
+        }
+        for event in events {
+            let (a, b, started, trigger) = match *event {
+                GameEvent::CollisionStart { a, b, trigger } => (a, b, true, trigger),
+                GameEvent::CollisionStop { a, b, trigger } => (a, b, false, trigger),
+                _ => continue,
+            };
+            for (this, other) in [(a, b), (b, a)] {
+                for key in keys.iter().filter(|k| k.0 == this) {
+                    let other = EntityHandle::new(other);
+                    self.call(*key, "on_collision", (other, started, trigger));
+                }
+            }
+        }
+        for key in &keys {
+            self.call(*key, "on_update", (dt as f64,));
+        }
+
+        let mut world = self.world.borrow_mut();
+        *scene = std::mem::take(&mut world.scene);
+        *input = std::mem::take(&mut world.input);
+        assets.prefabs = std::mem::take(&mut world.prefabs);
+        std::mem::take(&mut world.play)
+    }
+
+    /// Creates, reloads and drops instances to match the script components of the scene.
+    fn sync(&mut self, scene: &Scene, assets: &Assets) {
+        let mut wanted = BTreeMap::new();
+        scene.walk(&mut |e| {
+            let scripts = e.components.iter().filter_map(|c| match c {
+                Component::Script(s) if !s.script.is_empty() => Some(s.script.clone()),
+                _ => None,
+            });
+            for (i, script) in scripts.enumerate() {
+                wanted.insert((e.id, i), script);
+            }
+        });
+        self.instances
+            .retain(|key, instance| wanted.get(key) == Some(&instance.script));
+
+        for (key, script) in wanted {
+            let Some(source) = assets.scripts.get(&script) else {
+                if self.missing.insert(script.clone()) {
+                    log::warn!("Script {script} is not loaded.");
+                }
+                continue;
+            };
+            self.missing.remove(&script);
+            let current = self.instances.get(&key).map(|i| &i.source);
+            if current.is_some_and(|s| Arc::ptr_eq(s, source)) {
+                continue;
+            }
+            let Some(ast) = self.compile(&script, source) else {
+                continue;
+            };
+            match self.instances.get_mut(&key) {
+                Some(instance) => {
+                    log::info!("Reloaded {script} for entity {}.", key.0 .0);
+                    instance.source = source.clone();
+                    instance.ast = ast;
+                    instance.failed = false;
+                }
+                None => {
+                    let this = Dynamic::from(EntityHandle::new(key.0));
+                    self.instances.insert(
+                        key,
+                        Instance {
+                            script,
+                            source: source.clone(),
+                            ast,
+                            this,
+                            started: false,
+                            failed: false,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Compiles a script, logging errors once per version of the source.
+    fn compile(&mut self, script: &AssetRef, source: &Arc<str>) -> Option<AST> {
+        if self
+            .broken
+            .get(script)
+            .is_some_and(|s| Arc::ptr_eq(s, source))
+        {
+            return None;
+        }
+        match self.engine.compile(&**source) {
+            Ok(mut ast) => {
+                self.broken.remove(script);
+                ast.set_source(script.path());
+                Some(ast)
+            }
+            Err(e) => {
+                log::error!("{script}, {}", ScriptError::from(e));
+                self.broken.insert(script.clone(), source.clone());
+                None
+            }
+        }
+    }
+
+    /// Calls a callback of an instance if the script defines it.
+    fn call(&mut self, key: (EntityId, usize), name: &str, args: impl rhai::FuncArgs) {
+        let Some(instance) = self.instances.get_mut(&key) else {
+            return;
+        };
+        let defined = instance.ast.iter_functions().any(|f| f.name == name);
+        if instance.failed || !defined || self.world.borrow().scene.find(key.0).is_none() {
+            return;
+        }
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut instance.this);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut Scope::new(),
+            &instance.ast,
+            name,
+            args,
+        );
+        if let Err(e) = result {
+            log::error!("{}, {}", instance.script, ScriptError::from(e));
+            instance.failed = true;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Text drawn in the game world, and the fonts of the project it is drawn with.
+//!
+//! Project fonts are imported TTF/OTF files, installed into the egui font definitions next to
+//! the fonts of the editor. Every font is a family of its own, named by its path, and
+//! [`FontSettings`] adds named families with fallback chains, e.g. a pixel font falling back
+//! to a CJK font.
+
+use egui::{Color32, FontData, FontDefinitions, FontFamily, FontTweak, Vec2};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::assets::{AssetRef, Assets};
+
+/// Text drawn at the entity position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Text {
+    pub text: String,
+    /// Font family, empty for the default font.
+    pub font: String,
+    /// Height of a line in world units.
+    pub size: f32,
+    pub color: Color32,
+    /// Point of the text placed at the entity position, `(0, 0)` is the top left corner.
+    pub pivot: Vec2,
+    /// Texts with a higher layer are drawn on top. Texts are drawn above all sprites.
+    pub layer: i32,
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self {
+            text: "Text".to_owned(),
+            font: String::new(),
+            size: 16.0,
+            color: Color32::WHITE,
+            pivot: Vec2::splat(0.5),
+            layer: 0,
+        }
+    }
+}
+
+/// Families made of the project fonts, saved in the project.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FontSettings {
+    /// Fonts of each family, a character is drawn with the first one which has it.
+    /// `Proportional` and `Monospace` add fallbacks to the fonts of the editor.
+    #[serde(default)]
+    pub families: BTreeMap<String, Vec<AssetRef>>,
+    /// Scale and offsets of the glyphs of each font.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tweaks: BTreeMap<AssetRef, FontTweak>,
+}
+
+impl FontSettings {
+    /// Adds the loaded project fonts and families to `fonts`.
+    ///
+    /// The `Proportional` fonts are the last fallback of every new family.
+    pub fn install(&self, assets: &Assets, fonts: &mut FontDefinitions) {
+        for (asset, data) in &assets.fonts {
+            let tweak = self.tweaks.get(asset).copied().unwrap_or_default();
+            let font = FontData::from_owned(data.to_vec()).tweak(tweak);
+            fonts.font_data.insert(asset.path().to_owned(), font);
+        }
+        let chain = |fonts: &[AssetRef]| -> Vec<String> {
+            fonts
+                .iter()
+                .filter(|font| assets.fonts.contains_key(font))
+                .map(|font| font.path().to_owned())
+                .collect()
+        };
+        for (name, fonts_of_family) in &self.families {
+            let f = family(name);
+            if f == FontFamily::Proportional || f == FontFamily::Monospace {
+                let chain = chain(fonts_of_family);
+                fonts.families.entry(f).or_default().extend(chain);
+            }
+        }
+        let fallback = fonts
+            .families
+            .get(&FontFamily::Proportional)
+            .cloned()
+            .unwrap_or_default();
+        let mut new_family = |name: &str, mut chain: Vec<String>| {
+            for font in &fallback {
+                if !chain.contains(font) {
+                    chain.push(font.clone());
+                }
+            }
+            fonts.families.insert(family(name), chain);
+        };
+        for asset in assets.fonts.keys() {
+            new_family(asset.path(), vec![asset.path().to_owned()]);
+        }
+        for (name, fonts_of_family) in &self.families {
+            if let FontFamily::Name(_) = family(name) {
+                new_family(name, chain(fonts_of_family));
+            }
+        }
+    }
+}
+
+/// The family with the given name, as used by [`Text::font`].
+pub fn family(name: &str) -> FontFamily {
+    match name {
+        "" | "Proportional" => FontFamily::Proportional,
+        "Monospace" => FontFamily::Monospace,
+        name => FontFamily::Name(name.into()),
+    }
+}
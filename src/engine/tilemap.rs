@@ -0,0 +1,384 @@
+//! Tile-based levels: a [`Tilemap`] component draws a grid of tiles from a [`Tileset`] asset.
+//!
+//! Tiles are stored in square chunks so that huge, sparse maps stay cheap. In files, each
+//! chunk is saved as rows of space separated tile numbers, `0` being an empty cell and `n`
+//! the tile `n - 1` of the tileset, which keeps the scene files readable and diffable.
+
+use egui::{Rect, Vec2};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::assets::AssetRef;
+use super::format::{self, FormatError, Migration};
+
+pub const CHUNK_SIZE: i32 = 16;
+const CHUNK_CELLS: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+pub const TILESET_VERSION: u32 = 1;
+pub const TILESET_MIGRATIONS: &[Migration] = &[];
+
+/// An image cut into a grid of equally sized tiles, saved as `*.tileset.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tileset {
+    pub version: u32,
+    pub image: AssetRef,
+    /// Size of a tile in pixels.
+    pub tile_size: [u32; 2],
+    /// Tiles which block movement, used by the physics.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub solid: BTreeSet<u32>,
+}
+
+impl Default for Tileset {
+    fn default() -> Self {
+        Self {
+            version: TILESET_VERSION,
+            image: AssetRef::default(),
+            tile_size: [16, 16],
+            solid: BTreeSet::new(),
+        }
+    }
+}
+
+impl Tileset {
+    pub fn load(text: &str) -> Result<Self, FormatError> {
+        format::from_str(text, TILESET_VERSION, TILESET_MIGRATIONS)
+    }
+
+    pub fn save(&self) -> String {
+        format::to_string(self)
+    }
+
+    pub fn tile_size(&self) -> Vec2 {
+        Vec2::new(self.tile_size[0] as f32, self.tile_size[1] as f32)
+    }
+
+    /// Number of tile columns in an image of the given size.
+    pub fn columns(&self, image_size: [usize; 2]) -> u32 {
+        (image_size[0] as u32 / self.tile_size[0].max(1)).max(1)
+    }
+
+    pub fn tile_count(&self, image_size: [usize; 2]) -> u32 {
+        self.columns(image_size) * (image_size[1] as u32 / self.tile_size[1].max(1))
+    }
+
+    /// Pixel rectangle of a tile in the image.
+    pub fn tile_rect(&self, tile: u32, image_size: [usize; 2]) -> Rect {
+        let columns = self.columns(image_size);
+        let size = self.tile_size();
+        let min = Vec2::new((tile % columns) as f32, (tile / columns) as f32) * size;
+        Rect::from_min_size(min.to_pos2(), size)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    /// Tile number + 1 for each cell, row by row, 0 is empty.
+    cells: Box<[u32; CHUNK_CELLS]>,
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            cells: Box::new([0; CHUNK_CELLS]),
+        }
+    }
+}
+
+impl Chunk {
+    fn index(local: (i32, i32)) -> usize {
+        (local.1 * CHUNK_SIZE + local.0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|c| *c == 0)
+    }
+
+    /// Non-empty cells as `(local x, local y, tile)`.
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32, u32)> + '_ {
+        self.cells.iter().enumerate().filter_map(|(i, c)| {
+            let i = i as i32;
+            (*c != 0).then(|| (i % CHUNK_SIZE, i / CHUNK_SIZE, c - 1))
+        })
+    }
+}
+
+/// Splits a cell position into the chunk position and the position inside it.
+pub fn chunk_of(x: i32, y: i32) -> ((i32, i32), (i32, i32)) {
+    (
+        (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)),
+        (x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE)),
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileLayer {
+    pub name: String,
+    #[serde(default = "visible")]
+    pub visible: bool,
+    #[serde(
+        default,
+        serialize_with = "save_chunks",
+        deserialize_with = "load_chunks"
+    )]
+    pub chunks: BTreeMap<(i32, i32), Chunk>,
+}
+
+fn visible() -> bool {
+    true
+}
+
+impl TileLayer {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<u32> {
+        let (chunk, local) = chunk_of(x, y);
+        let cell = self.chunks.get(&chunk)?.cells[Chunk::index(local)];
+        cell.checked_sub(1)
+    }
+
+    /// Sets or clears a cell, returns true if it changed.
+    pub fn set(&mut self, x: i32, y: i32, tile: Option<u32>) -> bool {
+        let (chunk_pos, local) = chunk_of(x, y);
+        let value = tile.map_or(0, |t| t + 1);
+        if value == 0 && !self.chunks.contains_key(&chunk_pos) {
+            return false;
+        }
+        let chunk = self.chunks.entry(chunk_pos).or_default();
+        let cell = &mut chunk.cells[Chunk::index(local)];
+        if *cell == value {
+            return false;
+        }
+        *cell = value;
+        if value == 0 && chunk.is_empty() {
+            self.chunks.remove(&chunk_pos);
+        }
+        true
+    }
+
+    /// Cell bounds `(min, max)` (inclusive) of all chunks, `None` for an empty layer.
+    pub fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        let min_x = self.chunks.keys().map(|c| c.0).min()?;
+        let max_x = self.chunks.keys().map(|c| c.0).max()?;
+        let min_y = self.chunks.keys().map(|c| c.1).min()?;
+        let max_y = self.chunks.keys().map(|c| c.1).max()?;
+        Some((
+            (min_x * CHUNK_SIZE, min_y * CHUNK_SIZE),
+            ((max_x + 1) * CHUNK_SIZE - 1, (max_y + 1) * CHUNK_SIZE - 1),
+        ))
+    }
+
+    /// Flood fills the cells connected to `(x, y)` which hold the same tile as it, with
+    /// `tile(x, y)`. Empty areas have no natural end, so the fill stays within the inclusive
+    /// `bounds`. Returns the number of changed cells.
+    pub fn fill(
+        &mut self,
+        x: i32,
+        y: i32,
+        bounds: ((i32, i32), (i32, i32)),
+        mut tile: impl FnMut(i32, i32) -> Option<u32>,
+    ) -> usize {
+        let ((min_x, min_y), (max_x, max_y)) = bounds;
+        let target = self.get(x, y);
+        let mut visited = HashSet::new();
+        let mut open = vec![(x, y)];
+        let mut changed = 0;
+        while let Some((x, y)) = open.pop() {
+            if x < min_x || x > max_x || y < min_y || y > max_y {
+                continue;
+            }
+            if self.get(x, y) != target || !visited.insert((x, y)) {
+                continue;
+            }
+            if self.set(x, y, tile(x, y)) {
+                changed += 1;
+            }
+            open.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+        }
+        changed
+    }
+
+    /// All non-empty cells as `(x, y, tile)`.
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32, u32)> + '_ {
+        self.chunks.iter().flat_map(|((cx, cy), chunk)| {
+            chunk
+                .tiles()
+                .map(move |(x, y, t)| (cx * CHUNK_SIZE + x, cy * CHUNK_SIZE + y, t))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkData {
+    x: i32,
+    y: i32,
+    rows: Vec<String>,
+}
+
+fn save_chunks<S: Serializer>(
+    chunks: &BTreeMap<(i32, i32), Chunk>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    let data: Vec<ChunkData> = chunks
+        .iter()
+        .map(|(&(x, y), chunk)| ChunkData {
+            x,
+            y,
+            rows: chunk
+                .cells
+                .chunks(CHUNK_SIZE as usize)
+                .map(|row| {
+                    row.iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect(),
+        })
+        .collect();
+    data.serialize(s)
+}
+
+fn load_chunks<'de, D: Deserializer<'de>>(d: D) -> Result<BTreeMap<(i32, i32), Chunk>, D::Error> {
+    use serde::de::Error;
+    let data = Vec::<ChunkData>::deserialize(d)?;
+    let mut chunks = BTreeMap::new();
+    for c in data {
+        let mut chunk = Chunk::default();
+        let cells = c.rows.iter().flat_map(|r| r.split_whitespace());
+        for (i, cell) in cells.enumerate() {
+            if i >= CHUNK_CELLS {
+                return Err(D::Error::custom(format!(
+                    "chunk ({}, {}) has more than {CHUNK_CELLS} cells",
+                    c.x, c.y
+                )));
+            }
+            chunk.cells[i] = cell.parse().map_err(|_| {
+                D::Error::custom(format!("invalid tile `{cell}` in chunk ({}, {})", c.x, c.y))
+            })?;
+        }
+        chunks.insert((c.x, c.y), chunk);
+    }
+    Ok(chunks)
+}
+
+/// A grid of tiles with several layers, positioned by the entity transform.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tilemap {
+    pub tileset: AssetRef,
+    /// Draw order among sprites, see [`super::scene::Sprite::layer`].
+    pub layer: i32,
+    /// Drawn first to last.
+    pub layers: Vec<TileLayer>,
+}
+
+impl Default for Tilemap {
+    fn default() -> Self {
+        Self {
+            tileset: AssetRef::default(),
+            layer: 0,
+            layers: vec![TileLayer::new("Ground")],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get_across_chunks() {
+        let mut layer = TileLayer::new("Ground");
+        assert!(layer.set(-1, -1, Some(3)));
+        assert!(layer.set(16, 0, Some(0)));
+        assert!(!layer.set(16, 0, Some(0)));
+        assert_eq!(layer.get(-1, -1), Some(3));
+        assert_eq!(layer.get(16, 0), Some(0));
+        assert_eq!(layer.get(0, 0), None);
+        assert_eq!(layer.bounds(), Some(((-16, -16), (31, 15))));
+        // Clearing the last tile of a chunk drops the chunk.
+        assert!(layer.set(-1, -1, None));
+        assert_eq!(layer.chunks.len(), 1);
+    }
+
+    #[test]
+    fn fill_stops_at_other_tiles() {
+        let mut layer = TileLayer::new("Ground");
+        // A 3x3 room of floor `1` walled in by `2`, with one floor tile outside.
+        for y in 0..5 {
+            for x in 0..5 {
+                let wall = x == 0 || y == 0 || x == 4 || y == 4;
+                layer.set(x, y, Some(if wall { 2 } else { 1 }));
+            }
+        }
+        layer.set(6, 2, Some(1));
+        let bounds = ((-10, -10), (10, 10));
+        assert_eq!(layer.fill(2, 2, bounds, |_, _| Some(5)), 9);
+        assert_eq!(layer.get(1, 1), Some(5));
+        assert_eq!(layer.get(0, 0), Some(2));
+        assert_eq!(layer.get(6, 2), Some(1));
+        // Filling with the same tile changes nothing and ends.
+        assert_eq!(layer.fill(2, 2, bounds, |_, _| Some(5)), 0);
+    }
+
+    #[test]
+    fn fill_of_empty_cells_stays_within_bounds() {
+        let mut layer = TileLayer::new("Ground");
+        let changed = layer.fill(0, 0, ((-2, -1), (1, 1)), |x, y| {
+            Some((x + y).unsigned_abs())
+        });
+        assert_eq!(changed, 12);
+        assert_eq!(layer.get(-2, 1), Some(1));
+        assert_eq!(layer.get(2, 0), None);
+        assert_eq!(layer.tiles().count(), 12);
+    }
+
+    #[test]
+    fn layers_are_saved_as_rows() {
+        let mut layer = TileLayer::new("Ground");
+        layer.set(0, 0, Some(0));
+        layer.set(1, 0, Some(4));
+        layer.set(-1, -1, Some(9));
+        let json = serde_json::to_value(&layer).unwrap();
+        let chunks = json["chunks"].as_array().unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            (&chunks[0]["x"], &chunks[0]["y"]),
+            (&(-1).into(), &(-1).into())
+        );
+        let rows = chunks[1]["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), CHUNK_SIZE as usize);
+        assert!(rows[0].as_str().unwrap().starts_with("1 5 0 "));
+        assert_eq!(rows[1].as_str().unwrap(), vec!["0"; 16].join(" "));
+
+        let loaded: TileLayer = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, layer);
+    }
+
+    #[test]
+    fn invalid_rows_are_errors() {
+        let load = |rows: Vec<String>| {
+            let json = serde_json::json!({
+                "name": "Ground",
+                "chunks": [{ "x": 2, "y": 3, "rows": rows }],
+            });
+            serde_json::from_value::<TileLayer>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        let error = load(vec!["1 x 2".to_owned()]);
+        assert!(
+            error.contains("invalid tile `x` in chunk (2, 3)"),
+            "{error}"
+        );
+        let error = load(vec!["1".to_owned(); CHUNK_CELLS + 1]);
+        assert!(error.contains("more than 256 cells"), "{error}");
+    }
+}
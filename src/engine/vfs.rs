@@ -0,0 +1,484 @@
+//! Files of projects and games behind mount points, wherever they are stored: folders on disk,
+//! memory, the browser's IndexedDB, the web server or packs.
+//!
+//! Paths are written like asset references, the mount point followed by the path inside the
+//! mount with `/` between the parts, like `project/sprites/player.png`. Access is async since the
+//! browser storage is.
+
+use futures::future::{self, LocalBoxFuture};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io};
+
+use super::pack::Pack;
+pub use super::watch::{Changes, Watch};
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db;
+#[cfg(target_arch = "wasm32")]
+pub use indexed_db::IndexedDb;
+
+#[derive(Debug)]
+pub enum VfsError {
+    NotFound(String),
+    ReadOnly(String),
+    /// The path doesn't start with a mount point.
+    NoMount(String),
+    /// The storage failed, with what it said.
+    Io(String),
+}
+
+impl fmt::Display for VfsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VfsError::NotFound(path) => write!(f, "{path} not found"),
+            VfsError::ReadOnly(path) => write!(f, "{path} is read-only"),
+            VfsError::NoMount(path) => write!(f, "nothing is mounted at {path}"),
+            VfsError::Io(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for VfsError {}
+
+pub type VfsResult<T> = Result<T, VfsError>;
+pub type VfsFuture<'a, T> = LocalBoxFuture<'a, VfsResult<T>>;
+
+/// A storage of files, mounted into a [`Vfs`]. Paths are relative to the storage.
+pub trait FileSystem {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>>;
+
+    /// Creates or replaces a file.
+    fn write<'a>(&'a self, path: &'a str, data: Vec<u8>) -> VfsFuture<'a, ()>;
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()>;
+
+    /// The paths of all files, sorted.
+    fn list(&self) -> VfsFuture<'_, Vec<String>>;
+
+    /// Starts reporting files changed by other programs. Storages only the app writes to have
+    /// nothing to do.
+    fn watch(&self, _changes: Changes) -> VfsResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Mounts {
+    mounts: BTreeMap<String, Rc<dyn FileSystem>>,
+    watchers: Vec<(String, Changes)>,
+}
+
+/// The mounted storages. Clones share the mounts, so file tasks can take one along.
+#[derive(Clone, Default)]
+pub struct Vfs {
+    inner: Rc<RefCell<Mounts>>,
+}
+
+impl Vfs {
+    /// Mounts `fs` at `point`, in place of what was mounted there.
+    pub fn mount(&self, point: &str, fs: impl FileSystem + 'static) {
+        let mut inner = self.inner.borrow_mut();
+        inner.watchers.retain(|(p, _)| p != point);
+        inner.mounts.insert(point.to_owned(), Rc::new(fs));
+    }
+
+    pub fn unmount(&self, point: &str) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        inner.watchers.retain(|(p, _)| p != point);
+        inner.mounts.remove(point).is_some()
+    }
+
+    pub fn is_mounted(&self, point: &str) -> bool {
+        self.inner.borrow().mounts.contains_key(point)
+    }
+
+    /// The storage a path is in and the path inside it.
+    fn resolve(&self, path: &str) -> VfsResult<(Rc<dyn FileSystem>, String)> {
+        let (point, rest) = path.split_once('/').unwrap_or((path, ""));
+        let fs = self.inner.borrow().mounts.get(point).cloned();
+        let fs = fs.ok_or_else(|| VfsError::NoMount(path.to_owned()))?;
+        Ok((fs, rest.to_owned()))
+    }
+
+    /// Tells the watchers of the mount point about a change made through the VFS, so they
+    /// don't report it as a change by another program.
+    fn changed(&self, path: &str) {
+        let Some((point, rest)) = path.split_once('/') else {
+            return;
+        };
+        self.inner
+            .borrow_mut()
+            .watchers
+            .retain(|(p, changes)| p != point || changes.own(rest.to_owned()));
+    }
+
+    pub async fn read(&self, path: &str) -> VfsResult<Vec<u8>> {
+        let (fs, rest) = self.resolve(path)?;
+        fs.read(&rest).await
+    }
+
+    /// Creates or replaces a file.
+    pub async fn write(&self, path: &str, data: Vec<u8>) -> VfsResult<()> {
+        let (fs, rest) = self.resolve(path)?;
+        fs.write(&rest, data).await?;
+        self.changed(path);
+        Ok(())
+    }
+
+    pub async fn remove(&self, path: &str) -> VfsResult<()> {
+        let (fs, rest) = self.resolve(path)?;
+        fs.remove(&rest).await?;
+        self.changed(path);
+        Ok(())
+    }
+
+    /// The full paths of the files under a mount point, sorted.
+    pub async fn list(&self, point: &str) -> VfsResult<Vec<String>> {
+        let (fs, _) = self.resolve(point)?;
+        let files = fs.list().await?;
+        Ok(files.into_iter().map(|p| format!("{point}/{p}")).collect())
+    }
+
+    /// Starts watching the files under a mount point. `wake` is called, possibly from another
+    /// thread, when something changed.
+    pub fn watch(&self, point: &str, wake: impl Fn() + Send + Sync + 'static) -> VfsResult<Watch> {
+        let (fs, _) = self.resolve(point)?;
+        let (watch, changes) = Watch::new(point, wake);
+        fs.watch(changes.clone())?;
+        self.inner
+            .borrow_mut()
+            .watchers
+            .push((point.to_owned(), changes));
+        Ok(watch)
+    }
+}
+
+/// Runs a file task: on the web alongside the app, natively to the end right away.
+pub fn spawn(task: impl Future<Output = ()> + 'static) {
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+    #[cfg(not(target_arch = "wasm32"))]
+    futures::executor::block_on(task);
+}
+
+/// Files kept in memory, gone when the app closes.
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: RefCell<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryFs {
+    pub fn new(files: impl IntoIterator<Item = (String, Vec<u8>)>) -> Self {
+        Self {
+            files: RefCell::new(files.into_iter().collect()),
+        }
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>> {
+        let data = self.files.borrow().get(path).cloned();
+        Box::pin(future::ready(
+            data.ok_or_else(|| VfsError::NotFound(path.to_owned())),
+        ))
+    }
+
+    fn write<'a>(&'a self, path: &'a str, data: Vec<u8>) -> VfsFuture<'a, ()> {
+        self.files.borrow_mut().insert(path.to_owned(), data);
+        Box::pin(future::ready(Ok(())))
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()> {
+        let removed = self.files.borrow_mut().remove(path);
+        Box::pin(future::ready(
+            removed
+                .map(|_| ())
+                .ok_or_else(|| VfsError::NotFound(path.to_owned())),
+        ))
+    }
+
+    fn list(&self) -> VfsFuture<'_, Vec<String>> {
+        Box::pin(future::ready(Ok(self
+            .files
+            .borrow()
+            .keys()
+            .cloned()
+            .collect())))
+    }
+}
+
+/// Files on the server of the page, read-only. Servers don't list their files, so
+/// [`FileSystem::list`] fails.
+#[cfg(target_arch = "wasm32")]
+pub struct HttpFs {
+    /// URL of the folder, relative to the page. Empty for the folder of the page.
+    base: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl HttpFs {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self { base: base.into() }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl FileSystem for HttpFs {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>> {
+        let url = match self.base.as_str() {
+            "" => path.to_owned(),
+            base => format!("{}/{path}", base.trim_end_matches('/')),
+        };
+        Box::pin(async move {
+            let response = ehttp::fetch_async(ehttp::Request::get(&url))
+                .await
+                .map_err(|e| VfsError::Io(format!("{url}: {e}")))?;
+            match response.status {
+                404 => Err(VfsError::NotFound(path.to_owned())),
+                _ if response.ok => Ok(response.bytes),
+                _ => Err(VfsError::Io(format!("{url}: {}", response.status_text))),
+            }
+        })
+    }
+
+    fn write<'a>(&'a self, path: &'a str, _data: Vec<u8>) -> VfsFuture<'a, ()> {
+        Box::pin(future::ready(Err(VfsError::ReadOnly(path.to_owned()))))
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()> {
+        Box::pin(future::ready(Err(VfsError::ReadOnly(path.to_owned()))))
+    }
+
+    fn list(&self) -> VfsFuture<'_, Vec<String>> {
+        let e = VfsError::Io(format!(
+            "the server doesn't list the files of {}",
+            self.base
+        ));
+        Box::pin(future::ready(Err(e)))
+    }
+}
+
+/// A folder on disk. Hidden files and folders, like those of version control, are left out.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct NativeDir {
+    root: PathBuf,
+    watchers: RefCell<Vec<notify::RecommendedWatcher>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NativeDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            watchers: RefCell::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of a file relative to `root`, if it is in there and not hidden.
+    fn relative(root: &Path, path: &Path) -> Option<String> {
+        let parts: Vec<_> = path
+            .strip_prefix(root)
+            .ok()?
+            .iter()
+            .map(|p| p.to_string_lossy())
+            .collect();
+        let hidden = parts.iter().any(|p| p.starts_with('.'));
+        (!parts.is_empty() && !hidden).then(|| parts.join("/"))
+    }
+
+    fn files(&self) -> io::Result<Vec<String>> {
+        let mut files = vec![];
+        let mut folders = vec![self.root.clone()];
+        while let Some(folder) = folders.pop() {
+            for entry in fs::read_dir(&folder)? {
+                let path = entry?.path();
+                let Some(name) = Self::relative(&self.root, &path) else {
+                    continue;
+                };
+                if path.is_dir() {
+                    folders.push(path);
+                } else {
+                    files.push(name);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn io_error(path: &str, e: io::Error) -> VfsError {
+    match e.kind() {
+        io::ErrorKind::NotFound => VfsError::NotFound(path.to_owned()),
+        _ => VfsError::Io(format!("{path}: {e}")),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileSystem for NativeDir {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>> {
+        let data = fs::read(self.root.join(path)).map_err(|e| io_error(path, e));
+        Box::pin(future::ready(data))
+    }
+
+    fn write<'a>(&'a self, path: &'a str, data: Vec<u8>) -> VfsFuture<'a, ()> {
+        let file = self.root.join(path);
+        let result = file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&file, data))
+            .map_err(|e| io_error(path, e));
+        Box::pin(future::ready(result))
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()> {
+        let result = fs::remove_file(self.root.join(path)).map_err(|e| io_error(path, e));
+        Box::pin(future::ready(result))
+    }
+
+    fn list(&self) -> VfsFuture<'_, Vec<String>> {
+        let root = self.root.display().to_string();
+        Box::pin(future::ready(self.files().map_err(|e| io_error(&root, e))))
+    }
+
+    fn watch(&self, changes: Changes) -> VfsResult<()> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        // The watcher reports absolute paths.
+        let root = self
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| self.root.clone());
+        let folder = root.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let event: notify::Event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("File watcher error: {e}");
+                    return;
+                }
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                return;
+            }
+            for path in event.paths {
+                if let Some(name) = Self::relative(&folder, &path) {
+                    changes.send(name);
+                }
+            }
+        })
+        .map_err(|e| VfsError::Io(e.to_string()))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| VfsError::Io(format!("{}: {e}", root.display())))?;
+        self.watchers.borrow_mut().push(watcher);
+        Ok(())
+    }
+}
+
+/// The entries of a pack, read-only. Files hold the cooked data, not what was imported.
+pub struct PackFs {
+    pack: Pack,
+}
+
+impl PackFs {
+    pub fn new(pack: Pack) -> Self {
+        Self { pack }
+    }
+}
+
+impl FileSystem for PackFs {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>> {
+        let entry = self.pack.entries().iter().find(|e| e.name == path);
+        let data = match entry {
+            Some(entry) => self
+                .pack
+                .inflate(entry)
+                .map_err(|e| VfsError::Io(e.to_string())),
+            None => Err(VfsError::NotFound(path.to_owned())),
+        };
+        Box::pin(future::ready(data))
+    }
+
+    fn write<'a>(&'a self, path: &'a str, _data: Vec<u8>) -> VfsFuture<'a, ()> {
+        Box::pin(future::ready(Err(VfsError::ReadOnly(path.to_owned()))))
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()> {
+        Box::pin(future::ready(Err(VfsError::ReadOnly(path.to_owned()))))
+    }
+
+    fn list(&self) -> VfsFuture<'_, Vec<String>> {
+        let mut names: Vec<String> = self.pack.entries().iter().map(|e| e.name.clone()).collect();
+        names.sort();
+        Box::pin(future::ready(Ok(names)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::assets::Assets;
+    use crate::engine::pack;
+    use crate::engine::project::Project;
+    use crate::engine::scene::Scene;
+    use futures::executor::block_on;
+
+    #[test]
+    fn paths_go_to_their_mount() {
+        let vfs = Vfs::default();
+        vfs.mount("a", MemoryFs::new([("x.txt".to_owned(), b"a".to_vec())]));
+        vfs.mount("b", MemoryFs::default());
+        block_on(async {
+            vfs.write("b/sub/y.txt", b"b".to_vec()).await.unwrap();
+            assert_eq!(vfs.read("a/x.txt").await.unwrap(), b"a");
+            assert_eq!(vfs.list("b").await.unwrap(), ["b/sub/y.txt"]);
+            vfs.remove("b/sub/y.txt").await.unwrap();
+            assert!(matches!(
+                vfs.read("b/sub/y.txt").await,
+                Err(VfsError::NotFound(_))
+            ));
+            assert!(matches!(
+                vfs.read("c/x.txt").await,
+                Err(VfsError::NoMount(_))
+            ));
+        });
+        assert!(vfs.unmount("a"));
+        assert!(!vfs.is_mounted("a"));
+    }
+
+    #[test]
+    fn packs_mount_read_only() {
+        let scenes = [("main.scene.json".to_owned(), Scene::default())];
+        let (data, _) = pack::cook(&Project::default(), &scenes, &Assets::default()).unwrap();
+        let vfs = Vfs::default();
+        vfs.mount("game", PackFs::new(Pack::read(data).unwrap()));
+        block_on(async {
+            assert_eq!(
+                vfs.list("game").await.unwrap(),
+                ["game/main.scene.json", "game/project"]
+            );
+            let scene: Scene =
+                pack::decode(&vfs.read("game/main.scene.json").await.unwrap()).unwrap();
+            assert_eq!(scene, Scene::default());
+            let written = vfs.write("game/project", vec![]).await;
+            assert!(matches!(written, Err(VfsError::ReadOnly(_))));
+        });
+    }
+}
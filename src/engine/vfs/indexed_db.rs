@@ -0,0 +1,158 @@
+//! Files kept by the browser in IndexedDB, which survive reloading the page.
+
+use js_sys::{Array, Uint8Array};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+use super::{FileSystem, VfsError, VfsFuture, VfsResult};
+
+/// The object store of the files, keyed by their path.
+const STORE: &str = "files";
+const DB_VERSION: u32 = 1;
+
+/// The files of a database whose paths start with a prefix. Databases can hold the files of
+/// several mounts under different prefixes.
+#[derive(Clone, Debug)]
+pub struct IndexedDb {
+    db: IdbDatabase,
+    prefix: String,
+}
+
+fn js_error(e: JsValue) -> VfsError {
+    let message = e
+        .dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .or_else(|| e.as_string())
+        .unwrap_or_else(|| format!("{e:?}"));
+    VfsError::Io(message)
+}
+
+/// Waits for a request to finish, and returns its result.
+async fn finished(request: &IdbRequest) -> VfsResult<JsValue> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+    let done = |success: bool| {
+        let tx = tx.clone();
+        Closure::once(move |_: web_sys::Event| {
+            if let Some(tx) = tx.borrow_mut().take() {
+                let _ = tx.send(success);
+            }
+        })
+    };
+    let (on_success, on_error) = (done(true), done(false));
+    request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+    request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    let success = rx.await.unwrap_or(false);
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    if success {
+        request.result().map_err(js_error)
+    } else {
+        let error = request.error().ok().flatten();
+        Err(VfsError::Io(error.map_or_else(
+            || "IndexedDB request failed".to_owned(),
+            |e| e.message(),
+        )))
+    }
+}
+
+impl IndexedDb {
+    /// Opens the database, creating it if the browser has none of this name.
+    pub async fn open(database: &str, prefix: &str) -> VfsResult<Self> {
+        let factory = web_sys::window()
+            .and_then(|w| w.indexed_db().ok().flatten())
+            .ok_or_else(|| VfsError::Io("this browser has no IndexedDB".to_owned()))?;
+        let request: IdbOpenDbRequest = factory
+            .open_with_u32(database, DB_VERSION)
+            .map_err(js_error)?;
+        let upgrade = Closure::once(move |event: web_sys::Event| {
+            let db = event
+                .target()
+                .and_then(|t| t.dyn_into::<IdbOpenDbRequest>().ok())
+                .and_then(|r| r.result().ok())
+                .and_then(|db| db.dyn_into::<IdbDatabase>().ok());
+            if let Some(db) = db {
+                if let Err(e) = db.create_object_store(STORE) {
+                    log::error!("Can't create the IndexedDB store: {}", js_error(e));
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+        let db = finished(&request).await;
+        request.set_onupgradeneeded(None);
+        Ok(Self {
+            db: db?.unchecked_into(),
+            prefix: prefix.to_owned(),
+        })
+    }
+
+    /// The same database, with the files under another prefix.
+    pub fn with_prefix(&self, prefix: &str) -> Self {
+        Self {
+            db: self.db.clone(),
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    fn store(&self, mode: IdbTransactionMode) -> VfsResult<IdbObjectStore> {
+        self.db
+            .transaction_with_str_and_mode(STORE, mode)
+            .and_then(|t| t.object_store(STORE))
+            .map_err(js_error)
+    }
+
+    fn key(&self, path: &str) -> JsValue {
+        JsValue::from_str(&format!("{}{path}", self.prefix))
+    }
+}
+
+impl FileSystem for IndexedDb {
+    fn read<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let store = self.store(IdbTransactionMode::Readonly)?;
+            let request = store.get(&self.key(path)).map_err(js_error)?;
+            let value = finished(&request).await?;
+            if value.is_undefined() {
+                return Err(VfsError::NotFound(path.to_owned()));
+            }
+            Ok(Uint8Array::new(&value).to_vec())
+        })
+    }
+
+    fn write<'a>(&'a self, path: &'a str, data: Vec<u8>) -> VfsFuture<'a, ()> {
+        Box::pin(async move {
+            let store = self.store(IdbTransactionMode::Readwrite)?;
+            let value = Uint8Array::from(data.as_slice());
+            let request = store
+                .put_with_key(&value, &self.key(path))
+                .map_err(js_error)?;
+            finished(&request).await.map(|_| ())
+        })
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> VfsFuture<'a, ()> {
+        Box::pin(async move {
+            let store = self.store(IdbTransactionMode::Readwrite)?;
+            let request = store.delete(&self.key(path)).map_err(js_error)?;
+            finished(&request).await.map(|_| ())
+        })
+    }
+
+    fn list(&self) -> VfsFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let store = self.store(IdbTransactionMode::Readonly)?;
+            let request = store.get_all_keys().map_err(js_error)?;
+            let keys: Array = finished(&request).await?.unchecked_into();
+            let mut files: Vec<String> = keys
+                .iter()
+                .filter_map(|key| key.as_string())
+                .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_owned))
+                .collect();
+            files.sort();
+            Ok(files)
+        })
+    }
+}
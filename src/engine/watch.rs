@@ -0,0 +1,112 @@
+//! Notices when files change, so they can be imported again.
+//!
+//! Storages report the files other programs changed through [`Changes`], like the folders on disk
+//! watched with `notify`. The [`super::vfs::Vfs`] reports the files it wrote itself, which are
+//! left out: saving the project must not import it again.
+
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc};
+use web_time::{Duration, Instant};
+
+/// Tools often write a file in several steps, changes are reported once they are quiet this long.
+pub const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+enum Change {
+    /// Changed by another program.
+    Other(String),
+    /// Written by the app, through the VFS.
+    Own(String),
+}
+
+/// Reports changed files by their path in the storage, and wakes the app.
+#[derive(Clone)]
+pub struct Changes {
+    tx: mpsc::Sender<Change>,
+    wake: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl Changes {
+    /// Returns false once nobody watches anymore.
+    pub fn send(&self, path: String) -> bool {
+        let sent = self.tx.send(Change::Other(path)).is_ok();
+        if sent {
+            (self.wake)();
+        }
+        sent
+    }
+
+    /// Tells the watch about a file the app wrote, so the storage reporting it is ignored.
+    pub(super) fn own(&self, path: String) -> bool {
+        self.tx.send(Change::Own(path)).is_ok()
+    }
+}
+
+/// Changed files of a mount point, reported once they settled.
+pub struct Watch {
+    point: String,
+    changes: mpsc::Receiver<Change>,
+    /// Changed files with the time of their last change.
+    changed: BTreeMap<String, Instant>,
+    /// Files the app wrote, with the time it wrote them.
+    written: BTreeMap<String, Instant>,
+}
+
+impl Watch {
+    /// Watches the files of the mount point `point`. `wake` is called, possibly from another
+    /// thread, when something changed.
+    pub(super) fn new(point: &str, wake: impl Fn() + Send + Sync + 'static) -> (Self, Changes) {
+        let (tx, rx) = mpsc::channel();
+        let watch = Self {
+            point: point.to_owned(),
+            changes: rx,
+            changed: BTreeMap::new(),
+            written: BTreeMap::new(),
+        };
+        let changes = Changes {
+            tx,
+            wake: Arc::new(wake),
+        };
+        (watch, changes)
+    }
+
+    /// Whether changes are waiting for their files to settle.
+    pub fn pending(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Returns the full paths of the files which changed and settled since the last call.
+    pub fn poll(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        let mut other = vec![];
+        for change in self.changes.try_iter() {
+            match change {
+                Change::Own(path) => {
+                    self.changed.remove(&path);
+                    self.written.insert(path, now);
+                }
+                Change::Other(path) => other.push(path),
+            }
+        }
+        // Storages report the writes of the app a little later, if at all.
+        self.written
+            .retain(|_, time| now.duration_since(*time) < SETTLE_TIME);
+        for path in other {
+            if !self.written.contains_key(&path) {
+                self.changed.insert(path, now);
+            }
+        }
+        let settled: Vec<String> = self
+            .changed
+            .iter()
+            .filter(|(_, time)| now.duration_since(**time) >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &settled {
+            self.changed.remove(path);
+        }
+        settled
+            .into_iter()
+            .map(|path| format!("{}/{path}", self.point))
+            .collect()
+    }
+}
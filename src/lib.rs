@@ -1,4 +1,7 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 pub mod app;
+pub mod engine;
+pub mod player;
 pub use app::App;
+pub use player::Player;
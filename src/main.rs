@@ -1,35 +1,120 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+use archaic_engine::engine::alloc::CountingAllocator;
 use simplelog::*;
 
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
-fn main() -> eframe::Result<()> {
+fn main() -> std::process::ExitCode {
+    use clap::Parser;
+    use std::process::ExitCode;
+
+    let cli = cli::Cli::parse();
     let (log_widget, log_writer) = archaic_engine::app::logwidget::new_logger();
     let mut loggers: Vec<Box<dyn SharedLogger>> = vec![WriteLogger::new(
-        LevelFilter::Debug,
+        cli.log_level,
         ConfigBuilder::new()
             .set_time_level(LevelFilter::Trace)
             .set_thread_level(LevelFilter::Trace)
             .build(),
         log_writer,
     )];
-    #[cfg(debug_assertions)]
-    loggers.push(TermLogger::new(
-        LevelFilter::Debug,
-        Config::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    ));
-    let _ = CombinedLogger::init(loggers).unwrap();
+    if cfg!(debug_assertions) || cli.is_headless() {
+        loggers.push(TermLogger::new(
+            cli.log_level,
+            Config::default(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        ));
+    }
+    if let Some(path) = &cli.log_file {
+        match std::fs::File::create(path) {
+            Ok(file) => loggers.push(WriteLogger::new(cli.log_level, Config::default(), file)),
+            Err(e) => eprintln!("Can't write the log to {}: {e}", path.display()),
+        }
+    }
+    CombinedLogger::init(loggers).unwrap();
+
+    let result = if cli.is_headless() {
+        cli.run()
+    } else {
+        run_editor(&cli, log_widget)
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Headless runs log to the terminal anyway.
+            if cli.is_headless() {
+                log::error!("{e}");
+            } else {
+                eprintln!("{e}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_editor(
+    cli: &cli::Cli,
+    log_widget: archaic_engine::app::logwidget::MyLogger,
+) -> Result<(), String> {
+    use archaic_engine::engine::project::ProjectDir;
+    use archaic_engine::engine::vfs::NativeDir;
+
+    // Files to open besides the project, by the name they are imported as.
+    let mut files = vec![];
+    let root = cli.project_root();
+    if let Some(root) = &root {
+        let dir = ProjectDir::read(root).map_err(|e| format!("{}: {e}", root.display()))?;
+        if let Some(name) = &cli.play {
+            let scene = dir
+                .find_scene(name)
+                .ok_or_else(|| format!("No scene '{name}' in {}.", root.display()))?;
+            files.push((dir.reference(scene), scene.to_owned()));
+        }
+    } else if let Some(path) = &cli.path {
+        if cli.play.is_some() {
+            return Err("--play needs a project folder".to_owned());
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        files.push((name.into_owned(), path.clone()));
+    }
+
     let mut native_options = eframe::NativeOptions::default();
-    native_options.viewport.maximized = Some(true);
+    match cli.window_size {
+        Some(size) => native_options.viewport.inner_size = Some(size),
+        None => native_options.viewport.maximized = Some(true),
+    }
     native_options.viewport.drag_and_drop = Some(true);
+    native_options.follow_system_theme = true;
+    let play = cli.play.is_some();
     eframe::run_native(
         "Archaic Engine",
         native_options,
-        Box::new(|cc| Box::new(archaic_engine::App::new(cc, log_widget))),
+        Box::new(move |cc| {
+            let mut app = archaic_engine::App::new(cc, log_widget);
+            if let Some(root) = root {
+                app.mount_project(&cc.egui_ctx, NativeDir::new(root));
+            }
+            for (name, path) in files {
+                if let Err(e) = app.open(name, path.clone()) {
+                    log::error!("Can't open {}: {e}", path.display());
+                }
+            }
+            if play {
+                app.play_on_start();
+            }
+            Box::new(app)
+        }),
     )
+    .map_err(|e| e.to_string())
 }
 
 // When compiling to web using trunk:
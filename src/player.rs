@@ -0,0 +1,190 @@
+//! Runs a cooked game without the editor: the scene fills the window and there are no panels.
+
+use egui::{Color32, Context, Key, Sense};
+use std::sync::mpsc;
+
+use crate::app::logwidget::MyLogger;
+use crate::app::style::EditorFonts;
+use crate::engine::assets::Assets;
+use crate::engine::audio::Audio;
+use crate::engine::input::{Gamepads, InputFrame};
+use crate::engine::pack::{self, EntryKind, Pack};
+use crate::engine::project::Project;
+use crate::engine::render::{self, Camera};
+use crate::engine::runtime::Runtime;
+use crate::engine::scene::Scene;
+use crate::engine::vfs::{self, PackFs, Vfs};
+
+/// Shows and hides the console, if the player has one.
+pub const CONSOLE_KEY: Key = Key::F1;
+
+/// Where the pack file is mounted from: its folder natively, the server of the page on the web.
+pub const FILES_MOUNT: &str = "files";
+
+/// Where the pack is mounted, the game reads its project, scenes and assets from there.
+pub const GAME_MOUNT: &str = "game";
+
+/// The project, the first scene and the assets of the game, or why they couldn't be loaded.
+type Loaded = Result<(Project, Scene, Assets), String>;
+
+enum State {
+    Loading(mpsc::Receiver<Loaded>),
+    Running {
+        runtime: Box<Runtime>,
+        assets: Assets,
+    },
+    Failed(String),
+}
+
+pub struct Player {
+    /// Keeps the pack mounted while the game runs.
+    _vfs: Vfs,
+    state: State,
+    audio: Audio,
+    gamepads: Gamepads,
+    camera: Camera,
+    /// Whether the pointer was over the game in the last frame, for the game input.
+    hovered: bool,
+    /// The log, shown over the game with [`CONSOLE_KEY`]. `None` in release builds of games.
+    console: Option<MyLogger>,
+    console_open: bool,
+}
+
+impl Player {
+    /// Plays the pack at `pack` in `vfs`, on the web once it was downloaded.
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        vfs: Vfs,
+        pack: String,
+        console: Option<MyLogger>,
+    ) -> Self {
+        // The console is the only text before the game installs its own fonts.
+        cc.egui_ctx.set_fonts(EditorFonts::default().definitions());
+        let (tx, rx) = mpsc::channel();
+        let ctx = cc.egui_ctx.clone();
+        let task_vfs = vfs.clone();
+        vfs::spawn(async move {
+            let _ = tx.send(load(&task_vfs, &pack, &ctx).await);
+            ctx.request_repaint();
+        });
+        Self {
+            _vfs: vfs,
+            state: State::Loading(rx),
+            audio: Audio::null(),
+            gamepads: Gamepads::new(),
+            camera: Camera::default(),
+            hovered: false,
+            console,
+            console_open: false,
+        }
+    }
+
+    /// Starts the first scene of the pack.
+    fn start(&mut self, ctx: &Context, loaded: Loaded) -> Result<State, String> {
+        let (project, scene, assets) = loaded?;
+        let mut fonts = EditorFonts::default().definitions();
+        project.fonts.install(&assets, &mut fonts);
+        ctx.set_fonts(fonts);
+        log::info!("Playing '{}', scene '{}'.", project.name, scene.name);
+        let runtime = Box::new(Runtime::new(scene, &project, self.audio.mixer.clone()));
+        Ok(State::Running { runtime, assets })
+    }
+
+    fn update_game(&mut self, ctx: &Context) {
+        let dt = ctx.input(|i| i.stable_dt);
+        // Browsers only play sound after the user did something.
+        if ctx.input(|i| i.pointer.any_pressed() || !i.keys_down.is_empty()) {
+            self.audio.start_device();
+        }
+        self.audio.update(dt);
+        let State::Running { runtime, assets } = &mut self.state else {
+            return;
+        };
+        let mut input = InputFrame::capture(ctx, !ctx.wants_keyboard_input(), self.hovered);
+        self.gamepads.poll(&mut input);
+        runtime.input.update(input);
+        // Long frames (e.g. a dragged window) must not make the game jump.
+        runtime.update(assets, dt.min(0.1));
+    }
+}
+
+/// Reads the pack file, mounts the pack at [`GAME_MOUNT`] and loads the game from there.
+async fn load(vfs: &Vfs, path: &str, ctx: &Context) -> Loaded {
+    let data = vfs.read(path).await.map_err(|e| e.to_string())?;
+    let pack = Pack::read(data).map_err(|e| format!("{path}: {e}"))?;
+    let entries = pack.entries().to_vec();
+    vfs.mount(GAME_MOUNT, PackFs::new(pack));
+
+    let mut project = None;
+    let mut scenes = vec![];
+    let mut assets = Assets::default();
+    for entry in &entries {
+        let data = vfs
+            .read(&format!("{GAME_MOUNT}/{}", entry.name))
+            .await
+            .map_err(|e| e.to_string())?;
+        let loaded = match entry.kind {
+            EntryKind::Project => pack::decode(&data).map(|p| project = Some(p)),
+            EntryKind::Scene => pack::decode(&data).map(|s: Scene| scenes.push(s)),
+            _ => pack::load_asset(ctx, &mut assets, entry, data),
+        };
+        loaded.map_err(|e| format!("{path}: {e}"))?;
+    }
+    let project = project.ok_or_else(|| format!("{path}: the pack has no project."))?;
+    let scene = scenes.into_iter().next().ok_or("The pack has no scenes.")?;
+    Ok((project, scene, assets))
+}
+
+impl eframe::App for Player {
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        if let State::Loading(pack) = &self.state {
+            let started = match pack.try_recv() {
+                Ok(loaded) => Some(self.start(ctx, loaded)),
+                Err(mpsc::TryRecvError::Empty) => None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    Some(Err("The pack could not be loaded.".to_owned()))
+                }
+            };
+            match started {
+                Some(Ok(state)) => self.state = state,
+                Some(Err(e)) => {
+                    log::error!("{e}");
+                    self.state = State::Failed(e);
+                }
+                None => {}
+            }
+        }
+        self.update_game(ctx);
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(Color32::BLACK))
+            .show(ctx, |ui| {
+                let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::hover());
+                self.hovered = response.hovered();
+                match &self.state {
+                    State::Running { runtime, assets } => {
+                        let painter = ui.painter_at(rect);
+                        render::draw_scene(&painter, &self.camera, rect, &runtime.scene, assets);
+                    }
+                    State::Loading(_) => {
+                        ui.put(rect, egui::Spinner::new().size(32.0));
+                    }
+                    State::Failed(e) => {
+                        let text = egui::RichText::new(e).color(Color32::LIGHT_RED);
+                        ui.put(rect, egui::Label::new(text));
+                    }
+                }
+            });
+
+        if let Some(console) = &mut self.console {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, CONSOLE_KEY)) {
+                self.console_open = !self.console_open;
+            }
+            egui::Window::new("Console")
+                .open(&mut self.console_open)
+                .default_size([480.0, 240.0])
+                .show(ctx, |ui| console.show_log(ui));
+        }
+        ctx.request_repaint();
+    }
+}